@@ -0,0 +1,4 @@
+-- Add migration script here
+ALTER TABLE products ADD COLUMN IF NOT EXISTS slug TEXT UNIQUE;
+
+ALTER TABLE product_variants ADD COLUMN IF NOT EXISTS sku TEXT UNIQUE;
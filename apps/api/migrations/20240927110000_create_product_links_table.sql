@@ -0,0 +1,13 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS product_links (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    kind TEXT NOT NULL CHECK (kind IN ('related', 'cross_sell', 'up_sell')),
+    position INTEGER NOT NULL DEFAULT 0,
+    product_id UUID NOT NULL,
+    linked_product_id UUID NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (product_id, linked_product_id, kind),
+    CHECK (product_id <> linked_product_id),
+    FOREIGN KEY (product_id) REFERENCES products (id) ON DELETE CASCADE,
+    FOREIGN KEY (linked_product_id) REFERENCES products (id) ON DELETE CASCADE
+)
@@ -0,0 +1,11 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS stocks (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    quantity INTEGER NOT NULL DEFAULT 0 CHECK (quantity >= 0),
+    variant_id UUID NOT NULL,
+    warehouse_id UUID NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (variant_id, warehouse_id),
+    FOREIGN KEY (variant_id) REFERENCES product_variants (id) ON DELETE CASCADE,
+    FOREIGN KEY (warehouse_id) REFERENCES warehouses (id) ON DELETE CASCADE
+)
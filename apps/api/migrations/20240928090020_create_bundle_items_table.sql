@@ -0,0 +1,10 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS bundle_items (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    bundle_id UUID NOT NULL,
+    variant_id UUID NOT NULL,
+    UNIQUE (bundle_id, variant_id),
+    FOREIGN KEY (bundle_id) REFERENCES bundles (id) ON DELETE CASCADE,
+    FOREIGN KEY (variant_id) REFERENCES product_variants (id) ON DELETE CASCADE
+)
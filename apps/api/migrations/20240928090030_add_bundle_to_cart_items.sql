@@ -0,0 +1,7 @@
+-- Add migration script here
+ALTER TABLE cart_items ALTER COLUMN variant_id DROP NOT NULL;
+
+ALTER TABLE cart_items ADD COLUMN IF NOT EXISTS bundle_id UUID REFERENCES bundles (id) ON DELETE CASCADE;
+
+-- a cart line is either a single variant or a whole bundle
+ALTER TABLE cart_items ADD CONSTRAINT cart_items_variant_or_bundle CHECK ((variant_id IS NULL) <> (bundle_id IS NULL));
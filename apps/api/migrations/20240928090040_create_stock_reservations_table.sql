@@ -0,0 +1,10 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS stock_reservations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    stock_id UUID NOT NULL,
+    cart_item_id UUID NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (stock_id) REFERENCES stocks (id) ON DELETE CASCADE,
+    FOREIGN KEY (cart_item_id) REFERENCES cart_items (id) ON DELETE CASCADE
+)
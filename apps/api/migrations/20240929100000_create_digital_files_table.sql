@@ -0,0 +1,10 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS digital_files (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    name TEXT NOT NULL,
+    key TEXT NOT NULL UNIQUE,
+    content_type TEXT NOT NULL,
+    variant_id UUID NOT NULL UNIQUE,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (variant_id) REFERENCES product_variants (id) ON DELETE CASCADE
+)
@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE users
+ADD COLUMN customer_group_id UUID REFERENCES customer_groups (id) ON DELETE SET NULL
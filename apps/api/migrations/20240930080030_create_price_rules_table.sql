@@ -0,0 +1,14 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS price_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    kind TEXT NOT NULL CHECK (kind IN ('fixed', 'percentage')),
+    value REAL NOT NULL CHECK (value >= 0),
+    min_quantity INTEGER NOT NULL DEFAULT 1 CHECK (min_quantity > 0),
+    price_list_id UUID NOT NULL,
+    variant_id UUID,
+    category_id UUID,
+    CHECK (num_nonnulls (variant_id, category_id) = 1),
+    FOREIGN KEY (price_list_id) REFERENCES price_lists (id) ON DELETE CASCADE,
+    FOREIGN KEY (variant_id) REFERENCES product_variants (id) ON DELETE CASCADE,
+    FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE CASCADE
+)
@@ -0,0 +1,18 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS sales (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('fixed', 'percentage')),
+    value REAL NOT NULL CHECK (value >= 0),
+    variant_id UUID,
+    category_id UUID,
+    starts_at TIMESTAMP NOT NULL,
+    ends_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    CHECK (num_nonnulls (variant_id, category_id) = 1),
+    CHECK (ends_at > starts_at),
+    FOREIGN KEY (variant_id) REFERENCES product_variants (id) ON DELETE CASCADE,
+    FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS sales_period_index ON sales (starts_at, ends_at)
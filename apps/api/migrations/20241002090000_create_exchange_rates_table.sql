@@ -0,0 +1,10 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS exchange_rates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    currency TEXT NOT NULL UNIQUE,
+    -- units of the currency one unit of the base currency buys
+    rate DOUBLE PRECISION NOT NULL CHECK (rate > 0),
+    decimals SMALLINT NOT NULL DEFAULT 2 CHECK (decimals BETWEEN 0 AND 4),
+    rounding TEXT NOT NULL DEFAULT 'nearest' CHECK (rounding IN ('nearest', 'up', 'down')),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+)
@@ -0,0 +1,19 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS orders (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    cart_id UUID,
+    user_id UUID,
+    -- what the customer is charged
+    currency TEXT NOT NULL,
+    total REAL NOT NULL,
+    -- the same order in the store's base currency
+    base_currency TEXT NOT NULL,
+    base_total REAL NOT NULL,
+    rate DOUBLE PRECISION NOT NULL CHECK (rate > 0),
+    paid_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (cart_id) REFERENCES carts (id) ON DELETE SET NULL,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS orders_cart_id_index ON orders (cart_id)
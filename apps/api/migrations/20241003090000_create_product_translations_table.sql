@@ -0,0 +1,11 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS product_translations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    locale TEXT NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT,
+    product_id UUID NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (product_id, locale),
+    FOREIGN KEY (product_id) REFERENCES products (id) ON DELETE CASCADE
+)
@@ -0,0 +1,11 @@
+-- Add migration script here
+-- variant options are recreated on every product update, so labels are matched by their text
+CREATE TABLE IF NOT EXISTS option_translations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    locale TEXT NOT NULL,
+    source TEXT NOT NULL,
+    name TEXT NOT NULL,
+    product_id UUID NOT NULL,
+    UNIQUE (product_id, locale, source),
+    FOREIGN KEY (product_id) REFERENCES products (id) ON DELETE CASCADE
+)
@@ -0,0 +1,10 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS category_translations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    locale TEXT NOT NULL,
+    name TEXT NOT NULL,
+    category_id UUID NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (category_id, locale),
+    FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE CASCADE
+)
@@ -0,0 +1,10 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS unit_translations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    locale TEXT NOT NULL,
+    name TEXT NOT NULL,
+    unit_id UUID NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (unit_id, locale),
+    FOREIGN KEY (unit_id) REFERENCES units (id) ON DELETE CASCADE
+)
@@ -0,0 +1,3 @@
+-- Add migration script here
+-- checkout holds expire, bundle lines keep theirs until the line goes away
+ALTER TABLE stock_reservations ADD COLUMN expires_at TIMESTAMP;
@@ -0,0 +1,5 @@
+-- Add migration script here
+-- alerted_at is set once stock falls below the threshold and cleared when it's back up
+ALTER TABLE stocks
+    ADD COLUMN reorder_threshold INTEGER CHECK (reorder_threshold >= 0),
+    ADD COLUMN alerted_at TIMESTAMP;
@@ -0,0 +1,6 @@
+-- Add migration script here
+ALTER TABLE sessions
+    ADD COLUMN expires_at TIMESTAMP NOT NULL DEFAULT NOW() + INTERVAL '30 days',
+    ADD COLUMN last_seen_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    ADD COLUMN user_agent TEXT,
+    ADD COLUMN ip TEXT;
@@ -0,0 +1,9 @@
+-- Add migration script here
+DELETE FROM magic_tokens;
+
+ALTER TABLE magic_tokens
+    DROP COLUMN token,
+    ADD COLUMN token_hash TEXT NOT NULL UNIQUE,
+    ADD COLUMN otp_hash TEXT NOT NULL,
+    ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT NOW();
@@ -0,0 +1,7 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS oauth_states (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    state_hash TEXT NOT NULL UNIQUE,
+    verifier TEXT NOT NULL,
+    expires_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE sessions ADD COLUMN second_factor_at TIMESTAMP;
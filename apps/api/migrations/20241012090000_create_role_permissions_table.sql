@@ -0,0 +1,24 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS role_permissions (
+    role_id UUID NOT NULL,
+    permission TEXT NOT NULL,
+    PRIMARY KEY (role_id, permission),
+    FOREIGN KEY (role_id) REFERENCES roles (id) ON DELETE CASCADE
+);
+
+-- admins keep everything they could do before roles had permissions
+INSERT INTO role_permissions (role_id, permission)
+SELECT roles.id, permissions.permission
+FROM roles
+CROSS JOIN UNNEST(ARRAY[
+    'roles.manage',
+    'users.manage',
+    'pricing.write',
+    'catalog.write',
+    'products.write',
+    'reviews.moderate',
+    'stock.manage',
+    'orders.manage',
+    'settings.manage'
+]) AS permissions (permission)
+WHERE roles.name = 'admin';
@@ -0,0 +1,3 @@
+-- Add migration script here
+-- bundle lines used to hold their stock with no expiry, their next checkout takes it again
+DELETE FROM stock_reservations WHERE expires_at IS NULL;
@@ -0,0 +1,3 @@
+-- Add migration script here
+-- secrets stored before this are sealed by the app on startup, only it holds the key
+ALTER TABLE totp_factors ADD COLUMN IF NOT EXISTS sealed BOOLEAN NOT NULL DEFAULT FALSE
@@ -0,0 +1,16 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS order_lines (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    order_id UUID NOT NULL,
+    -- one of the two, like the cart item the line was taken from
+    variant_id UUID,
+    bundle_id UUID,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    -- in the order's currency
+    unit_price REAL NOT NULL,
+    FOREIGN KEY (order_id) REFERENCES orders (id) ON DELETE CASCADE,
+    FOREIGN KEY (variant_id) REFERENCES product_variants (id) ON DELETE SET NULL,
+    FOREIGN KEY (bundle_id) REFERENCES bundles (id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS order_lines_order_id_index ON order_lines (order_id)
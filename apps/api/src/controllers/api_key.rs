@@ -0,0 +1,123 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::Utc;
+use log::error;
+use uuid::Uuid;
+
+use crate::{
+    extractors::session::CurrentUser,
+    models::api_key::CreatedApiKey,
+    services,
+    utils::api_key,
+    validations::{
+        api_key::{CreateApiKeySchema, StoreApiKeySchema},
+        ValidatedForm,
+    },
+    AppState,
+};
+
+pub async fn index(State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let api_keys = match services::api_key::all(&mut connection).await {
+        Ok(api_keys) => api_keys,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    Json(api_keys).into_response()
+}
+
+pub async fn store(
+    State(state): State<AppState>,
+    CurrentUser(auth): CurrentUser,
+    ValidatedForm(input): ValidatedForm<CreateApiKeySchema>,
+) -> impl IntoResponse {
+    // a key is limited to what its creator can do at any time, so it can't be used to escalate
+    if input
+        .scopes
+        .iter()
+        .any(|scope| !auth.permissions.contains(scope))
+    {
+        return (
+            StatusCode::FORBIDDEN,
+            Json("Scopes must be permissions you hold"),
+        )
+            .into_response();
+    }
+
+    if input
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= Utc::now().naive_utc())
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json("Expiry must be in the future"),
+        )
+            .into_response();
+    }
+
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let (prefix, key) = api_key::generate();
+
+    let api_key = services::api_key::insert(
+        &StoreApiKeySchema {
+            name: input.name,
+            prefix,
+            key_hash: api_key::hash(&key),
+            scopes: input.scopes,
+            user_id: auth.user.id,
+            expires_at: input.expires_at,
+        },
+        &mut connection,
+    )
+    .await;
+
+    let api_key = match api_key {
+        Ok(api_key) => api_key,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    (StatusCode::CREATED, Json(CreatedApiKey { api_key, key })).into_response()
+}
+
+pub async fn destroy(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::api_key::destroy(&id, &mut connection).await {
+        Ok(result) if result.rows_affected() == 0 => (StatusCode::NOT_FOUND).into_response(),
+        Ok(_) => (StatusCode::NO_CONTENT).into_response(),
+        Err(err) => {
+            error!("{err}");
+            (StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
@@ -1,26 +1,35 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, net::SocketAddr};
 
 use axum::{
-    extract::{Query, State},
-    http::{header, StatusCode},
-    response::IntoResponse,
-    Extension, Json,
+    extract::{ConnectInfo, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
 };
 use chrono::{Duration, Utc};
 use log::error;
 use resend_rs::types::CreateEmailBaseOptions;
 use serde::Serialize;
+use sqlx::{Acquire, PgConnection};
 use url::Url;
 use uuid::Uuid;
 
 use crate::{
-    models::{role::Role, session::PopulatedSession, user::User},
+    extractors::session::CurrentUser,
+    models::{role::Role, user::User},
     services,
-    utils::constants::{ROLES, SESSION_COOKIE_NAME},
+    utils::{
+        client,
+        constants::{
+            MAGIC_TOKEN_EXPIRY, OTP_ATTEMPTS, OTP_LENGTH, ROLES, SESSION_COOKIE_NAME,
+            SESSION_MAX_AGE,
+        },
+        env::Env,
+        signing,
+    },
     validations::{
         auth::{SignInSchema, StoreSessionSchema},
-        magic_tokens::StoreMagicTokenSchema,
-        user::StoreUserSchema,
+        magic_tokens::{StoreMagicTokenSchema, VerifyOtpSchema},
         ValidatedForm,
     },
     AppState,
@@ -38,73 +47,48 @@ pub async fn sign_in(
         }
     };
 
-    let user = match services::user::find_by_email(&input.email, &mut connection).await {
-        Ok(user) => user,
+    let user =
+        match services::user::find_or_insert(&input.email, ROLES.member, &mut connection).await {
+            Ok(user) => user,
+            Err(err) => {
+                error!("{err}");
+                return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+            }
+        };
+
+    let token = Uuid::new_v4();
+    let code = otp();
+
+    let mut tx = match connection.begin().await {
+        Ok(tx) => tx,
         Err(err) => {
             error!("{err}");
             return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
         }
     };
 
-    let user = match user {
-        Some(user) => user,
-        None => {
-            let role = match services::role::find_by_name(ROLES.member, &mut connection).await {
-                Ok(Some(role)) => role,
-                Ok(None) => {
-                    error!("{} role not found", ROLES.member);
-                    return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
-                }
-                Err(err) => {
-                    error!("{err}");
-                    return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
-                }
-            };
-
-            let new_user = services::user::insert(
-                &StoreUserSchema {
-                    name: None,
-                    role_id: role.id,
-                    email: input.email.clone(),
-                },
-                &mut connection,
-            )
-            .await;
-
-            match new_user {
-                Ok(user) => user,
-                Err(err) => {
-                    error!("{err}");
-                    return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
-                }
-            }
-        }
-    };
-
-    let token = Uuid::new_v4();
-
-    let token = services::magic_tokens::insert(
+    let inserted = services::magic_tokens::insert(
         &StoreMagicTokenSchema {
-            token,
+            token_hash: token_hash(&token),
+            otp_hash: otp_hash(&state.env.app_secret, &code),
             user_id: user.user.id,
-            expires_at: Utc::now().naive_utc() + Duration::hours(1),
+            expires_at: Utc::now().naive_utc() + Duration::seconds(MAGIC_TOKEN_EXPIRY),
         },
-        &mut connection,
+        &mut tx,
     )
     .await;
 
-    let token = match token {
-        Ok(token) => token,
-        Err(err) => {
-            error!("{err}");
-            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
-        }
-    };
+    if let Err(err) = inserted {
+        error!("{err}");
+        let _ = tx.rollback().await;
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
 
     let mut url = match Url::parse(&state.env.client_url) {
         Ok(url) => url,
         Err(err) => {
             error!("{err}");
+            let _ = tx.rollback().await;
             return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
         }
     };
@@ -114,7 +98,7 @@ pub async fn sign_in(
 
     // set the token
     url.query_pairs_mut()
-        .append_pair("token", &token.token.token.to_string());
+        .append_pair("token", &token.to_string());
 
     // prepare the url
     let url = url.to_string();
@@ -124,9 +108,19 @@ pub async fn sign_in(
         vec![&input.email],
         "Authentication",
     )
-    .with_html(&format!(r#"<a href="{}" >Sign In</a>"#, url));
+    .with_html(&format!(
+        r#"<a href="{}" >Sign In</a><p>Or enter this code: <strong>{}</strong></p>"#,
+        url, code
+    ));
 
+    // the token is only kept once the user can actually receive it
     if let Err(err) = state.mailer.mail(email).await {
+        error!("{err}");
+        let _ = tx.rollback().await;
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    if let Err(err) = tx.commit().await {
         error!("{err}");
         return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
     }
@@ -135,6 +129,8 @@ pub async fn sign_in(
 }
 
 pub async fn auth(
+    headers: HeaderMap,
+    peer: Option<ConnectInfo<SocketAddr>>,
     Query(params): Query<HashMap<String, String>>,
     State(state): State<AppState>,
 ) -> impl IntoResponse {
@@ -160,12 +156,17 @@ pub async fn auth(
         }
     };
 
-    let token = services::magic_tokens::find_by_token(&token, &mut connection).await;
+    let token = services::magic_tokens::consume(
+        &token_hash(&token),
+        &Utc::now().naive_utc(),
+        &mut connection,
+    )
+    .await;
 
     let token = match token {
         Ok(Some(token)) => token,
         Ok(None) => {
-            return (StatusCode::BAD_REQUEST, Json("Token not found")).into_response();
+            return (StatusCode::BAD_REQUEST, Json("Token not found or expired")).into_response();
         }
         Err(err) => {
             error!("{err}");
@@ -173,21 +174,80 @@ pub async fn auth(
         }
     };
 
-    if let Err(err) = services::magic_tokens::destroy(&token.token.id, &mut connection).await {
-        error!("{err}");
-        return (StatusCode::INTERNAL_SERVER_ERROR, Json("Token expired")).into_response();
+    start_session(&state.env, &token.user.id, &headers, peer, &mut connection).await
+}
+
+pub async fn verify_otp(
+    headers: HeaderMap,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<VerifyOtpSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
     };
 
-    if Utc::now().naive_utc() > token.token.created_at {
-        return (StatusCode::BAD_REQUEST, Json("Token expired")).into_response();
-    }
+    let attempt = services::magic_tokens::attempt_otp(
+        &input.email,
+        &Utc::now().naive_utc(),
+        OTP_ATTEMPTS,
+        &mut connection,
+    )
+    .await;
+
+    let id = match attempt {
+        Ok(Some(id)) => id,
+        Ok(None) => {
+            return (StatusCode::BAD_REQUEST, Json("Invalid or expired code")).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let token = services::magic_tokens::consume_otp(
+        &id,
+        &otp_hashes(&state.env, input.code.trim()),
+        &mut connection,
+    )
+    .await;
+
+    let token = match token {
+        Ok(Some(token)) => token,
+        Ok(None) => {
+            return (StatusCode::BAD_REQUEST, Json("Invalid or expired code")).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    start_session(&state.env, &token.user.id, &headers, peer, &mut connection).await
+}
 
+pub(crate) async fn start_session(
+    env: &Env,
+    user_id: &Uuid,
+    headers: &HeaderMap,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    connection: &mut PgConnection,
+) -> Response {
     let session = services::sessions::insert(
         StoreSessionSchema {
-            user_id: token.user.id,
+            user_id: *user_id,
             session: Uuid::new_v4(),
+            expires_at: Utc::now().naive_utc() + Duration::seconds(SESSION_MAX_AGE),
+            user_agent: client::user_agent(headers),
+            ip: client::trusted_ip(env, headers, peer.map(|ConnectInfo(peer)| peer))
+                .map(|ip| ip.to_string()),
         },
-        &mut connection,
+        connection,
     )
     .await;
 
@@ -202,18 +262,40 @@ pub async fn auth(
     (
         [(
             header::SET_COOKIE,
-            format!(
-                "{}={}; Path=/; HttpOnly; Secure; SameSite=Strict; Max-Age=2592000",
-                SESSION_COOKIE_NAME, session.session
-            ),
+            client::session_cookie(env, &session.session),
         )],
         Json(session.session),
     )
         .into_response()
 }
 
+// only hashes are stored so a leaked table can't be replayed, short codes are keyed on top
+// so they can't be brute forced offline either
+fn token_hash(token: &Uuid) -> String {
+    signing::digest(&format!("magic:{}", token))
+}
+
+fn otp_hash(secret: &str, code: &str) -> String {
+    signing::sign(secret, &format!("otp:{}", code))
+}
+
+// a code sent before the secret was rotated still matches one of these
+fn otp_hashes(env: &Env, code: &str) -> Vec<String> {
+    env.secrets().map(|secret| otp_hash(secret, code)).collect()
+}
+
+// a v4 uuid carries 122 random bits, the modulo bias on a short code is negligible
+fn otp() -> String {
+    let modulus = 10u128.pow(OTP_LENGTH);
+    format!(
+        "{:0width$}",
+        Uuid::new_v4().as_u128() % modulus,
+        width = OTP_LENGTH as usize
+    )
+}
+
 pub async fn sign_out(
-    Extension(auth): Extension<PopulatedSession>,
+    CurrentUser(auth): CurrentUser,
     State(state): State<AppState>,
 ) -> impl IntoResponse {
     let mut connection = match state.db.acquire().await {
@@ -231,10 +313,7 @@ pub async fn sign_out(
 
     ([(
         header::SET_COOKIE,
-        format!(
-            "{}=; Path=/; Expires=Thu, 01 Jan 1970 00:00:00 GMT",
-            SESSION_COOKIE_NAME
-        ),
+        client::clear_cookie(SESSION_COOKIE_NAME),
     )])
     .into_response()
 }
@@ -245,7 +324,7 @@ struct Profile {
     pub role: Role,
 }
 
-pub async fn profile(Extension(auth): Extension<PopulatedSession>) -> impl IntoResponse {
+pub async fn profile(CurrentUser(auth): CurrentUser) -> impl IntoResponse {
     Json(Profile {
         user: auth.user,
         role: auth.role,
@@ -0,0 +1,206 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use log::error;
+use sqlx::{Acquire, PgConnection};
+use uuid::Uuid;
+
+use crate::{
+    services,
+    validations::{bundle::StoreBundleSchema, ValidatedForm},
+    AppState,
+};
+
+// every component has to be an existing variant, listed once
+async fn validate_items(
+    input: &StoreBundleSchema,
+    connection: &mut PgConnection,
+) -> Result<Option<&'static str>, sqlx::Error> {
+    let mut variant_ids = input
+        .items
+        .iter()
+        .map(|item| item.variant_id)
+        .collect::<Vec<_>>();
+    variant_ids.sort();
+    variant_ids.dedup();
+
+    if variant_ids.len() != input.items.len() {
+        return Ok(Some("Variants can only be listed once"));
+    }
+
+    let existing = services::product::count_existing_variants(&variant_ids, connection).await?;
+
+    if existing != variant_ids.len() as i64 {
+        return Ok(Some("Variant doesn't exist"));
+    }
+
+    Ok(None)
+}
+
+pub async fn index(State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let bundles = match services::bundle::all(&mut connection).await {
+        Ok(bundles) => bundles,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    Json(bundles).into_response()
+}
+
+pub async fn show(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::bundle::find(&id, &mut connection).await {
+        Ok(Some(bundle)) => Json(bundle).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND).into_response(),
+        Err(err) => {
+            error!("{err}");
+            (StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
+
+pub async fn store(
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<StoreBundleSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match validate_items(&input, &mut connection).await {
+        Ok(None) => {}
+        Ok(Some(message)) => {
+            return (StatusCode::BAD_REQUEST, Json(message)).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let mut tx = match connection.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let bundle = match services::bundle::insert(&input, &mut tx).await {
+        Ok(bundle) => bundle,
+        Err(err) => {
+            error!("{err}");
+            if let Err(err) = tx.rollback().await {
+                error!("{err}");
+            }
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = tx.commit().await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    (StatusCode::CREATED, Json(bundle)).into_response()
+}
+
+pub async fn update(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<StoreBundleSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::bundle::find(&id, &mut connection).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match validate_items(&input, &mut connection).await {
+        Ok(None) => {}
+        Ok(Some(message)) => {
+            return (StatusCode::BAD_REQUEST, Json(message)).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let mut tx = match connection.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = services::bundle::update(&id, &input, &mut tx).await {
+        error!("{err}");
+        if let Err(err) = tx.rollback().await {
+            error!("{err}");
+        }
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    if let Err(err) = tx.commit().await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    (StatusCode::NO_CONTENT).into_response()
+}
+
+pub async fn destroy(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = services::bundle::destroy(&id, &mut connection).await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    (StatusCode::NO_CONTENT).into_response()
+}
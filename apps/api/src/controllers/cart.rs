@@ -1,36 +1,39 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::{header, HeaderMap, StatusCode},
     response::IntoResponse,
-    Extension, Json,
+    Json,
 };
-use biscotti::{Processor, ProcessorConfig, RequestCookies};
+use chrono::{Duration, Utc};
 use log::error;
+use sqlx::Acquire;
 use uuid::Uuid;
 
 use crate::{
-    models::session::PopulatedSession,
-    services,
-    validations::cart::{StoreCartItemSchema, StoreCartSchema},
+    extractors::{cart::CurrentCart, session::OptionalUser},
+    models::{cart::Checkout, download::CartShipping},
+    services::{self, cart::CartError},
+    utils::{client, constants::LINK_KINDS},
+    validations::{
+        cart::{StoreCartItemSchema, StoreCartSchema},
+        currency::CurrencyParams,
+    },
     AppState,
 };
 
 pub async fn add_item(
-    headers: HeaderMap,
     State(state): State<AppState>,
-    Extension(auth): Extension<Option<PopulatedSession>>,
+    OptionalUser(auth): OptionalUser,
+    CurrentCart(cart): CurrentCart,
     Json(input): Json<StoreCartItemSchema>,
 ) -> impl IntoResponse {
-    let cart_id = headers
-        .get(header::COOKIE)
-        .and_then(|cookie_header| cookie_header.to_str().ok())
-        .and_then(|cookies| {
-            let processor: Processor = ProcessorConfig::default().into();
-            RequestCookies::parse_header(cookies, &processor).ok()
-        })
-        .and_then(|cookies| cookies.get("cart_id"))
-        .and_then(|cookie| Some(cookie.value().to_string()))
-        .and_then(|cart_id| Uuid::parse_str(&cart_id).ok());
+    if input.variant_id.is_some() == input.bundle_id.is_some() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json("Either a variant or a bundle is required"),
+        )
+            .into_response();
+    }
 
     let mut connection = match state.db.acquire().await {
         Ok(connection) => connection,
@@ -40,55 +43,49 @@ pub async fn add_item(
         }
     };
 
-    let cart = match cart_id {
-        Some(cart_id) => match services::cart::find(&cart_id, &mut connection).await {
-            Ok(cart) => cart,
-            Err(err) => {
-                error!("{err}");
-                return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
-            }
-        },
-        None => None,
-    };
-
-    let cart = match cart {
-        Some(cart) => cart,
+    let cart_id = match cart {
+        Some(cart) => cart.cart.id,
         None => {
             let input = StoreCartSchema {
-                user_id: match auth {
-                    Some(auth) => Some(auth.user.id),
-                    None => None,
-                },
+                user_id: auth.map(|auth| auth.user.id),
             };
 
-            let cart = match services::cart::insert(&input, &mut connection).await {
-                Ok(cart) => cart,
+            match services::cart::insert(&input, &mut connection).await {
+                Ok(cart) => cart.cart.id,
                 Err(err) => {
                     error!("{err}");
                     return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
                 }
-            };
-
-            cart
+            }
         }
     };
 
-    if let Err(err) = services::cart::add_item(&cart.cart.id, &input, &mut connection).await {
-        error!("{err}");
-        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    let expires_at = Utc::now().naive_utc() + Duration::seconds(state.env.reservation_ttl);
+
+    match services::cart::add_item(&cart_id, &input, expires_at, &mut connection).await {
+        Ok(_) => {}
+        Err(CartError::OutOfStock) => {
+            return (StatusCode::CONFLICT, Json("Not enough stock")).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
     }
 
     ([(
         header::SET_COOKIE,
-        format!("{}={}", "cart_id", cart.cart.id.to_string()),
+        client::cart_cookie(&state.env, &cart_id),
     )])
     .into_response()
 }
 
 pub async fn list_items(
     headers: HeaderMap,
+    Query(params): Query<CurrencyParams>,
     State(state): State<AppState>,
-    Extension(auth): Extension<Option<PopulatedSession>>,
+    OptionalUser(auth): OptionalUser,
+    CurrentCart(cart): CurrentCart,
 ) -> impl IntoResponse {
     let mut connection = match state.db.acquire().await {
         Ok(connection) => connection,
@@ -98,58 +95,41 @@ pub async fn list_items(
         }
     };
 
-    let cart = match auth {
-        Some(auth) => match services::cart::find_by_user_id(&auth.user.id, &mut connection).await {
-            Ok(Some(cart)) => Some(cart),
-            Ok(None) => None,
+    let conversion =
+        match services::currency::conversion(params.preferred(&headers), &mut connection).await {
+            Ok(Some(conversion)) => conversion,
+            Ok(None) => {
+                return (StatusCode::BAD_REQUEST, Json("Currency isn't supported")).into_response();
+            }
             Err(err) => {
                 error!("{err}");
                 return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
             }
-        },
-        None => None,
-    };
+        };
 
-    let cart = match cart {
-        Some(cart) => Some(cart),
-        None => {
-            let cart_id = headers
-                .get(header::COOKIE)
-                .and_then(|cookie_header| cookie_header.to_str().ok())
-                .and_then(|cookies| {
-                    let processor: Processor = ProcessorConfig::default().into();
-                    RequestCookies::parse_header(cookies, &processor).ok()
-                })
-                .and_then(|cookies| cookies.get("cart_id"))
-                .and_then(|cookie| Some(cookie.value().to_string()))
-                .and_then(|cart_id| Uuid::parse_str(&cart_id).ok());
-
-            match cart_id {
-                Some(cart_id) => match services::cart::find(&cart_id, &mut connection).await {
-                    Ok(Some(cart)) => Some(cart),
-                    Ok(None) => None,
-                    Err(err) => {
-                        error!("{err}");
-                        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
-                    }
-                },
-                None => None,
-            }
-        }
-    };
+    let customer_group_id = auth.as_ref().and_then(|auth| auth.user.customer_group_id);
 
     let items = match cart {
         Some(cart) => cart.items,
         None => Vec::new(),
     };
 
-    (Json(items)).into_response()
+    let mut lines = match services::cart::lines(items, customer_group_id, &mut connection).await {
+        Ok(lines) => lines,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    services::currency::convert_lines(&mut lines, &conversion);
+
+    (Json(lines)).into_response()
 }
 
 pub async fn delete_item(
     Path(item_id): Path<Uuid>,
-    headers: HeaderMap,
-    Extension(auth): Extension<Option<PopulatedSession>>,
+    CurrentCart(cart): CurrentCart,
     State(state): State<AppState>,
 ) -> impl IntoResponse {
     let mut connection = match state.db.acquire().await {
@@ -160,46 +140,6 @@ pub async fn delete_item(
         }
     };
 
-    let cart = match auth {
-        Some(auth) => match services::cart::find_by_user_id(&auth.user.id, &mut connection).await {
-            Ok(Some(cart)) => Some(cart),
-            Ok(None) => None,
-            Err(err) => {
-                error!("{err}");
-                return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
-            }
-        },
-        None => None,
-    };
-
-    let cart = match cart {
-        Some(cart) => Some(cart),
-        None => {
-            let cart_id = headers
-                .get(header::COOKIE)
-                .and_then(|cookie_header| cookie_header.to_str().ok())
-                .and_then(|cookies| {
-                    let processor: Processor = ProcessorConfig::default().into();
-                    RequestCookies::parse_header(cookies, &processor).ok()
-                })
-                .and_then(|cookies| cookies.get("cart_id"))
-                .and_then(|cookie| Some(cookie.value().to_string()))
-                .and_then(|cart_id| Uuid::parse_str(&cart_id).ok());
-
-            match cart_id {
-                Some(cart_id) => match services::cart::find(&cart_id, &mut connection).await {
-                    Ok(Some(cart)) => Some(cart),
-                    Ok(None) => None,
-                    Err(err) => {
-                        error!("{err}");
-                        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
-                    }
-                },
-                None => None,
-            }
-        }
-    };
-
     let cart = match cart {
         Some(cart) => cart,
         None => {
@@ -217,10 +157,54 @@ pub async fn delete_item(
 
 pub async fn update_item(
     Path(item_id): Path<Uuid>,
-    headers: HeaderMap,
-    Extension(auth): Extension<Option<PopulatedSession>>,
+    CurrentCart(cart): CurrentCart,
     State(state): State<AppState>,
     Json(input): Json<StoreCartItemSchema>,
+) -> impl IntoResponse {
+    if input.variant_id.is_some() == input.bundle_id.is_some() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json("Either a variant or a bundle is required"),
+        )
+            .into_response();
+    }
+
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let cart = match cart {
+        Some(cart) => cart,
+        None => {
+            return ().into_response();
+        }
+    };
+
+    let expires_at = Utc::now().naive_utc() + Duration::seconds(state.env.reservation_ttl);
+
+    match services::cart::update_item(&cart.cart.id, &item_id, &input, expires_at, &mut connection)
+        .await
+    {
+        Ok(_) => {}
+        Err(CartError::OutOfStock) => {
+            return (StatusCode::CONFLICT, Json("Not enough stock")).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    }
+
+    ().into_response()
+}
+
+pub async fn cross_sells(
+    State(state): State<AppState>,
+    CurrentCart(cart): CurrentCart,
 ) -> impl IntoResponse {
     let mut connection = match state.db.acquire().await {
         Ok(connection) => connection,
@@ -230,59 +214,192 @@ pub async fn update_item(
         }
     };
 
-    let cart = match auth {
-        Some(auth) => match services::cart::find_by_user_id(&auth.user.id, &mut connection).await {
-            Ok(Some(cart)) => Some(cart),
-            Ok(None) => None,
+    let variant_ids = match cart {
+        Some(cart) => cart
+            .items
+            .into_iter()
+            .filter_map(|item| item.variant_id)
+            .collect::<Vec<_>>(),
+        None => Vec::new(),
+    };
+
+    let product_ids =
+        match services::product::product_ids_of_variants(&variant_ids, &mut connection).await {
+            Ok(product_ids) => product_ids,
             Err(err) => {
                 error!("{err}");
                 return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
             }
-        },
-        None => None,
+        };
+
+    let products =
+        match services::product_link::linked(&product_ids, LINK_KINDS.cross_sell, &mut connection)
+            .await
+        {
+            Ok(products) => products,
+            Err(err) => {
+                error!("{err}");
+                return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+            }
+        };
+
+    Json(products).into_response()
+}
+
+pub async fn shipping(
+    State(state): State<AppState>,
+    CurrentCart(cart): CurrentCart,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
     };
 
-    let cart = match cart {
-        Some(cart) => Some(cart),
-        None => {
-            let cart_id = headers
-                .get(header::COOKIE)
-                .and_then(|cookie_header| cookie_header.to_str().ok())
-                .and_then(|cookies| {
-                    let processor: Processor = ProcessorConfig::default().into();
-                    RequestCookies::parse_header(cookies, &processor).ok()
-                })
-                .and_then(|cookies| cookies.get("cart_id"))
-                .and_then(|cookie| Some(cookie.value().to_string()))
-                .and_then(|cart_id| Uuid::parse_str(&cart_id).ok());
-
-            match cart_id {
-                Some(cart_id) => match services::cart::find(&cart_id, &mut connection).await {
-                    Ok(Some(cart)) => Some(cart),
-                    Ok(None) => None,
-                    Err(err) => {
-                        error!("{err}");
-                        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
-                    }
-                },
-                None => None,
+    // digital only carts skip the shipping step
+    let requires_shipping = match cart {
+        Some(cart) => {
+            match services::download::requires_shipping(&cart.cart.id, &mut connection).await {
+                Ok(requires_shipping) => requires_shipping,
+                Err(err) => {
+                    error!("{err}");
+                    return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+                }
             }
         }
+        None => false,
+    };
+
+    Json(CartShipping { requires_shipping }).into_response()
+}
+
+pub async fn checkout(
+    headers: HeaderMap,
+    Query(params): Query<CurrencyParams>,
+    State(state): State<AppState>,
+    OptionalUser(auth): OptionalUser,
+    CurrentCart(cart): CurrentCart,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
     };
 
     let cart = match cart {
-        Some(cart) => cart,
-        None => {
-            return ().into_response();
+        Some(cart) if !cart.items.is_empty() => cart,
+        _ => {
+            return (StatusCode::BAD_REQUEST, Json("Cart is empty")).into_response();
+        }
+    };
+
+    let conversion =
+        match services::currency::conversion(params.preferred(&headers), &mut connection).await {
+            Ok(Some(conversion)) => conversion,
+            Ok(None) => {
+                return (StatusCode::BAD_REQUEST, Json("Currency isn't supported")).into_response();
+            }
+            Err(err) => {
+                error!("{err}");
+                return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+            }
+        };
+
+    let base_currency = match services::currency::base(&mut connection).await {
+        Ok(base_currency) => base_currency,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
         }
     };
 
-    if let Err(err) =
-        services::cart::update_item(&cart.cart.id, &item_id, &input, &mut connection).await
+    let customer_group_id = auth.and_then(|auth| auth.user.customer_group_id);
+    let expires_at = Utc::now().naive_utc() + Duration::seconds(state.env.reservation_ttl);
+
+    // the stock is only held once there is an order to hold it for
+    let mut tx = match connection.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::cart::checkout(&cart, expires_at, &mut tx).await {
+        Ok(_) => {}
+        Err(CartError::OutOfStock) => {
+            if let Err(err) = tx.rollback().await {
+                error!("{err}");
+            }
+
+            return (StatusCode::CONFLICT, Json("Not enough stock")).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+
+            if let Err(err) = tx.rollback().await {
+                error!("{err}");
+            }
+
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    }
+
+    let mut lines = match services::cart::lines(cart.items, customer_group_id, &mut tx).await {
+        Ok(lines) => lines,
+        Err(err) => {
+            error!("{err}");
+
+            if let Err(err) = tx.rollback().await {
+                error!("{err}");
+            }
+
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    // the order keeps what was charged and what that is worth in the base currency
+    let base_total = lines.iter().map(|line| line.total).sum::<f32>();
+    services::currency::convert_lines(&mut lines, &conversion);
+
+    let order = match services::order::place(
+        &cart.cart.id,
+        cart.cart.user_id,
+        &base_currency,
+        base_total,
+        &conversion,
+        &lines,
+        &mut tx,
+    )
+    .await
     {
+        Ok(order) => order,
+        Err(err) => {
+            error!("{err}");
+
+            if let Err(err) = tx.rollback().await {
+                error!("{err}");
+            }
+
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = tx.commit().await {
         error!("{err}");
         return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
     }
 
-    ().into_response()
+    Json(Checkout {
+        cart_id: cart.cart.id,
+        expires_at,
+        order_id: order.id,
+        currency: order.currency,
+        total: order.total,
+    })
+    .into_response()
 }
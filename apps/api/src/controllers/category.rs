@@ -1,15 +1,23 @@
-use crate::{services, validations::category::StoreCategorySchema, AppState};
+use crate::{
+    services,
+    validations::{category::StoreCategorySchema, translation::LocaleParams},
+    AppState,
+};
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     Json,
 };
 use log::error;
 use uuid::Uuid;
 
-pub async fn index(State(state): State<AppState>) -> impl IntoResponse {
+pub async fn index(
+    headers: HeaderMap,
+    Query(locale): Query<LocaleParams>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
     let mut connection = match state.db.acquire().await {
         Ok(connection) => connection,
         Err(err) => {
@@ -18,7 +26,7 @@ pub async fn index(State(state): State<AppState>) -> impl IntoResponse {
         }
     };
 
-    let categories = match services::category::all(&mut connection).await {
+    let mut categories = match services::category::all(&mut connection).await {
         Ok(categories) => categories,
         Err(err) => {
             error!("{err}");
@@ -26,10 +34,35 @@ pub async fn index(State(state): State<AppState>) -> impl IntoResponse {
         }
     };
 
+    let locales = match services::translation::candidates(
+        locale.preferred(&headers),
+        &mut connection,
+    )
+    .await
+    {
+        Ok(locales) => locales,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) =
+        services::translation::localize_categories(&mut categories, &locales, &mut connection).await
+    {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
     Json(categories).into_response()
 }
 
-pub async fn show(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl IntoResponse {
+pub async fn show(
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    Query(locale): Query<LocaleParams>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
     let mut connection = match state.db.acquire().await {
         Ok(connection) => connection,
         Err(err) => {
@@ -49,7 +82,29 @@ pub async fn show(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl I
         }
     };
 
-    Json(category).into_response()
+    let mut categories = vec![category];
+
+    let locales = match services::translation::candidates(
+        locale.preferred(&headers),
+        &mut connection,
+    )
+    .await
+    {
+        Ok(locales) => locales,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) =
+        services::translation::localize_categories(&mut categories, &locales, &mut connection).await
+    {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    Json(&categories[0]).into_response()
 }
 
 pub async fn store(
@@ -0,0 +1,170 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{Duration, Utc};
+use log::error;
+use sqlx::Acquire;
+use uuid::Uuid;
+
+use crate::{
+    services,
+    utils::constants::{DOWNLOAD_EXPIRY, DOWNLOAD_LIMIT},
+    AppState,
+};
+
+// stand-ins for the payment provider callbacks until payments exist
+pub async fn paid(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let mut tx = match connection.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    // a second callback for the same cart waits here and then finds it emptied
+    match services::stock::lock_checkout(&id, &mut tx).await {
+        Ok(true) => {}
+        Ok(false) => {
+            if let Err(err) = tx.rollback().await {
+                error!("{err}");
+            }
+
+            return (StatusCode::NOT_FOUND, Json("Cart doesn't exist")).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+
+            if let Err(err) = tx.rollback().await {
+                error!("{err}");
+            }
+
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let now = Utc::now().naive_utc();
+
+    // an expired checkout may have lost its stock to someone else
+    match services::stock::checkout_active(&id, &now, &mut tx).await {
+        Ok(true) => {}
+        Ok(false) => {
+            if let Err(err) = tx.rollback().await {
+                error!("{err}");
+            }
+
+            return (StatusCode::CONFLICT, Json("Checkout has expired")).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+
+            if let Err(err) = tx.rollback().await {
+                error!("{err}");
+            }
+
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let cart = match services::cart::find(&id, &mut tx).await {
+        Ok(cart) => cart,
+        Err(err) => {
+            error!("{err}");
+
+            if let Err(err) = tx.rollback().await {
+                error!("{err}");
+            }
+
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    // the buyer's digital files, guests have nobody to hand a download to
+    let buyer = cart.and_then(|cart| cart.cart.user_id.map(|user_id| (user_id, cart.items)));
+
+    if let Some((user_id, items)) = buyer {
+        let variant_ids = items
+            .iter()
+            .filter_map(|item| item.variant_id)
+            .collect::<Vec<_>>();
+        let expires_at = (Utc::now() + Duration::seconds(DOWNLOAD_EXPIRY)).naive_utc();
+
+        if let Err(err) =
+            services::download::grant(&user_id, &variant_ids, DOWNLOAD_LIMIT, expires_at, &mut tx)
+                .await
+        {
+            error!("{err}");
+
+            if let Err(err) = tx.rollback().await {
+                error!("{err}");
+            }
+
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    }
+
+    if let Err(err) = services::order::pay(&id, &now, &mut tx).await {
+        error!("{err}");
+
+        if let Err(err) = tx.rollback().await {
+            error!("{err}");
+        }
+
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    if let Err(err) = services::stock::deduct(&id, &now, &mut tx).await {
+        error!("{err}");
+
+        if let Err(err) = tx.rollback().await {
+            error!("{err}");
+        }
+
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    if let Err(err) = tx.commit().await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    if let Err(err) = services::stock::alert_low(
+        state.mailer.as_ref(),
+        &state.env.resend_domain,
+        &mut connection,
+    )
+    .await
+    {
+        error!("{err}");
+    }
+
+    ().into_response()
+}
+
+pub async fn failed(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = services::stock::release_checkout(&id, &mut connection).await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    ().into_response()
+}
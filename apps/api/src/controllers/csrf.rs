@@ -0,0 +1,27 @@
+use axum::{
+    http::{header, HeaderMap},
+    response::IntoResponse,
+    Json,
+};
+use rand::{rngs::OsRng, RngCore};
+
+use crate::utils::{client, constants::CSRF_COOKIE_NAME};
+
+// hands the token to the dashboard, which can't read the cookie itself
+pub async fn token(headers: HeaderMap) -> impl IntoResponse {
+    // reused while the cookie lives so other open tabs keep a valid token
+    let token = match client::cookie(&headers, CSRF_COOKIE_NAME) {
+        Some(token) if !token.is_empty() => token,
+        _ => {
+            let mut bytes = [0u8; 32];
+            OsRng.fill_bytes(&mut bytes);
+            hex::encode(bytes)
+        }
+    };
+
+    (
+        [(header::SET_COOKIE, client::csrf_cookie(&token))],
+        Json(token),
+    )
+        .into_response()
+}
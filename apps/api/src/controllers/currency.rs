@@ -0,0 +1,124 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use log::error;
+use uuid::Uuid;
+
+use crate::{
+    models::currency::Currencies,
+    services,
+    validations::{
+        currency::{SetBaseCurrencySchema, StoreExchangeRateSchema},
+        ValidatedForm,
+    },
+    AppState,
+};
+
+pub async fn index(State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let base = match services::currency::base(&mut connection).await {
+        Ok(base) => base,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let rates = match services::currency::rates(&mut connection).await {
+        Ok(rates) => rates,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    Json(Currencies { base, rates }).into_response()
+}
+
+pub async fn set_base(
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<SetBaseCurrencySchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = services::currency::set_base(&input.currency, &mut connection).await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    ().into_response()
+}
+
+pub async fn set_rate(
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<StoreExchangeRateSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::currency::base(&mut connection).await {
+        Ok(base) if base == input.currency => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json("The base currency doesn't need an exchange rate"),
+            )
+                .into_response();
+        }
+        Ok(_) => {}
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::currency::set_rate(&input, &mut connection).await {
+        Ok(rate) => Json(rate).into_response(),
+        Err(err) => {
+            error!("{err}");
+            (StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
+
+pub async fn destroy_rate(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::currency::destroy_rate(&id, &mut connection).await {
+        Ok(result) if result.rows_affected() == 0 => (StatusCode::NOT_FOUND).into_response(),
+        Ok(_) => ().into_response(),
+        Err(err) => {
+            error!("{err}");
+            (StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
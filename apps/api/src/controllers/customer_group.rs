@@ -0,0 +1,160 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use log::error;
+use uuid::Uuid;
+
+use crate::{
+    services,
+    validations::{
+        customer_group::{AssignCustomerGroupSchema, StoreCustomerGroupSchema},
+        ValidatedForm,
+    },
+    AppState,
+};
+
+pub async fn index(State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let customer_groups = match services::customer_group::all(&mut connection).await {
+        Ok(customer_groups) => customer_groups,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    Json(customer_groups).into_response()
+}
+
+pub async fn store(
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<StoreCustomerGroupSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::customer_group::find_by_name(&input.name, &mut connection).await {
+        Ok(None) => {}
+        Ok(Some(_)) => {
+            return (StatusCode::CONFLICT, Json("Customer group already exists")).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let customer_group = match services::customer_group::insert(&input, &mut connection).await {
+        Ok(customer_group) => customer_group,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    (StatusCode::CREATED, Json(customer_group)).into_response()
+}
+
+pub async fn update(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<StoreCustomerGroupSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::customer_group::find_by_name(&input.name, &mut connection).await {
+        Ok(Some(customer_group)) if customer_group.id != id => {
+            return (StatusCode::CONFLICT, Json("Customer group already exists")).into_response();
+        }
+        Ok(_) => {}
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::customer_group::update(&id, &input, &mut connection).await {
+        Ok(result) if result.rows_affected() == 0 => (StatusCode::NOT_FOUND).into_response(),
+        Ok(_) => (StatusCode::NO_CONTENT).into_response(),
+        Err(err) => {
+            error!("{err}");
+            (StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
+
+pub async fn destroy(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = services::customer_group::destroy(&id, &mut connection).await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    (StatusCode::NO_CONTENT).into_response()
+}
+
+pub async fn assign(
+    Path(user_id): Path<Uuid>,
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<AssignCustomerGroupSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Some(customer_group_id) = input.customer_group_id {
+        match services::customer_group::find(&customer_group_id, &mut connection).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return (StatusCode::NOT_FOUND, Json("Customer group doesn't exist"))
+                    .into_response();
+            }
+            Err(err) => {
+                error!("{err}");
+                return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+            }
+        };
+    }
+
+    match services::customer_group::assign(&user_id, input.customer_group_id, &mut connection).await
+    {
+        Ok(result) if result.rows_affected() == 0 => (StatusCode::NOT_FOUND).into_response(),
+        Ok(_) => (StatusCode::NO_CONTENT).into_response(),
+        Err(err) => {
+            error!("{err}");
+            (StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
@@ -0,0 +1,333 @@
+use axum::{
+    extract::{Multipart, Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use chrono::{Duration, Utc};
+use log::error;
+use sqlx::Acquire;
+use uuid::Uuid;
+
+use crate::{
+    extractors::session::CurrentUser,
+    models::download::{Download, DownloadLink},
+    services,
+    utils::{
+        constants::{DOWNLOAD_EXPIRY, DOWNLOAD_LIMIT},
+        signing,
+    },
+    validations::{
+        download::{DownloadParams, StoreDownloadsSchema},
+        ValidatedForm,
+    },
+    AppState,
+};
+
+fn signature_payload(id: &Uuid, expires: i64) -> String {
+    format!("{}:{}", id, expires)
+}
+
+fn signed_url(download: &Download, secret: &str) -> String {
+    let expires = download.expires_at.and_utc().timestamp();
+    let signature = signing::sign(secret, &signature_payload(&download.id, expires));
+
+    format!(
+        "/downloads/{}?expires={}&signature={}",
+        download.id, expires, signature
+    )
+}
+
+pub async fn show_file(
+    Path(variant_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::download::find_file(&variant_id, &mut connection).await {
+        Ok(Some(file)) => Json(file).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND).into_response(),
+        Err(err) => {
+            error!("{err}");
+            (StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
+
+pub async fn upload_file(
+    Path(variant_id): Path<Uuid>,
+    State(state): State<AppState>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::product::find_variant(&variant_id, &mut connection).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let file = match multipart.next_field().await {
+        Ok(Some(file)) => file,
+        Ok(None) => {
+            return (StatusCode::BAD_REQUEST, Json("File not found")).into_response();
+        }
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json("Invalid file")).into_response();
+        }
+    };
+
+    let name = match file.file_name().or(file.name()) {
+        Some(name) => name.to_string(),
+        None => {
+            return (StatusCode::BAD_REQUEST, Json("Couldn't find file name")).into_response();
+        }
+    };
+
+    let content_type = file
+        .content_type()
+        .unwrap_or("application/octet-stream")
+        .to_string();
+
+    let bytes = match file.bytes().await {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json("Couldn't decode")).into_response();
+        }
+    };
+
+    // same naming as images, the key is never exposed to buyers
+    let key = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' | '_' => c,
+            _ => '-',
+        })
+        .collect::<String>();
+
+    let file = match services::download::attach_file(
+        &variant_id,
+        &name,
+        &format!("{}-{}", Uuid::new_v4(), key),
+        &content_type,
+        &bytes,
+        state.private_storage.as_ref(),
+        &mut connection,
+    )
+    .await
+    {
+        Ok(file) => file,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    (StatusCode::CREATED, Json(file)).into_response()
+}
+
+pub async fn destroy_file(
+    Path(variant_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let file = match services::download::find_file(&variant_id, &mut connection).await {
+        Ok(Some(file)) => file,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) =
+        services::download::detach_file(&file, state.private_storage.as_ref(), &mut connection)
+            .await
+    {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    (StatusCode::NO_CONTENT).into_response()
+}
+
+// paid checkouts grant their own downloads, this covers purchases made elsewhere
+pub async fn grant(
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<StoreDownloadsSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::user::find(&input.user_id, &mut connection).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json("User doesn't exist")).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let expires_at = (Utc::now() + Duration::seconds(DOWNLOAD_EXPIRY)).naive_utc();
+
+    let downloads = match services::download::grant(
+        &input.user_id,
+        &input.variant_ids,
+        DOWNLOAD_LIMIT,
+        expires_at,
+        &mut connection,
+    )
+    .await
+    {
+        Ok(downloads) => downloads,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    (StatusCode::CREATED, Json(downloads)).into_response()
+}
+
+pub async fn index(
+    State(state): State<AppState>,
+    CurrentUser(auth): CurrentUser,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let downloads = match services::download::by_user(&auth.user.id, &mut connection).await {
+        Ok(downloads) => downloads,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let links = downloads
+        .into_iter()
+        .map(|download| DownloadLink {
+            url: signed_url(&download.download, &state.env.app_secret),
+            download: download.download,
+            name: download.name,
+        })
+        .collect::<Vec<_>>();
+
+    Json(links).into_response()
+}
+
+pub async fn download(
+    Path(id): Path<Uuid>,
+    Query(params): Query<DownloadParams>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let signed = signing::verify(
+        &state.env.app_secret,
+        &signature_payload(&id, params.expires),
+        &params.signature,
+    );
+
+    if !signed {
+        return (StatusCode::FORBIDDEN, Json("Invalid signature")).into_response();
+    }
+
+    if params.expires < Utc::now().timestamp() {
+        return (StatusCode::GONE, Json("Link expired")).into_response();
+    }
+
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let mut tx = match connection.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let file = match services::download::consume(&id, &mut tx).await {
+        Ok(Some(file)) => file,
+        Ok(None) => {
+            return (StatusCode::GONE, Json("Link expired")).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    // the download is only used up once the file could be read, a storage error gives it back
+    let bytes = match state.private_storage.get(&file.key).await {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            error!("{err}");
+
+            if let Err(err) = tx.rollback().await {
+                error!("{err}");
+            }
+
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = tx.commit().await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    (
+        [
+            (header::CONTENT_TYPE, file.content_type),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", file.name.replace('"', "")),
+            ),
+        ],
+        bytes,
+    )
+        .into_response()
+}
@@ -1,58 +1,146 @@
 use axum::{
-    extract::{Multipart, State},
+    extract::{Multipart, Path, State},
     http::StatusCode,
-    response::IntoResponse,
+    response::{IntoResponse, Redirect},
     Json,
 };
 use log::error;
+use sqlx::PgConnection;
 use uuid::Uuid;
 
 use crate::{
+    models::image::{ImageWithUrl, LibraryImage, PopulatedImage, UploadResult},
     services::{self},
-    validations::image::StoreImageSchema,
+    utils::{
+        constants::IMAGE_CONTENT_TYPES,
+        storage::{Storage, StorageError},
+    },
+    validations::{
+        image::{ImportImagesSchema, StoreImageSchema, UpdateImageSchema},
+        ValidatedForm,
+    },
     AppState,
 };
 
-pub async fn upload(State(state): State<AppState>, mut multipart: Multipart) -> impl IntoResponse {
-    let file = match multipart.next_field().await {
-        Ok(Some(file)) => file,
-        Ok(None) => {
-            return (StatusCode::BAD_REQUEST, Json("File not found")).into_response();
+async fn library_image(
+    image: PopulatedImage,
+    storage: &dyn Storage,
+) -> Result<LibraryImage, StorageError> {
+    let url = storage.url(&image.image.src).await?;
+
+    Ok(LibraryImage {
+        references: image.products.len(),
+        products: image.products,
+        image: ImageWithUrl {
+            image: image.image,
+            url,
+        },
+    })
+}
+
+pub async fn index(State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let images = match services::image::all_populated(&mut connection).await {
+        Ok(images) => images,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let images = futures::future::join_all(
+        images
+            .into_iter()
+            .map(|image| library_image(image, state.storage.as_ref())),
+    )
+    .await
+    .into_iter()
+    .collect::<Result<Vec<_>, _>>();
+
+    match images {
+        Ok(images) => Json(images).into_response(),
+        Err(err) => {
+            error!("{err}");
+            (StatusCode::INTERNAL_SERVER_ERROR).into_response()
         }
-        Err(_) => {
-            return (StatusCode::BAD_REQUEST, Json("Invalid file")).into_response();
+    }
+}
+
+pub async fn show(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
         }
     };
 
-    let content_type = match file.content_type() {
-        Some(content_type) => content_type,
-        None => {
-            return (StatusCode::BAD_REQUEST, Json("Invalid content type")).into_response();
+    let image = match services::image::find_populated(&id, &mut connection).await {
+        Ok(Some(image)) => image,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
         }
     };
 
-    if content_type != "image/jpeg" && content_type != "image/png" {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json("Only image file type is allowed"),
-        )
-            .into_response();
+    match library_image(image, state.storage.as_ref()).await {
+        Ok(image) => Json(image).into_response(),
+        Err(err) => {
+            error!("{err}");
+            (StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
     }
+}
 
-    let file_name = match file.name() {
-        Some(file_name) => file_name.to_string(),
-        None => {
-            return (StatusCode::BAD_REQUEST, Json("Couldn't find file name")).into_response();
+pub async fn update(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<UpdateImageSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
         }
     };
 
-    let bytes = match file.bytes().await {
-        Ok(bytes) => bytes,
-        Err(_) => {
-            return (StatusCode::BAD_REQUEST, Json("Couldn't decode")).into_response();
+    let image = match services::image::find(&id, &mut connection).await {
+        Ok(Some(image)) => image,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND).into_response();
         }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    // only the display name changes, the stored file keeps its key
+    let input = StoreImageSchema {
+        name: input.name,
+        src: image.src,
     };
 
+    if let Err(err) = services::image::update(&image.id, &input, &mut connection).await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    (StatusCode::NO_CONTENT).into_response()
+}
+
+pub async fn destroy(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl IntoResponse {
     let mut connection = match state.db.acquire().await {
         Ok(connection) => connection,
         Err(err) => {
@@ -61,23 +149,243 @@ pub async fn upload(State(state): State<AppState>, mut multipart: Multipart) ->
         }
     };
 
-    let image = services::image::upload(
+    let image = match services::image::find_populated(&id, &mut connection).await {
+        Ok(Some(image)) => image,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if !image.products.is_empty() {
+        return (StatusCode::CONFLICT, Json("Image is used by products")).into_response();
+    }
+
+    if let Err(err) =
+        services::image::unload(&image.image, state.storage.as_ref(), &mut connection).await
+    {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    (StatusCode::NO_CONTENT).into_response()
+}
+
+fn upload_failed(name: String, error: &str) -> UploadResult {
+    UploadResult {
+        name,
+        image: None,
+        error: Some(error.to_string()),
+    }
+}
+
+fn upload_status(results: &[UploadResult]) -> StatusCode {
+    let created = results
+        .iter()
+        .filter(|result| result.image.is_some())
+        .count();
+
+    if created == results.len() {
+        StatusCode::CREATED
+    } else if created == 0 {
+        StatusCode::BAD_REQUEST
+    } else {
+        StatusCode::MULTI_STATUS
+    }
+}
+
+async fn store_image(
+    name: String,
+    content_type: &str,
+    bytes: &[u8],
+    state: &AppState,
+    connection: &mut PgConnection,
+) -> UploadResult {
+    if !IMAGE_CONTENT_TYPES.contains(&content_type) {
+        return upload_failed(name, "Only image file type is allowed");
+    }
+
+    if bytes.len() > state.env.upload_max_file_size {
+        return upload_failed(name, "File too large");
+    }
+
+    // keep the original name readable but safe to use as a storage key
+    let src = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' | '_' => c,
+            _ => '-',
+        })
+        .collect::<String>();
+
+    let image = match services::image::upload(
         &StoreImageSchema {
-            name: file_name.to_string(),
-            src: format!("{}-{}", Uuid::new_v4(), file_name),
+            name: name.clone(),
+            src: format!("{}-{}", Uuid::new_v4(), src),
         },
-        &bytes,
-        &mut connection,
+        bytes,
+        content_type,
+        state.storage.as_ref(),
+        connection,
     )
-    .await;
-
-    let image = match image {
+    .await
+    {
         Ok(image) => image,
+        Err(err) => {
+            error!("{err}");
+            return upload_failed(name, "Couldn't store file");
+        }
+    };
+
+    match state.storage.url(&image.src).await {
+        Ok(url) => UploadResult {
+            name,
+            image: Some(ImageWithUrl { image, url }),
+            error: None,
+        },
+        Err(err) => {
+            error!("{err}");
+            upload_failed(name, "Couldn't store file")
+        }
+    }
+}
+
+pub async fn upload(State(state): State<AppState>, mut multipart: Multipart) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
         Err(err) => {
             error!("{err}");
             return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
-        } 
+        }
     };
 
-    (StatusCode::CREATED, Json(image)).into_response()
+    let mut results = Vec::new();
+
+    loop {
+        let mut file = match multipart.next_field().await {
+            Ok(Some(file)) => file,
+            Ok(None) => break,
+            Err(_) => {
+                return (StatusCode::BAD_REQUEST, Json("Invalid file")).into_response();
+            }
+        };
+
+        let file_name = match file.file_name().or(file.name()) {
+            Some(file_name) => file_name.to_string(),
+            None => {
+                results.push(upload_failed(String::new(), "Couldn't find file name"));
+                continue;
+            }
+        };
+
+        let content_type = match file.content_type() {
+            Some(content_type) => content_type.to_string(),
+            None => {
+                results.push(upload_failed(file_name, "Invalid content type"));
+                continue;
+            }
+        };
+
+        if !IMAGE_CONTENT_TYPES.contains(&content_type.as_str()) {
+            results.push(upload_failed(file_name, "Only image file type is allowed"));
+            continue;
+        }
+
+        // read chunk by chunk so an oversized file is rejected without buffering all of it
+        let mut bytes = Vec::new();
+        let mut error = None;
+
+        loop {
+            match file.chunk().await {
+                Ok(Some(chunk)) => {
+                    if bytes.len() + chunk.len() > state.env.upload_max_file_size {
+                        error = Some("File too large");
+                        break;
+                    }
+
+                    bytes.extend_from_slice(&chunk);
+                }
+                Ok(None) => break,
+                Err(_) => {
+                    error = Some("Couldn't decode");
+                    break;
+                }
+            }
+        }
+
+        if let Some(error) = error {
+            results.push(upload_failed(file_name, error));
+            continue;
+        }
+
+        results.push(store_image(file_name, &content_type, &bytes, &state, &mut connection).await);
+    }
+
+    if results.is_empty() {
+        return (StatusCode::BAD_REQUEST, Json("File not found")).into_response();
+    }
+
+    (upload_status(&results), Json(results)).into_response()
+}
+
+pub async fn import(
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<ImportImagesSchema>,
+) -> impl IntoResponse {
+    let client = match services::image::download_client() {
+        Ok(client) => client,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let mut results = Vec::new();
+
+    for url in input.urls {
+        let download =
+            services::image::download(&client, &url, state.env.upload_max_file_size).await;
+
+        let download = match download {
+            Ok(download) => download,
+            Err(err) => {
+                results.push(upload_failed(url.to_string(), &err.to_string()));
+                continue;
+            }
+        };
+
+        results.push(
+            store_image(
+                download.name,
+                &download.content_type,
+                &download.bytes,
+                &state,
+                &mut connection,
+            )
+            .await,
+        );
+    }
+
+    (upload_status(&results), Json(results)).into_response()
+}
+
+pub async fn file(Path(src): Path<String>, State(state): State<AppState>) -> impl IntoResponse {
+    match state.storage.url(&src).await {
+        Ok(url) => Redirect::temporary(&url).into_response(),
+        Err(err) => {
+            error!("{err}");
+            (StatusCode::NOT_FOUND).into_response()
+        }
+    }
 }
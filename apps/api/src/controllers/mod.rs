@@ -1,10 +1,28 @@
+pub mod api_key;
 pub mod auth;
+pub mod bundle;
 pub mod cart;
 pub mod category;
+pub mod checkout;
+pub mod csrf;
+pub mod currency;
+pub mod customer_group;
+pub mod download;
 pub mod image;
+pub mod oauth;
+pub mod order;
+pub mod password;
+pub mod price_list;
 pub mod product;
+pub mod review;
 pub mod role;
+pub mod sale;
+pub mod session;
 pub mod settings;
+pub mod stock;
+pub mod translation;
+pub mod two_factor;
 pub mod unit;
 pub mod user;
 pub mod warehouse;
+pub mod wishlist;
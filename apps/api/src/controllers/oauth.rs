@@ -0,0 +1,228 @@
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{ConnectInfo, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Redirect},
+    Json,
+};
+use chrono::{Duration, Utc};
+use log::error;
+use uuid::Uuid;
+
+use crate::{
+    controllers::auth::start_session,
+    services,
+    utils::{
+        client,
+        constants::{OAUTH_STATE_COOKIE_NAME, OAUTH_STATE_EXPIRY, ROLES},
+        oauth::{self, OAuthProvider},
+        signing,
+    },
+    validations::identity::{OAuthCallbackParams, StoreIdentitySchema, StoreOAuthStateSchema},
+    AppState,
+};
+
+fn state_hash(secret: &str, state: &str) -> String {
+    signing::sign(secret, &format!("oauth:{}", state))
+}
+
+// lax so the cookie comes back on the provider's redirect, which is a cross site navigation
+fn state_cookie(state: &str, max_age: i64) -> String {
+    format!(
+        "{}={}; Path=/; HttpOnly; Secure; SameSite=Lax; Max-Age={}",
+        OAUTH_STATE_COOKIE_NAME, state, max_age
+    )
+}
+
+pub async fn authorize(State(state): State<AppState>) -> impl IntoResponse {
+    let provider = match OAuthProvider::from_env(&state.env) {
+        Some(provider) => provider,
+        None => return (StatusCode::NOT_FOUND, Json("OAuth is not configured")).into_response(),
+    };
+
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let oauth_state = Uuid::new_v4().to_string();
+    let (verifier, challenge) = oauth::pkce();
+
+    let inserted = services::identity::insert_state(
+        &StoreOAuthStateSchema {
+            state_hash: state_hash(&state.env.app_secret, &oauth_state),
+            verifier,
+            expires_at: Utc::now().naive_utc() + Duration::seconds(OAUTH_STATE_EXPIRY),
+        },
+        &mut connection,
+    )
+    .await;
+
+    if let Err(err) = inserted {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    let url = match provider.authorize(&oauth_state, &challenge) {
+        Ok(url) => url,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    (
+        [(
+            header::SET_COOKIE,
+            state_cookie(&oauth_state, OAUTH_STATE_EXPIRY),
+        )],
+        Redirect::to(&url),
+    )
+        .into_response()
+}
+
+pub async fn callback(
+    headers: HeaderMap,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    Query(params): Query<OAuthCallbackParams>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let provider = match OAuthProvider::from_env(&state.env) {
+        Some(provider) => provider,
+        None => return (StatusCode::NOT_FOUND, Json("OAuth is not configured")).into_response(),
+    };
+
+    if let Some(err) = params.error {
+        return (StatusCode::BAD_REQUEST, Json(err)).into_response();
+    }
+
+    let (code, oauth_state) = match (params.code, params.state) {
+        (Some(code), Some(oauth_state)) => (code, oauth_state),
+        _ => return (StatusCode::BAD_REQUEST, Json("Code and state required")).into_response(),
+    };
+
+    // the state has to come back to the browser that started the flow
+    if client::cookie(&headers, OAUTH_STATE_COOKIE_NAME).as_deref() != Some(oauth_state.as_str()) {
+        return (StatusCode::BAD_REQUEST, Json("State mismatch")).into_response();
+    }
+
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let stored = services::identity::consume_state(
+        &state_hash(&state.env.app_secret, &oauth_state),
+        &Utc::now().naive_utc(),
+        &mut connection,
+    )
+    .await;
+
+    let stored = match stored {
+        Ok(Some(stored)) => stored,
+        Ok(None) => {
+            return (StatusCode::BAD_REQUEST, Json("State not found or expired")).into_response()
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let access_token = match provider.exchange(&code, &stored.verifier).await {
+        Ok(access_token) => access_token,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::BAD_GATEWAY).into_response();
+        }
+    };
+
+    let external = match provider.user(&access_token).await {
+        Ok(external) => external,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::BAD_GATEWAY).into_response();
+        }
+    };
+
+    let identity =
+        match services::identity::find(&provider.name, &external.sub, &mut connection).await {
+            Ok(identity) => identity,
+            Err(err) => {
+                error!("{err}");
+                return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+            }
+        };
+
+    let user_id = match identity {
+        Some(identity) => identity.user_id,
+        None => {
+            // an unverified address could belong to someone else's account
+            let email = match (external.email, external.email_verified) {
+                (Some(email), true) => email,
+                _ => {
+                    return (
+                        StatusCode::FORBIDDEN,
+                        Json("Email not verified by provider"),
+                    )
+                        .into_response()
+                }
+            };
+
+            let user =
+                match services::user::find_or_insert(&email, ROLES.member, &mut connection).await {
+                    Ok(user) => user,
+                    Err(err) => {
+                        error!("{err}");
+                        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+                    }
+                };
+
+            let identity = services::identity::insert(
+                &StoreIdentitySchema {
+                    user_id: user.user.id,
+                    provider: provider.name.clone(),
+                    subject: external.sub,
+                    email,
+                },
+                &mut connection,
+            )
+            .await;
+
+            if let Err(err) = identity {
+                error!("{err}");
+                return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+            }
+
+            user.user.id
+        }
+    };
+
+    let session = start_session(&state.env, &user_id, &headers, peer, &mut connection).await;
+
+    if !session.status().is_success() {
+        return session;
+    }
+
+    // the provider sent the browser here, it goes back to the storefront signed in
+    let mut response = Redirect::to(&state.env.client_url).into_response();
+
+    for cookie in session.headers().get_all(header::SET_COOKIE) {
+        response
+            .headers_mut()
+            .append(header::SET_COOKIE, cookie.clone());
+    }
+
+    if let Ok(cookie) = HeaderValue::from_str(&state_cookie("", 0)) {
+        response.headers_mut().append(header::SET_COOKIE, cookie);
+    }
+
+    response
+}
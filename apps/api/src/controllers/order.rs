@@ -0,0 +1,24 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use log::error;
+
+use crate::{services, AppState};
+
+pub async fn index(State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let orders = match services::order::all(&mut connection).await {
+        Ok(orders) => orders,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    Json(orders).into_response()
+}
@@ -0,0 +1,307 @@
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use chrono::{Duration, Utc};
+use log::error;
+use resend_rs::types::CreateEmailBaseOptions;
+use sqlx::Acquire;
+use url::Url;
+use uuid::Uuid;
+
+use crate::{
+    controllers::auth::start_session,
+    extractors::session::CurrentUser,
+    services,
+    utils::{constants::PASSWORD_RESET_EXPIRY, password, signing},
+    validations::{
+        credential::{
+            ForgotPasswordSchema, PasswordSignInSchema, ResetPasswordSchema, SetPasswordSchema,
+        },
+        ValidatedForm,
+    },
+    AppState,
+};
+
+fn reset_hash(token: &Uuid) -> String {
+    signing::digest(&format!("reset:{}", token))
+}
+
+pub async fn sign_in(
+    headers: HeaderMap,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<PasswordSignInSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let user = match services::user::find_by_email(&input.email, &mut connection).await {
+        Ok(user) => user,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let credential = match &user {
+        Some(user) => match services::credential::find(&user.user.id, &mut connection).await {
+            Ok(credential) => credential,
+            Err(err) => {
+                error!("{err}");
+                return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+            }
+        },
+        None => None,
+    };
+
+    // unknown emails and magic link only accounts still pay for a hash check
+    let hash = match &credential {
+        Some(credential) => credential.password_hash.clone(),
+        None => password::dummy_hash(),
+    };
+
+    let valid = match password::verify(input.password, hash).await {
+        Ok(valid) => valid,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let user = match (user, credential, valid) {
+        (Some(user), Some(_), true) => user,
+        _ => return (StatusCode::UNAUTHORIZED, Json("Invalid email or password")).into_response(),
+    };
+
+    start_session(&state.env, &user.user.id, &headers, peer, &mut connection).await
+}
+
+pub async fn update(
+    CurrentUser(auth): CurrentUser,
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<SetPasswordSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let credential = match services::credential::find(&auth.user.id, &mut connection).await {
+        Ok(credential) => credential,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    // the first password can be set from the session alone, changing it needs the old one
+    if let Some(credential) = credential {
+        let current = match input.current_password {
+            Some(current) => current,
+            None => {
+                return (StatusCode::BAD_REQUEST, Json("Current password required")).into_response()
+            }
+        };
+
+        match password::verify(current, credential.password_hash).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json("Current password is incorrect"),
+                )
+                    .into_response()
+            }
+            Err(err) => {
+                error!("{err}");
+                return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+            }
+        }
+    }
+
+    let hash = match password::hash(input.password).await {
+        Ok(hash) => hash,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let now = Utc::now().naive_utc();
+
+    if let Err(err) = services::credential::set(&auth.user.id, &hash, &now, &mut connection).await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    ().into_response()
+}
+
+pub async fn forgot(
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<ForgotPasswordSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let user = match services::user::find_by_email(&input.email, &mut connection).await {
+        Ok(user) => user,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    // answers the same either way so the endpoint doesn't tell who has an account
+    let user = match user {
+        Some(user) => user,
+        None => return ().into_response(),
+    };
+
+    let token = Uuid::new_v4();
+
+    let mut tx = match connection.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let inserted = services::credential::insert_reset(
+        &user.user.id,
+        &reset_hash(&token),
+        &(Utc::now().naive_utc() + Duration::seconds(PASSWORD_RESET_EXPIRY)),
+        &mut tx,
+    )
+    .await;
+
+    if let Err(err) = inserted {
+        error!("{err}");
+        let _ = tx.rollback().await;
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    let mut url = match Url::parse(&state.env.client_url) {
+        Ok(url) => url,
+        Err(err) => {
+            error!("{err}");
+            let _ = tx.rollback().await;
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    url.set_path("/reset-password");
+    url.query_pairs_mut()
+        .append_pair("token", &token.to_string());
+
+    let email = CreateEmailBaseOptions::new(
+        format!("{}@{}", "auth", state.env.resend_domain),
+        vec![&input.email],
+        "Reset your password",
+    )
+    .with_html(&format!(r#"<a href="{}" >Reset password</a>"#, url));
+
+    if let Err(err) = state.mailer.mail(email).await {
+        error!("{err}");
+        let _ = tx.rollback().await;
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    if let Err(err) = tx.commit().await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    ().into_response()
+}
+
+pub async fn reset(
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<ResetPasswordSchema>,
+) -> impl IntoResponse {
+    let token = match Uuid::parse_str(&input.token) {
+        Ok(token) => token,
+        Err(_) => return (StatusCode::BAD_REQUEST, Json("Invalid token")).into_response(),
+    };
+
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let mut tx = match connection.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let now = Utc::now().naive_utc();
+
+    let reset = services::credential::consume_reset(&reset_hash(&token), &now, &mut tx).await;
+
+    let reset = match reset {
+        Ok(Some(reset)) => reset,
+        Ok(None) => {
+            let _ = tx.rollback().await;
+            return (StatusCode::BAD_REQUEST, Json("Token not found or expired")).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            let _ = tx.rollback().await;
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let hash = match password::hash(input.password).await {
+        Ok(hash) => hash,
+        Err(err) => {
+            error!("{err}");
+            let _ = tx.rollback().await;
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = services::credential::set(&reset.user_id, &hash, &now, &mut tx).await {
+        error!("{err}");
+        let _ = tx.rollback().await;
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    // whoever knew the old password is signed out everywhere
+    if let Err(err) = services::sessions::delete_by_user(&reset.user_id, &mut tx).await {
+        error!("{err}");
+        let _ = tx.rollback().await;
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    if let Err(err) = tx.commit().await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    ().into_response()
+}
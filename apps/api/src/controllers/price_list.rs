@@ -0,0 +1,220 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use log::error;
+use sqlx::{Acquire, PgConnection};
+use uuid::Uuid;
+
+use crate::{
+    services,
+    validations::{price_list::StorePriceListSchema, ValidatedForm},
+    AppState,
+};
+
+// the group and every rule target have to exist
+async fn validate_targets(
+    input: &StorePriceListSchema,
+    connection: &mut PgConnection,
+) -> Result<Option<&'static str>, sqlx::Error> {
+    if let Some(customer_group_id) = input.customer_group_id {
+        if services::customer_group::find(&customer_group_id, connection)
+            .await?
+            .is_none()
+        {
+            return Ok(Some("Customer group doesn't exist"));
+        }
+    }
+
+    let mut variant_ids = input
+        .rules
+        .iter()
+        .filter_map(|rule| rule.variant_id)
+        .collect::<Vec<_>>();
+    variant_ids.sort();
+    variant_ids.dedup();
+
+    let existing = services::product::count_existing_variants(&variant_ids, connection).await?;
+
+    if existing != variant_ids.len() as i64 {
+        return Ok(Some("Variant doesn't exist"));
+    }
+
+    for category_id in input.rules.iter().filter_map(|rule| rule.category_id) {
+        if services::category::find(&category_id, connection)
+            .await?
+            .is_none()
+        {
+            return Ok(Some("Category doesn't exist"));
+        }
+    }
+
+    Ok(None)
+}
+
+pub async fn index(State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let price_lists = match services::price_list::all(&mut connection).await {
+        Ok(price_lists) => price_lists,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    Json(price_lists).into_response()
+}
+
+pub async fn show(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::price_list::find(&id, &mut connection).await {
+        Ok(Some(price_list)) => Json(price_list).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND).into_response(),
+        Err(err) => {
+            error!("{err}");
+            (StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
+
+pub async fn store(
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<StorePriceListSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match validate_targets(&input, &mut connection).await {
+        Ok(None) => {}
+        Ok(Some(message)) => {
+            return (StatusCode::BAD_REQUEST, Json(message)).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let mut tx = match connection.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let price_list = match services::price_list::insert(&input, &mut tx).await {
+        Ok(price_list) => price_list,
+        Err(err) => {
+            error!("{err}");
+            if let Err(err) = tx.rollback().await {
+                error!("{err}");
+            }
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = tx.commit().await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    (StatusCode::CREATED, Json(price_list)).into_response()
+}
+
+pub async fn update(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<StorePriceListSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::price_list::find(&id, &mut connection).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match validate_targets(&input, &mut connection).await {
+        Ok(None) => {}
+        Ok(Some(message)) => {
+            return (StatusCode::BAD_REQUEST, Json(message)).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let mut tx = match connection.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = services::price_list::update(&id, &input, &mut tx).await {
+        error!("{err}");
+        if let Err(err) = tx.rollback().await {
+            error!("{err}");
+        }
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    if let Err(err) = tx.commit().await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    (StatusCode::NO_CONTENT).into_response()
+}
+
+pub async fn destroy(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = services::price_list::destroy(&id, &mut connection).await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    (StatusCode::NO_CONTENT).into_response()
+}
@@ -1,6 +1,6 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
     response::IntoResponse,
     Json,
 };
@@ -9,11 +9,24 @@ use sqlx::Acquire;
 use uuid::Uuid;
 
 use crate::{
-    services, utils::constants::PUBLIC_FOLDER_NAME, validations::product::StoreProductSchema,
+    extractors::session::OptionalUser,
+    services,
+    validations::{
+        currency::CurrencyParams,
+        product::{ImportParams, StoreProductSchema},
+        product_link::StoreProductLinksSchema,
+        translation::LocaleParams,
+    },
     AppState,
 };
 
-pub async fn index(State(state): State<AppState>) -> impl IntoResponse {
+pub async fn index(
+    headers: HeaderMap,
+    Query(params): Query<CurrencyParams>,
+    Query(locale): Query<LocaleParams>,
+    State(state): State<AppState>,
+    OptionalUser(auth): OptionalUser,
+) -> impl IntoResponse {
     let mut connection = match state.db.acquire().await {
         Ok(connection) => connection,
         Err(err) => {
@@ -22,7 +35,19 @@ pub async fn index(State(state): State<AppState>) -> impl IntoResponse {
         }
     };
 
-    let products = match services::product::all(&mut connection).await {
+    let conversion =
+        match services::currency::conversion(params.preferred(&headers), &mut connection).await {
+            Ok(Some(conversion)) => conversion,
+            Ok(None) => {
+                return (StatusCode::BAD_REQUEST, Json("Currency isn't supported")).into_response();
+            }
+            Err(err) => {
+                error!("{err}");
+                return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+            }
+        };
+
+    let mut products = match services::product::all(&mut connection).await {
         Ok(products) => products,
         Err(err) => {
             error!("{err}");
@@ -30,10 +55,48 @@ pub async fn index(State(state): State<AppState>) -> impl IntoResponse {
         }
     };
 
+    let customer_group_id = auth.and_then(|auth| auth.user.customer_group_id);
+
+    if let Err(err) =
+        services::price_list::apply(&mut products, customer_group_id, &mut connection).await
+    {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    services::currency::convert_products(&mut products, &conversion);
+
+    let locales = match services::translation::candidates(
+        locale.preferred(&headers),
+        &mut connection,
+    )
+    .await
+    {
+        Ok(locales) => locales,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) =
+        services::translation::localize_products(&mut products, &locales, &mut connection).await
+    {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
     Json(products).into_response()
 }
 
-pub async fn show(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl IntoResponse {
+pub async fn show(
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    Query(params): Query<CurrencyParams>,
+    Query(locale): Query<LocaleParams>,
+    State(state): State<AppState>,
+    OptionalUser(auth): OptionalUser,
+) -> impl IntoResponse {
     let mut connection = match state.db.acquire().await {
         Ok(connection) => connection,
         Err(err) => {
@@ -42,6 +105,18 @@ pub async fn show(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl I
         }
     };
 
+    let conversion =
+        match services::currency::conversion(params.preferred(&headers), &mut connection).await {
+            Ok(Some(conversion)) => conversion,
+            Ok(None) => {
+                return (StatusCode::BAD_REQUEST, Json("Currency isn't supported")).into_response();
+            }
+            Err(err) => {
+                error!("{err}");
+                return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+            }
+        };
+
     let product = match services::product::find(&id, &mut connection).await {
         Ok(Some(product)) => product,
         Ok(None) => {
@@ -53,7 +128,39 @@ pub async fn show(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl I
         }
     };
 
-    Json(product).into_response()
+    let customer_group_id = auth.and_then(|auth| auth.user.customer_group_id);
+    let mut products = vec![product];
+
+    if let Err(err) =
+        services::price_list::apply(&mut products, customer_group_id, &mut connection).await
+    {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    services::currency::convert_products(&mut products, &conversion);
+
+    let locales = match services::translation::candidates(
+        locale.preferred(&headers),
+        &mut connection,
+    )
+    .await
+    {
+        Ok(locales) => locales,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) =
+        services::translation::localize_products(&mut products, &locales, &mut connection).await
+    {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    Json(&products[0]).into_response()
 }
 
 pub async fn store(
@@ -192,10 +299,7 @@ pub async fn update(
 
     let tobe_deleted_tasks = tobe_deleted
         .iter()
-        .map(|image| {
-            let path = std::path::Path::new(PUBLIC_FOLDER_NAME).join(&image.src);
-            tokio::fs::remove_file(path)
-        })
+        .map(|image| state.storage.delete(&image.src))
         .collect::<Vec<_>>();
 
     futures::future::join_all(tobe_deleted_tasks).await;
@@ -268,3 +372,207 @@ pub async fn destroy(Path(id): Path<Uuid>, State(state): State<AppState>) -> imp
 
     (StatusCode::NO_CONTENT).into_response()
 }
+
+pub async fn export(State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let rows = match services::catalog::export(&mut connection).await {
+        Ok(rows) => rows,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let csv = match services::catalog::to_csv(&rows) {
+        Ok(csv) => csv,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"products.csv\"",
+            ),
+        ],
+        csv,
+    )
+        .into_response()
+}
+
+pub async fn import(
+    State(state): State<AppState>,
+    Query(params): Query<ImportParams>,
+    csv: String,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    // the whole import runs in a transaction, a dry run simply never commits it
+    let mut tx = match connection.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let mut report = match services::catalog::import(&csv, &mut tx).await {
+        Ok(report) => report,
+        Err(err) => {
+            error!("{err}");
+            if let Err(err) = tx.rollback().await {
+                error!("{err}");
+            }
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    report.dry_run = params.dry_run;
+
+    if !report.errors.is_empty() || params.dry_run {
+        if let Err(err) = tx.rollback().await {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+
+        let status = match report.errors.is_empty() {
+            true => StatusCode::OK,
+            false => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+
+        return (status, Json(report)).into_response();
+    }
+
+    if let Err(err) = tx.commit().await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    Json(report).into_response()
+}
+
+pub async fn links(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::product::exists(&id, &mut connection).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (StatusCode::NOT_FOUND).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let links = match services::product_link::links(&id, &mut connection).await {
+        Ok(links) => links,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    Json(links).into_response()
+}
+
+pub async fn update_links(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Json(input): Json<StoreProductLinksSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::product::exists(&id, &mut connection).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (StatusCode::NOT_FOUND).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let mut linked_ids = input
+        .related
+        .iter()
+        .chain(input.cross_sell.iter())
+        .chain(input.up_sell.iter())
+        .cloned()
+        .collect::<Vec<_>>();
+
+    linked_ids.sort();
+    linked_ids.dedup();
+
+    if linked_ids.contains(&id) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json("Product can't link to itself"),
+        )
+            .into_response();
+    }
+
+    match services::product::count_existing(&linked_ids, &mut connection).await {
+        Ok(count) if count == linked_ids.len() as i64 => {}
+        Ok(_) => {
+            return (StatusCode::BAD_REQUEST, Json("Linked product not found")).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let mut tx = match connection.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = services::product_link::replace(&id, &input, &mut tx).await {
+        error!("{err}");
+        if let Err(err) = tx.rollback().await {
+            error!("{err}");
+        }
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    if let Err(err) = tx.commit().await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    (StatusCode::NO_CONTENT).into_response()
+}
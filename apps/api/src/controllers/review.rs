@@ -0,0 +1,177 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use log::error;
+use uuid::Uuid;
+
+use crate::{
+    extractors::session::CurrentUser,
+    services,
+    validations::{
+        review::{ReviewFilter, StoreReviewSchema, UpdateReviewSchema},
+        ValidatedForm,
+    },
+    AppState,
+};
+
+pub async fn index(
+    State(state): State<AppState>,
+    Query(filter): Query<ReviewFilter>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let reviews = match services::review::all(filter.status.as_deref(), &mut connection).await {
+        Ok(reviews) => reviews,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    Json(reviews).into_response()
+}
+
+pub async fn product_reviews(
+    Path(product_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let reviews = match services::review::approved(&product_id, &mut connection).await {
+        Ok(reviews) => reviews,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    Json(reviews).into_response()
+}
+
+pub async fn store(
+    Path(product_id): Path<Uuid>,
+    State(state): State<AppState>,
+    CurrentUser(auth): CurrentUser,
+    ValidatedForm(input): ValidatedForm<StoreReviewSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::product::exists(&product_id, &mut connection).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (StatusCode::NOT_FOUND).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::review::find_by_author(&product_id, &auth.user.id, &mut connection).await {
+        Ok(None) => {}
+        Ok(Some(_)) => {
+            return (StatusCode::CONFLICT, Json("Product already reviewed")).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let verified =
+        match services::order::purchased(&auth.user.id, &product_id, &mut connection).await {
+            Ok(verified) => verified,
+            Err(err) => {
+                error!("{err}");
+                return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+            }
+        };
+
+    let review = match services::review::insert(
+        &product_id,
+        &auth.user.id,
+        verified,
+        &input,
+        &mut connection,
+    )
+    .await
+    {
+        Ok(review) => review,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    (StatusCode::CREATED, Json(review)).into_response()
+}
+
+pub async fn update(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<UpdateReviewSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::review::find(&id, &mut connection).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = services::review::update_status(&id, &input.status, &mut connection).await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    (StatusCode::NO_CONTENT).into_response()
+}
+
+pub async fn destroy(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = services::review::destroy(&id, &mut connection).await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    (StatusCode::NO_CONTENT).into_response()
+}
@@ -1,4 +1,11 @@
-use crate::{services, AppState};
+use crate::{
+    extractors::session::CurrentUser,
+    models::session::PopulatedSession,
+    services,
+    utils::constants::{PERMISSION_NAMES, ROLES},
+    validations::{role::SaveRoleSchema, ValidatedForm},
+    AppState,
+};
 use axum::{
     extract::{Path, State},
     http::StatusCode,
@@ -6,8 +13,29 @@ use axum::{
     Json,
 };
 use log::error;
+use sqlx::{Acquire, PgConnection};
 use uuid::Uuid;
 
+// nobody hands out more than they hold, api keys included
+fn holds_all(auth: &PopulatedSession, permissions: &[String]) -> bool {
+    permissions
+        .iter()
+        .all(|permission| auth.permissions.contains(permission))
+}
+
+// a role can only be changed or assigned by someone who holds everything it grants
+pub(crate) async fn can_manage(
+    auth: &PopulatedSession,
+    role_id: &Uuid,
+    connection: &mut PgConnection,
+) -> Result<bool, sqlx::Error> {
+    match services::role::find_populated(role_id, connection).await? {
+        Some(role) => Ok(holds_all(auth, &role.permissions)
+            && (role.role.name != ROLES.admin || auth.role.name == ROLES.admin)),
+        None => Ok(true),
+    }
+}
+
 pub async fn index(State(state): State<AppState>) -> impl IntoResponse {
     let mut connection = match state.db.acquire().await {
         Ok(connection) => connection,
@@ -17,7 +45,7 @@ pub async fn index(State(state): State<AppState>) -> impl IntoResponse {
         }
     };
 
-    let roles = match services::role::all(&mut connection).await {
+    let roles = match services::role::all_populated(&mut connection).await {
         Ok(roles) => roles,
         Err(err) => {
             error!("{err}");
@@ -37,7 +65,7 @@ pub async fn show(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl I
         }
     };
 
-    let role = match services::role::find(&id, &mut connection).await {
+    let role = match services::role::find_populated(&id, &mut connection).await {
         Ok(Some(role)) => role,
         Ok(None) => {
             return (StatusCode::NOT_FOUND).into_response();
@@ -50,3 +78,232 @@ pub async fn show(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl I
 
     Json(role).into_response()
 }
+
+pub async fn permissions() -> impl IntoResponse {
+    Json(PERMISSION_NAMES).into_response()
+}
+
+pub async fn store(
+    State(state): State<AppState>,
+    CurrentUser(auth): CurrentUser,
+    ValidatedForm(input): ValidatedForm<SaveRoleSchema>,
+) -> impl IntoResponse {
+    if !holds_all(&auth, &input.permissions) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json("Permissions must be ones you hold"),
+        )
+            .into_response();
+    }
+
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::role::find_by_name(&input.name, &mut connection).await {
+        Ok(Some(_)) => {
+            return (StatusCode::CONFLICT, Json("Role already exists")).into_response();
+        }
+        Ok(None) => {}
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let mut tx = match connection.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let role = match services::role::insert_named(&input.name, &mut tx).await {
+        Ok(role) => role,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = services::role::set_permissions(&role.id, &input.permissions, &mut tx).await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    let role = match services::role::find_populated(&role.id, &mut tx).await {
+        Ok(Some(role)) => role,
+        Ok(None) => return (StatusCode::INTERNAL_SERVER_ERROR).into_response(),
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = tx.commit().await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    (StatusCode::CREATED, Json(role)).into_response()
+}
+
+pub async fn update(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    CurrentUser(auth): CurrentUser,
+    ValidatedForm(input): ValidatedForm<SaveRoleSchema>,
+) -> impl IntoResponse {
+    if id == auth.role.id {
+        return (
+            StatusCode::FORBIDDEN,
+            Json("Your own role can't be changed"),
+        )
+            .into_response();
+    }
+
+    if !holds_all(&auth, &input.permissions) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json("Permissions must be ones you hold"),
+        )
+            .into_response();
+    }
+
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let role = match services::role::find(&id, &mut connection).await {
+        Ok(Some(role)) => role,
+        Ok(None) => return (StatusCode::NOT_FOUND).into_response(),
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    // the admin role always holds every permission so there is no way to lock everyone out
+    if role.name == ROLES.admin {
+        return (
+            StatusCode::FORBIDDEN,
+            Json("The admin role can't be changed"),
+        )
+            .into_response();
+    }
+
+    // sign up looks the member role up by name
+    if role.name == ROLES.member && input.name != ROLES.member {
+        return (
+            StatusCode::FORBIDDEN,
+            Json("The member role can't be renamed"),
+        )
+            .into_response();
+    }
+
+    match can_manage(&auth, &id, &mut connection).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::FORBIDDEN,
+                Json("The role grants permissions you don't hold"),
+            )
+                .into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::role::find_by_name(&input.name, &mut connection).await {
+        Ok(Some(role)) if role.id != id => {
+            return (StatusCode::CONFLICT, Json("Role already exists")).into_response();
+        }
+        Ok(_) => {}
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let mut tx = match connection.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = services::role::rename(&id, &input.name, &mut tx).await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    if let Err(err) = services::role::set_permissions(&id, &input.permissions, &mut tx).await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    if let Err(err) = tx.commit().await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    (StatusCode::NO_CONTENT).into_response()
+}
+
+pub async fn destroy(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let role = match services::role::find(&id, &mut connection).await {
+        Ok(Some(role)) => role,
+        Ok(None) => return (StatusCode::NOT_FOUND).into_response(),
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if role.name == ROLES.admin || role.name == ROLES.member {
+        return (
+            StatusCode::FORBIDDEN,
+            Json("Built-in roles can't be deleted"),
+        )
+            .into_response();
+    }
+
+    // users cascade with their role, so refuse instead of deleting accounts
+    match services::role::count_users(&id, &mut connection).await {
+        Ok(0) => {}
+        Ok(_) => {
+            return (StatusCode::CONFLICT, Json("Role is assigned to users")).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = services::role::destroy(&id, &mut connection).await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    (StatusCode::NO_CONTENT).into_response()
+}
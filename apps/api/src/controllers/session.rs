@@ -0,0 +1,55 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use log::error;
+use uuid::Uuid;
+
+use crate::{extractors::session::CurrentUser, services, AppState};
+
+pub async fn index(
+    CurrentUser(auth): CurrentUser,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::sessions::devices(&auth.user.id, &auth.session.id, &mut connection).await {
+        Ok(devices) => Json(devices).into_response(),
+        Err(err) => {
+            error!("{err}");
+            (StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
+
+// signs a device out, only among the caller's own sessions
+pub async fn destroy(
+    Path(id): Path<Uuid>,
+    CurrentUser(auth): CurrentUser,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::sessions::delete_for_user(&id, &auth.user.id, &mut connection).await {
+        Ok(result) if result.rows_affected() == 0 => (StatusCode::NOT_FOUND).into_response(),
+        Ok(_) => ().into_response(),
+        Err(err) => {
+            error!("{err}");
+            (StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
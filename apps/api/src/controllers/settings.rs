@@ -3,7 +3,7 @@ use log::error;
 
 use crate::{
     services,
-    utils::constants::{ROLES, SETTINGS},
+    utils::constants::{PERMISSION_NAMES, ROLES, SETTINGS},
     validations::{
         role::StoreRoleSchema,
         settings::{SetupSchema, StoreSettingsSchema},
@@ -62,8 +62,12 @@ pub async fn setup(
     // setup roles
     let roles = services::role::insert_many(
         &vec![
-            StoreRoleSchema { name: ROLES.member },
-            StoreRoleSchema { name: ROLES.admin },
+            StoreRoleSchema {
+                name: ROLES.member.to_string(),
+            },
+            StoreRoleSchema {
+                name: ROLES.admin.to_string(),
+            },
         ],
         &mut connection,
     )
@@ -77,16 +81,26 @@ pub async fn setup(
         }
     };
 
+    let admin = roles
+        .into_iter()
+        .find(|role| role.name == ROLES.admin)
+        .unwrap();
+
+    let permissions = PERMISSION_NAMES.map(String::from);
+
+    if let Err(err) =
+        services::role::set_permissions(&admin.id, &permissions, &mut connection).await
+    {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
     // setup admin
     let user = services::user::insert(
         &StoreUserSchema {
             name: None,
             email: input.email,
-            role_id: roles
-                .into_iter()
-                .find(|role| role.name == ROLES.admin)
-                .unwrap()
-                .id,
+            role_id: admin.id,
         },
         &mut connection,
     )
@@ -0,0 +1,179 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use log::error;
+
+use crate::{
+    services,
+    validations::{
+        stock::{StoreAlertRecipientsSchema, StoreStockSchema, StoreThresholdSchema},
+        ValidatedForm,
+    },
+    AppState,
+};
+
+pub async fn index(State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let stocks = match services::stock::all(&mut connection).await {
+        Ok(stocks) => stocks,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    Json(stocks).into_response()
+}
+
+pub async fn set(
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<StoreStockSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::product::find_variant(&input.variant_id, &mut connection).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json("Variant doesn't exist")).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::warehouse::find(&input.warehouse_id, &mut connection).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json("Warehouse doesn't exist")).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let stock = match services::stock::set(&input, &mut connection).await {
+        Ok(stock) => stock,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    // the stock is saved either way, a failed alert is retried on the next change
+    if let Err(err) = services::stock::alert_low(
+        state.mailer.as_ref(),
+        &state.env.resend_domain,
+        &mut connection,
+    )
+    .await
+    {
+        error!("{err}");
+    }
+
+    Json(stock).into_response()
+}
+
+pub async fn set_threshold(
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<StoreThresholdSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let stock = match services::stock::set_threshold(&input, &mut connection).await {
+        Ok(Some(stock)) => stock,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json("Stock doesn't exist")).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = services::stock::alert_low(
+        state.mailer.as_ref(),
+        &state.env.resend_domain,
+        &mut connection,
+    )
+    .await
+    {
+        error!("{err}");
+    }
+
+    Json(stock).into_response()
+}
+
+pub async fn low(State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::stock::low(&mut connection).await {
+        Ok(stocks) => Json(stocks).into_response(),
+        Err(err) => {
+            error!("{err}");
+            (StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
+
+pub async fn alert_recipients(State(state): State<AppState>) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match services::stock::alert_recipients(&mut connection).await {
+        Ok(emails) => Json(emails).into_response(),
+        Err(err) => {
+            error!("{err}");
+            (StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
+
+pub async fn set_alert_recipients(
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<StoreAlertRecipientsSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = services::stock::set_alert_recipients(&input.emails, &mut connection).await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    ().into_response()
+}
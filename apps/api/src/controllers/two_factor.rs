@@ -0,0 +1,387 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use chrono::Utc;
+use log::error;
+use sqlx::{Acquire, PgConnection};
+
+use crate::{
+    extractors::session::CurrentUser,
+    models::two_factor::{Enrollment, RecoveryCodes, TotpFactor, TwoFactorStatus},
+    services,
+    utils::{constants::RECOVERY_CODE_COUNT, env::Env, signing, totp},
+    validations::{
+        two_factor::{SetTwoFactorRequiredSchema, TwoFactorCodeSchema},
+        ValidatedForm,
+    },
+    AppState,
+};
+
+fn recovery_hash(secret: &str, factor: &TotpFactor, code: &str) -> String {
+    signing::sign(secret, &format!("recovery:{}:{}", factor.user_id, code))
+}
+
+// a code issued before the secret was rotated still matches one of these
+fn recovery_hashes(env: &Env, factor: &TotpFactor, code: &str) -> Vec<String> {
+    env.secrets()
+        .map(|secret| recovery_hash(secret, factor, code))
+        .collect()
+}
+
+// factors from before secrets were sealed are sealed on startup, until then they are read as is
+fn factor_secret(env: &Env, factor: &TotpFactor) -> Option<Vec<u8>> {
+    if !factor.sealed {
+        return Some(factor.secret.clone());
+    }
+
+    totp::open(env.secrets(), &factor.secret)
+}
+
+// a current totp code, or once confirmed one of the recovery codes, each good only once
+async fn check(
+    env: &Env,
+    factor: &TotpFactor,
+    code: &str,
+    db: &mut PgConnection,
+) -> Result<bool, sqlx::Error> {
+    let code = code.trim().to_lowercase();
+
+    let step = factor_secret(env, factor)
+        .and_then(|secret| totp::verify(&secret, &code, Utc::now().timestamp()));
+
+    if let Some(step) = step {
+        return services::two_factor::use_step(&factor.user_id, step, db).await;
+    }
+
+    if factor.confirmed_at.is_none() {
+        return Ok(false);
+    }
+
+    services::two_factor::consume_recovery_code(
+        &factor.user_id,
+        &recovery_hashes(env, factor, &code),
+        db,
+    )
+    .await
+}
+
+// replaces whatever codes were left, the plain codes are only ever in this response
+async fn issue_recovery_codes(
+    secret: &str,
+    factor: &TotpFactor,
+    db: &mut PgConnection,
+) -> Result<Vec<String>, sqlx::Error> {
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| totp::recovery_code())
+        .collect();
+
+    let hashes: Vec<String> = codes
+        .iter()
+        .map(|code| recovery_hash(secret, factor, code))
+        .collect();
+
+    services::two_factor::replace_recovery_codes(&factor.user_id, &hashes, db).await?;
+
+    Ok(codes)
+}
+
+pub async fn status(
+    CurrentUser(auth): CurrentUser,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let factor = match services::two_factor::find(&auth.user.id, &mut connection).await {
+        Ok(factor) => factor,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let required = match services::two_factor::required(&mut connection).await {
+        Ok(required) => required && !auth.permissions.is_empty(),
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let recovery_codes =
+        match services::two_factor::recovery_codes_left(&auth.user.id, &mut connection).await {
+            Ok(recovery_codes) => recovery_codes,
+            Err(err) => {
+                error!("{err}");
+                return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+            }
+        };
+
+    Json(TwoFactorStatus {
+        enabled: factor.is_some_and(|factor| factor.confirmed_at.is_some()),
+        verified: auth.session.second_factor_at.is_some(),
+        required,
+        recovery_codes,
+    })
+    .into_response()
+}
+
+pub async fn enroll(
+    CurrentUser(auth): CurrentUser,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let secret = totp::secret();
+    let sealed = totp::seal(&state.env.app_secret, &secret);
+    let now = Utc::now().naive_utc();
+
+    match services::two_factor::enroll(&auth.user.id, &sealed, &now, &mut connection).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (StatusCode::CONFLICT, Json("Two-factor already enabled")).into_response()
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    Json(Enrollment {
+        secret: totp::base32(&secret),
+        uri: totp::uri(&secret, &auth.user.email),
+    })
+    .into_response()
+}
+
+pub async fn confirm(
+    CurrentUser(auth): CurrentUser,
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<TwoFactorCodeSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let factor = match services::two_factor::find(&auth.user.id, &mut connection).await {
+        Ok(Some(factor)) => factor,
+        Ok(None) => return (StatusCode::NOT_FOUND).into_response(),
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if factor.confirmed_at.is_some() {
+        return (StatusCode::CONFLICT, Json("Two-factor already enabled")).into_response();
+    }
+
+    let mut tx = match connection.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match check(&state.env, &factor, &input.code, &mut tx).await {
+        Ok(true) => {}
+        Ok(false) => {
+            let _ = tx.rollback().await;
+            return (StatusCode::BAD_REQUEST, Json("Invalid code")).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            let _ = tx.rollback().await;
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    }
+
+    let now = Utc::now().naive_utc();
+
+    if let Err(err) = services::two_factor::confirm(&auth.user.id, &now, &mut tx).await {
+        error!("{err}");
+        let _ = tx.rollback().await;
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    let codes = match issue_recovery_codes(&state.env.app_secret, &factor, &mut tx).await {
+        Ok(codes) => codes,
+        Err(err) => {
+            error!("{err}");
+            let _ = tx.rollback().await;
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    // proving the code just now counts for this session
+    if let Err(err) =
+        services::sessions::verify_second_factor(&auth.session.id, &now, &mut tx).await
+    {
+        error!("{err}");
+        let _ = tx.rollback().await;
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    if let Err(err) = tx.commit().await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    Json(RecoveryCodes { codes }).into_response()
+}
+
+pub async fn verify(
+    CurrentUser(auth): CurrentUser,
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<TwoFactorCodeSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let factor = match services::two_factor::find(&auth.user.id, &mut connection).await {
+        Ok(Some(factor)) if factor.confirmed_at.is_some() => factor,
+        Ok(_) => return (StatusCode::BAD_REQUEST, Json("Two-factor not enabled")).into_response(),
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match check(&state.env, &factor, &input.code, &mut connection).await {
+        Ok(true) => {}
+        Ok(false) => return (StatusCode::BAD_REQUEST, Json("Invalid code")).into_response(),
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    }
+
+    let now = Utc::now().naive_utc();
+
+    if let Err(err) =
+        services::sessions::verify_second_factor(&auth.session.id, &now, &mut connection).await
+    {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    ().into_response()
+}
+
+pub async fn disable(
+    CurrentUser(auth): CurrentUser,
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<TwoFactorCodeSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let factor = match services::two_factor::find(&auth.user.id, &mut connection).await {
+        Ok(Some(factor)) => factor,
+        Ok(None) => return (StatusCode::NOT_FOUND).into_response(),
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match check(&state.env, &factor, &input.code, &mut connection).await {
+        Ok(true) => {}
+        Ok(false) => return (StatusCode::BAD_REQUEST, Json("Invalid code")).into_response(),
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    }
+
+    if let Err(err) = services::two_factor::destroy(&auth.user.id, &mut connection).await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    ().into_response()
+}
+
+pub async fn recovery_codes(
+    CurrentUser(auth): CurrentUser,
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<TwoFactorCodeSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let factor = match services::two_factor::find(&auth.user.id, &mut connection).await {
+        Ok(Some(factor)) if factor.confirmed_at.is_some() => factor,
+        Ok(_) => return (StatusCode::BAD_REQUEST, Json("Two-factor not enabled")).into_response(),
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match check(&state.env, &factor, &input.code, &mut connection).await {
+        Ok(true) => {}
+        Ok(false) => return (StatusCode::BAD_REQUEST, Json("Invalid code")).into_response(),
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    }
+
+    match issue_recovery_codes(&state.env.app_secret, &factor, &mut connection).await {
+        Ok(codes) => Json(RecoveryCodes { codes }).into_response(),
+        Err(err) => {
+            error!("{err}");
+            (StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
+
+pub async fn set_required(
+    State(state): State<AppState>,
+    ValidatedForm(input): ValidatedForm<SetTwoFactorRequiredSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = services::two_factor::set_required(input.required, &mut connection).await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    ().into_response()
+}
@@ -1,14 +1,22 @@
-use crate::{services, validations::unit::StoreUnitSchema, AppState};
+use crate::{
+    services,
+    validations::{translation::LocaleParams, unit::StoreUnitSchema},
+    AppState,
+};
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     Json,
 };
 use log::error;
 use uuid::Uuid;
 
-pub async fn index(State(state): State<AppState>) -> impl IntoResponse {
+pub async fn index(
+    headers: HeaderMap,
+    Query(locale): Query<LocaleParams>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
     let mut connection = match state.db.acquire().await {
         Ok(connection) => connection,
         Err(err) => {
@@ -17,7 +25,7 @@ pub async fn index(State(state): State<AppState>) -> impl IntoResponse {
         }
     };
 
-    let units = match services::unit::all(&mut connection).await {
+    let mut units = match services::unit::all(&mut connection).await {
         Ok(units) => units,
         Err(err) => {
             error!("{err}");
@@ -25,10 +33,35 @@ pub async fn index(State(state): State<AppState>) -> impl IntoResponse {
         }
     };
 
+    let locales = match services::translation::candidates(
+        locale.preferred(&headers),
+        &mut connection,
+    )
+    .await
+    {
+        Ok(locales) => locales,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) =
+        services::translation::localize_units(&mut units, &locales, &mut connection).await
+    {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
     Json(units).into_response()
 }
 
-pub async fn show(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl IntoResponse {
+pub async fn show(
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    Query(locale): Query<LocaleParams>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
     let mut connection = match state.db.acquire().await {
         Ok(connection) => connection,
         Err(err) => {
@@ -48,7 +81,29 @@ pub async fn show(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl I
         }
     };
 
-    Json(unit).into_response()
+    let mut units = vec![unit];
+
+    let locales = match services::translation::candidates(
+        locale.preferred(&headers),
+        &mut connection,
+    )
+    .await
+    {
+        Ok(locales) => locales,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) =
+        services::translation::localize_units(&mut units, &locales, &mut connection).await
+    {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    Json(&units[0]).into_response()
 }
 
 pub async fn store(
@@ -7,7 +7,10 @@ use axum::{
 use log::error;
 use uuid::Uuid;
 
-use crate::{services, validations::user::StoreUserSchema, AppState};
+use crate::{
+    controllers::role::can_manage, extractors::session::CurrentUser, services,
+    validations::user::StoreUserSchema, AppState,
+};
 
 pub async fn index(State(state): State<AppState>) -> impl IntoResponse {
     let mut connection = match state.db.acquire().await {
@@ -54,6 +57,7 @@ pub async fn show(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl I
 
 pub async fn store(
     State(state): State<AppState>,
+    CurrentUser(auth): CurrentUser,
     Json(input): Json<StoreUserSchema>,
 ) -> impl IntoResponse {
     let mut connection = match state.db.acquire().await {
@@ -64,6 +68,21 @@ pub async fn store(
         }
     };
 
+    match can_manage(&auth, &input.role_id, &mut connection).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::FORBIDDEN,
+                Json("The role grants permissions you don't hold"),
+            )
+                .into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
     if let Err(err) = services::user::insert(&input, &mut connection).await {
         error!("{err}");
         return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
@@ -75,8 +94,17 @@ pub async fn store(
 pub async fn update(
     Path(id): Path<Uuid>,
     State(state): State<AppState>,
+    CurrentUser(auth): CurrentUser,
     Json(input): Json<StoreUserSchema>,
 ) -> impl IntoResponse {
+    if id == auth.user.id && input.role_id != auth.role.id {
+        return (
+            StatusCode::FORBIDDEN,
+            Json("Your own role can't be changed"),
+        )
+            .into_response();
+    }
+
     let mut connection = match state.db.acquire().await {
         Ok(connection) => connection,
         Err(err) => {
@@ -85,6 +113,44 @@ pub async fn update(
         }
     };
 
+    // users who can do more than the caller are out of their reach
+    match services::user::find(&id, &mut connection).await {
+        Ok(Some(user)) => match can_manage(&auth, &user.role.id, &mut connection).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return (
+                    StatusCode::FORBIDDEN,
+                    Json("The user holds permissions you don't"),
+                )
+                    .into_response();
+            }
+            Err(err) => {
+                error!("{err}");
+                return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+            }
+        },
+        Ok(None) => return (StatusCode::NOT_FOUND).into_response(),
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match can_manage(&auth, &input.role_id, &mut connection).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::FORBIDDEN,
+                Json("The role grants permissions you don't hold"),
+            )
+                .into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
     if let Err(err) = services::user::update(&id, &input, &mut connection).await {
         error!("{err}");
         return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
@@ -93,7 +159,11 @@ pub async fn update(
     (StatusCode::NO_CONTENT).into_response()
 }
 
-pub async fn destroy(Path(id): Path<Uuid>, State(state): State<AppState>) -> impl IntoResponse {
+pub async fn destroy(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    CurrentUser(auth): CurrentUser,
+) -> impl IntoResponse {
     let mut connection = match state.db.acquire().await {
         Ok(connection) => connection,
         Err(err) => {
@@ -102,6 +172,29 @@ pub async fn destroy(Path(id): Path<Uuid>, State(state): State<AppState>) -> imp
         }
     };
 
+    // users who can do more than the caller are out of their reach
+    match services::user::find(&id, &mut connection).await {
+        Ok(Some(user)) => match can_manage(&auth, &user.role.id, &mut connection).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return (
+                    StatusCode::FORBIDDEN,
+                    Json("The user holds permissions you don't"),
+                )
+                    .into_response();
+            }
+            Err(err) => {
+                error!("{err}");
+                return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+            }
+        },
+        Ok(None) => return (StatusCode::NOT_FOUND).into_response(),
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
     if let Err(err) = services::user::destroy(&id, &mut connection).await {
         error!("{err}");
         return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
@@ -0,0 +1,353 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use chrono::{Duration, Utc};
+use log::error;
+use sqlx::{Acquire, PgConnection};
+use uuid::Uuid;
+
+use crate::{
+    extractors::{cart::CurrentCart, session::OptionalUser},
+    models::{session::PopulatedSession, wishlist::Wishlist},
+    services,
+    utils::{client, constants::WISHLIST_COOKIE_NAME, env::Env},
+    validations::{
+        cart::{StoreCartItemSchema, StoreCartSchema},
+        wishlist::{MoveWishlistItemSchema, StoreWishlistItemSchema},
+        ValidatedForm,
+    },
+    AppState,
+};
+
+// signed in users own a single wishlist, guests are tracked through a cookie
+async fn current(
+    env: &Env,
+    headers: &HeaderMap,
+    auth: &Option<PopulatedSession>,
+    connection: &mut PgConnection,
+) -> Result<Option<Wishlist>, sqlx::Error> {
+    let guest_id = client::cookie_id(env, headers, WISHLIST_COOKIE_NAME);
+
+    // what was saved before signing in carries over
+    if let Some(auth) = auth {
+        return match guest_id {
+            Some(guest_id) => services::wishlist::claim(&guest_id, &auth.user.id, connection).await,
+            None => services::wishlist::find_by_user_id(&auth.user.id, connection).await,
+        };
+    }
+
+    let wishlist = match guest_id {
+        Some(wishlist_id) => services::wishlist::find(&wishlist_id, connection).await?,
+        None => None,
+    };
+
+    // a guest cookie must never reach a user's wishlist
+    Ok(wishlist.filter(|wishlist| wishlist.user_id.is_none()))
+}
+
+pub async fn index(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    OptionalUser(auth): OptionalUser,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let wishlist = match current(&state.env, &headers, &auth, &mut connection).await {
+        Ok(wishlist) => wishlist,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let items = match wishlist {
+        Some(wishlist) => match services::wishlist::items(&wishlist.id, &mut connection).await {
+            Ok(items) => items,
+            Err(err) => {
+                error!("{err}");
+                return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+            }
+        },
+        None => Vec::new(),
+    };
+
+    Json(items).into_response()
+}
+
+pub async fn add_item(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    OptionalUser(auth): OptionalUser,
+    ValidatedForm(input): ValidatedForm<StoreWishlistItemSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let variant = match input.variant_id {
+        Some(variant_id) => {
+            match services::product::find_variant(&variant_id, &mut connection).await {
+                Ok(Some(variant)) => Some(variant),
+                Ok(None) => {
+                    return (StatusCode::NOT_FOUND).into_response();
+                }
+                Err(err) => {
+                    error!("{err}");
+                    return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+                }
+            }
+        }
+        None => None,
+    };
+
+    let product_id = match (input.product_id, &variant) {
+        (Some(product_id), Some(variant)) if product_id != variant.product_id => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json("Variant doesn't belong to product"),
+            )
+                .into_response();
+        }
+        (_, Some(variant)) => variant.product_id,
+        (Some(product_id), None) => product_id,
+        (None, None) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json("Product or variant is required"),
+            )
+                .into_response();
+        }
+    };
+
+    match services::product::exists(&product_id, &mut connection).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (StatusCode::NOT_FOUND).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let wishlist = match current(&state.env, &headers, &auth, &mut connection).await {
+        Ok(wishlist) => wishlist,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let wishlist = match wishlist {
+        Some(wishlist) => wishlist,
+        None => {
+            let user_id = auth.as_ref().map(|auth| auth.user.id);
+
+            match services::wishlist::insert(user_id, &mut connection).await {
+                Ok(wishlist) => wishlist,
+                Err(err) => {
+                    error!("{err}");
+                    return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+                }
+            }
+        }
+    };
+
+    let item = match services::wishlist::add_item(
+        &wishlist.id,
+        &product_id,
+        variant.map(|variant| variant.id),
+        &mut connection,
+    )
+    .await
+    {
+        Ok(item) => item,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    (
+        StatusCode::CREATED,
+        [(
+            header::SET_COOKIE,
+            client::wishlist_cookie(&state.env, &wishlist.id),
+        )],
+        Json(item),
+    )
+        .into_response()
+}
+
+pub async fn delete_item(
+    Path(item_id): Path<Uuid>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    OptionalUser(auth): OptionalUser,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let wishlist = match current(&state.env, &headers, &auth, &mut connection).await {
+        Ok(Some(wishlist)) => wishlist,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    if let Err(err) = services::wishlist::delete_item(&wishlist.id, &item_id, &mut connection).await
+    {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    (StatusCode::NO_CONTENT).into_response()
+}
+
+pub async fn move_to_cart(
+    Path(item_id): Path<Uuid>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    OptionalUser(auth): OptionalUser,
+    CurrentCart(cart): CurrentCart,
+    ValidatedForm(input): ValidatedForm<MoveWishlistItemSchema>,
+) -> impl IntoResponse {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let wishlist = match current(&state.env, &headers, &auth, &mut connection).await {
+        Ok(Some(wishlist)) => wishlist,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let item = match services::wishlist::find_item(&wishlist.id, &item_id, &mut connection).await {
+        Ok(Some(item)) => item,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND).into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    // a wishlisted product needs a variant picked before it can go to the cart
+    let variant_id = match item.variant_id.or(input.variant_id) {
+        Some(variant_id) => variant_id,
+        None => {
+            return (StatusCode::BAD_REQUEST, Json("Variant is required")).into_response();
+        }
+    };
+
+    match services::product::find_variant(&variant_id, &mut connection).await {
+        Ok(Some(variant)) if variant.product_id == item.product_id => {}
+        Ok(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json("Variant doesn't belong to product"),
+            )
+                .into_response();
+        }
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let mut tx = match connection.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let cart = match cart {
+        Some(cart) => cart,
+        None => {
+            let input = StoreCartSchema {
+                user_id: auth.as_ref().map(|auth| auth.user.id),
+            };
+
+            match services::cart::insert(&input, &mut tx).await {
+                Ok(cart) => cart,
+                Err(err) => {
+                    error!("{err}");
+                    if let Err(err) = tx.rollback().await {
+                        error!("{err}");
+                    }
+                    return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+                }
+            }
+        }
+    };
+
+    let cart_item = StoreCartItemSchema {
+        variant_id: Some(variant_id),
+        bundle_id: None,
+        quantity: input.quantity,
+    };
+
+    let expires_at = Utc::now().naive_utc() + Duration::seconds(state.env.reservation_ttl);
+
+    if let Err(err) = services::cart::add_item(&cart.cart.id, &cart_item, expires_at, &mut tx).await
+    {
+        error!("{err}");
+        if let Err(err) = tx.rollback().await {
+            error!("{err}");
+        }
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    if let Err(err) = services::wishlist::delete_item(&wishlist.id, &item.id, &mut tx).await {
+        error!("{err}");
+        if let Err(err) = tx.rollback().await {
+            error!("{err}");
+        }
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    if let Err(err) = tx.commit().await {
+        error!("{err}");
+        return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    ([(
+        header::SET_COOKIE,
+        client::cart_cookie(&state.env, &cart.cart.id),
+    )])
+    .into_response()
+}
@@ -0,0 +1,67 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+};
+use log::error;
+
+use crate::{
+    models::cart::PopulatedCart,
+    services,
+    utils::{client, constants::CART_COOKIE_NAME},
+    AppState,
+};
+
+use super::session::OptionalUser;
+
+// the signed in user's cart first, otherwise whatever cart the cookie points at
+pub struct CurrentCart(pub Option<PopulatedCart>);
+
+#[async_trait]
+impl FromRequestParts<AppState> for CurrentCart {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let OptionalUser(auth) = OptionalUser::from_request_parts(parts, state).await?;
+        let user_id = auth.map(|auth| auth.user.id);
+
+        let mut connection = match state.db.acquire().await {
+            Ok(connection) => connection,
+            Err(err) => {
+                error!("{err}");
+                return Err((StatusCode::INTERNAL_SERVER_ERROR).into_response());
+            }
+        };
+
+        if let Some(user_id) = &user_id {
+            match services::cart::find_by_user_id(user_id, &mut connection).await {
+                Ok(Some(cart)) => return Ok(CurrentCart(Some(cart))),
+                Ok(None) => {}
+                Err(err) => {
+                    error!("{err}");
+                    return Err((StatusCode::INTERNAL_SERVER_ERROR).into_response());
+                }
+            }
+        }
+
+        let cart = match client::cookie_id(&state.env, &parts.headers, CART_COOKIE_NAME) {
+            Some(cart_id) => match services::cart::find(&cart_id, &mut connection).await {
+                Ok(cart) => cart,
+                Err(err) => {
+                    error!("{err}");
+                    return Err((StatusCode::INTERNAL_SERVER_ERROR).into_response());
+                }
+            },
+            None => None,
+        };
+
+        // a cookie can only reach a guest cart or the caller's own
+        Ok(CurrentCart(cart.filter(|cart| {
+            cart.cart.user_id.is_none() || cart.cart.user_id == user_id
+        })))
+    }
+}
@@ -0,0 +1,80 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use log::error;
+
+use crate::{
+    models::session::PopulatedSession,
+    services,
+    utils::{client, constants::SESSION_COOKIE_NAME},
+    AppState,
+};
+
+pub struct OptionalUser(pub Option<PopulatedSession>);
+
+pub struct CurrentUser(pub PopulatedSession);
+
+#[async_trait]
+impl FromRequestParts<AppState> for OptionalUser {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        // the auth middlewares have usually resolved it already
+        if let Some(session) = parts.extensions.get::<PopulatedSession>() {
+            return Ok(OptionalUser(Some(session.clone())));
+        }
+
+        if let Some(session) = parts.extensions.get::<Option<PopulatedSession>>() {
+            return Ok(OptionalUser(session.clone()));
+        }
+
+        let session = match client::cookie_id(&state.env, &parts.headers, SESSION_COOKIE_NAME) {
+            Some(session) => session,
+            None => return Ok(OptionalUser(None)),
+        };
+
+        let mut connection = match state.db.acquire().await {
+            Ok(connection) => connection,
+            Err(err) => {
+                error!("{err}");
+                return Err((StatusCode::INTERNAL_SERVER_ERROR).into_response());
+            }
+        };
+
+        let session = match services::sessions::find_by_session(&session, &mut connection).await {
+            Ok(session) => session,
+            Err(err) => {
+                error!("{err}");
+                return Err((StatusCode::INTERNAL_SERVER_ERROR).into_response());
+            }
+        };
+
+        parts.extensions.insert(session.clone());
+
+        Ok(OptionalUser(session))
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for CurrentUser {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        match OptionalUser::from_request_parts(parts, state).await? {
+            OptionalUser(Some(session)) => Ok(CurrentUser(session)),
+            OptionalUser(None) => {
+                Err((StatusCode::UNAUTHORIZED, Json("Missing session")).into_response())
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use log::{error, info};
+
+use crate::{
+    services,
+    utils::constants::{IMAGE_ORPHAN_GRACE, IMAGE_SWEEP_INTERVAL},
+    State,
+};
+
+pub async fn sweep(state: State) {
+    let mut interval = tokio::time::interval(Duration::from_secs(IMAGE_SWEEP_INTERVAL));
+
+    loop {
+        interval.tick().await;
+
+        let mut connection = match state.db.acquire().await {
+            Ok(connection) => connection,
+            Err(err) => {
+                error!("{err}");
+                continue;
+            }
+        };
+
+        let created_before = Utc::now().naive_utc() - chrono::Duration::seconds(IMAGE_ORPHAN_GRACE);
+
+        let removed =
+            services::image::sweep(&created_before, state.storage.as_ref(), &mut connection).await;
+
+        match removed {
+            Ok(0) => {}
+            Ok(removed) => info!("removed {removed} orphan images"),
+            Err(err) => error!("{err}"),
+        }
+    }
+}
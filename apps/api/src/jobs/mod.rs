@@ -0,0 +1,4 @@
+pub mod image;
+pub mod rate_limit;
+pub mod reservation;
+pub mod two_factor;
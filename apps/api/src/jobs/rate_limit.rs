@@ -0,0 +1,26 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use log::{error, info};
+
+use crate::{
+    utils::constants::{RATE_LIMIT_IDLE, RATE_LIMIT_SWEEP_INTERVAL},
+    State,
+};
+
+// idle buckets are full again, dropping them only keeps the backend small
+pub async fn sweep(state: State) {
+    let mut interval = tokio::time::interval(Duration::from_secs(RATE_LIMIT_SWEEP_INTERVAL));
+
+    loop {
+        interval.tick().await;
+
+        let before = Utc::now().naive_utc() - chrono::Duration::seconds(RATE_LIMIT_IDLE);
+
+        match state.rate_limit.sweep(before).await {
+            Ok(0) => {}
+            Ok(removed) => info!("dropped {removed} idle rate limit buckets"),
+            Err(err) => error!("{err}"),
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use log::{error, info};
+
+use crate::{services, utils::constants::RESERVATION_SWEEP_INTERVAL, State};
+
+// expired holds already stop counting, this only keeps the table small
+pub async fn sweep(state: State) {
+    let mut interval = tokio::time::interval(Duration::from_secs(RESERVATION_SWEEP_INTERVAL));
+
+    loop {
+        interval.tick().await;
+
+        let mut connection = match state.db.acquire().await {
+            Ok(connection) => connection,
+            Err(err) => {
+                error!("{err}");
+                continue;
+            }
+        };
+
+        let now = Utc::now().naive_utc();
+
+        match services::stock::sweep(&now, &mut connection).await {
+            Ok(0) => {}
+            Ok(removed) => info!("released {removed} expired reservations"),
+            Err(err) => error!("{err}"),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use log::{error, info};
+
+use crate::{services, utils::totp, State};
+
+// runs once on startup for factors enrolled before secrets were stored sealed
+pub async fn seal(state: State) {
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return;
+        }
+    };
+
+    let factors = match services::two_factor::unsealed(&mut connection).await {
+        Ok(factors) => factors,
+        Err(err) => {
+            error!("{err}");
+            return;
+        }
+    };
+
+    let mut sealed = 0;
+
+    for factor in factors {
+        let secret = totp::seal(&state.env.app_secret, &factor.secret);
+
+        match services::two_factor::seal(&factor.user_id, &factor.secret, &secret, &mut connection)
+            .await
+        {
+            Ok(_) => sealed += 1,
+            Err(err) => error!("{err}"),
+        }
+    }
+
+    if sealed > 0 {
+        info!("sealed {sealed} two-factor secrets");
+    }
+}
@@ -1,15 +1,20 @@
 use std::sync::Arc;
 
 use axum::{
-    http::{header::CONTENT_TYPE, HeaderValue, Method},
+    http::{header::CONTENT_TYPE, HeaderName, HeaderValue, Method},
     middleware::from_fn_with_state,
     Router,
 };
 use routers::{admin, auth, public};
 use tower_http::cors::CorsLayer;
-use utils::{db::DB, env::Env, mailer::Mail};
+use utils::{
+    constants::CSRF_HEADER_NAME, db::DB, env::Env, mailer::Mail, rate_limit::RateLimit,
+    storage::Storage,
+};
 
 pub mod controllers;
+pub mod extractors;
+pub mod jobs;
 pub mod middlewares;
 pub mod models;
 pub mod routers;
@@ -22,6 +27,9 @@ pub struct State {
     pub db: DB,
     pub env: Env,
     pub mailer: Box<dyn Mail>,
+    pub storage: Box<dyn Storage>,
+    pub private_storage: Box<dyn Storage>,
+    pub rate_limit: Box<dyn RateLimit>,
 }
 
 pub type AppState = Arc<State>;
@@ -41,8 +49,14 @@ pub fn create_app(state: State) -> Router {
         .layer(
             CorsLayer::new()
                 .allow_origin(state.env.client_url.parse::<HeaderValue>().unwrap())
-                .allow_methods([Method::GET, Method::POST, Method::PATCH, Method::DELETE])
-                .allow_headers([CONTENT_TYPE])
+                .allow_methods([
+                    Method::GET,
+                    Method::POST,
+                    Method::PUT,
+                    Method::PATCH,
+                    Method::DELETE,
+                ])
+                .allow_headers([CONTENT_TYPE, HeaderName::from_static(CSRF_HEADER_NAME)])
                 .allow_credentials(true),
         )
         .route_layer(from_fn_with_state(state, middlewares::setup::middleware))
@@ -1,7 +1,9 @@
+use std::net::SocketAddr;
+
 use resend_rs::Resend;
 use rumerce::{
-    create_app,
-    utils::{db, env, mailer::Mailer},
+    create_app, jobs,
+    utils::{db, env, mailer::Mailer, rate_limit, storage},
     State,
 };
 
@@ -15,7 +17,23 @@ async fn main() {
         client: Resend::new(&env.resend_token),
     });
 
-    let state = State { env, db, mailer };
+    let storage = storage::init(&env);
+    let private_storage = storage::init_private(&env);
+    let rate_limit = rate_limit::init(&env, &db);
+
+    let state = State {
+        env,
+        db,
+        mailer,
+        storage,
+        private_storage,
+        rate_limit,
+    };
+
+    tokio::spawn(jobs::image::sweep(state.clone()));
+    tokio::spawn(jobs::reservation::sweep(state.clone()));
+    tokio::spawn(jobs::rate_limit::sweep(state.clone()));
+    tokio::spawn(jobs::two_factor::seal(state.clone()));
 
     let app = create_app(state.clone());
 
@@ -23,5 +41,10 @@ async fn main() {
     let listener = tokio::net::TcpListener::bind(&state.env.app_url)
         .await
         .unwrap();
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 }
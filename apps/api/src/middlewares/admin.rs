@@ -1,21 +1,67 @@
 use axum::{
-    extract::Request,
+    extract::{Request, State},
     http::StatusCode,
     middleware::Next,
     response::{IntoResponse, Response},
-    Extension,
+    Json,
 };
+use log::error;
 
-use crate::{models::session::PopulatedSession, utils::constants::ROLES};
+use crate::{extractors::session::CurrentUser, services, AppState};
 
 pub async fn admin_middleware(
-    Extension(session): Extension<PopulatedSession>,
+    State(state): State<AppState>,
+    CurrentUser(session): CurrentUser,
     request: Request,
     next: Next,
 ) -> Response {
-    if session.role.name != ROLES.admin {
+    // staff are users whose role grants anything, each route group checks its own permission
+    if session.permissions.is_empty() {
         return (StatusCode::FORBIDDEN).into_response();
     }
 
+    let mut connection = match state.db.acquire().await {
+        Ok(connection) => connection,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let factor = match services::two_factor::find(&session.user.id, &mut connection).await {
+        Ok(factor) => factor,
+        Err(err) => {
+            error!("{err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    // staff with an authenticator has to have used it on this session
+    let enrolled = factor.is_some_and(|factor| factor.confirmed_at.is_some());
+    let required = match enrolled {
+        true => false,
+        false => match services::two_factor::required(&mut connection).await {
+            Ok(required) => required,
+            Err(err) => {
+                error!("{err}");
+                return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+            }
+        },
+    };
+
+    drop(connection);
+
+    if enrolled && session.session.second_factor_at.is_none() {
+        return (StatusCode::FORBIDDEN, Json("Second factor required")).into_response();
+    }
+
+    if required {
+        return (
+            StatusCode::FORBIDDEN,
+            Json("Two-factor enrollment required"),
+        )
+            .into_response();
+    }
+
     next.run(request).await
 }
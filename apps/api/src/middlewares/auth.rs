@@ -1,15 +1,23 @@
 use axum::{
-    extract::{Request, State},
-    http::{header, HeaderMap, StatusCode},
+    extract::{FromRequestParts, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
-    Json,
+    Extension, Json,
 };
-use cookie::Cookie;
+use chrono::{NaiveDateTime, Utc};
 use log::error;
-use uuid::Uuid;
 
-use crate::{services, AppState};
+use crate::{
+    extractors::session::CurrentUser,
+    models::{
+        api_key::ApiKey,
+        session::{PopulatedSession, Session},
+    },
+    services,
+    utils::{api_key, client},
+    AppState,
+};
 
 pub async fn auth_middleware(
     State(state): State<AppState>,
@@ -17,51 +25,37 @@ pub async fn auth_middleware(
     mut request: Request,
     next: Next,
 ) -> Response {
-    let cookies = match headers.get(header::COOKIE) {
-        Some(cookies) => cookies,
-        None => {
-            return (StatusCode::UNAUTHORIZED, Json("Missing session")).into_response();
-        }
-    };
-
-    let cookies = match cookies.to_str() {
-        Ok(cookies) => cookies,
-        Err(_) => {
-            return (StatusCode::UNAUTHORIZED, Json("Invalid cookies")).into_response();
-        }
-    };
-
-    let cookies: Vec<&str> = cookies.split("; ").collect();
-    let mut target_cookie: Option<Cookie> = None;
-
-    for cookie in cookies {
-        let cookie = match Cookie::parse(cookie) {
-            Ok(cookie) => cookie,
-            Err(_) => {
-                return (StatusCode::UNAUTHORIZED, "Invalid cookies").into_response();
+    if let Some(authorization) = headers.get(header::AUTHORIZATION) {
+        let key = match authorization.to_str().ok().and_then(api_key::bearer) {
+            Some(key) => key,
+            None => {
+                return (StatusCode::UNAUTHORIZED, Json("Invalid authorization")).into_response();
             }
         };
 
-        if cookie.name() == "session" {
-            target_cookie = Some(cookie);
-            break;
-        }
+        return match api_key_session(&state, key, &headers).await {
+            Ok(Some((api_key, session))) => {
+                request.extensions_mut().insert(session);
+                request.extensions_mut().insert(api_key);
+                next.run(request).await
+            }
+            Ok(None) => (StatusCode::UNAUTHORIZED, Json("Invalid API key")).into_response(),
+            Err(err) => {
+                error!("{err}");
+                (StatusCode::INTERNAL_SERVER_ERROR).into_response()
+            }
+        };
     }
 
-    let session_cookie = match target_cookie {
-        Some(target_cookie) => target_cookie,
-        None => {
-            return (StatusCode::UNAUTHORIZED, Json("Session not found")).into_response();
-        }
-    };
+    let (mut parts, body) = request.into_parts();
 
-    let session = match Uuid::parse_str(session_cookie.value()) {
-        Ok(session) => session,
-        Err(_) => {
-            return (StatusCode::UNAUTHORIZED, Json("Invalid session")).into_response();
-        }
+    let session = match CurrentUser::from_request_parts(&mut parts, &state).await {
+        Ok(CurrentUser(session)) => session,
+        Err(rejection) => return rejection,
     };
 
+    let mut request = Request::from_parts(parts, body);
+
     let mut connection = match state.db.acquire().await {
         Ok(connection) => connection,
         Err(err) => {
@@ -70,17 +64,99 @@ pub async fn auth_middleware(
         }
     };
 
-    let session = match services::sessions::find_by_session(&session, &mut connection).await {
-        Ok(Some(session)) => session,
-        Ok(None) => {
-            return (StatusCode::UNAUTHORIZED).into_response();
-        }
+    let renewed = match services::sessions::renew(&session.session, &mut connection).await {
+        Ok(renewed) => renewed,
         Err(err) => {
             error!("{err}");
-            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+            false
         }
     };
 
+    drop(connection);
+
+    let cookie = client::session_cookie(&state.env, &session.session.session);
+
     request.extensions_mut().insert(session);
+    let mut response = next.run(request).await;
+
+    if renewed {
+        if let Ok(cookie) = HeaderValue::from_str(&cookie) {
+            response.headers_mut().append(header::SET_COOKIE, cookie);
+        }
+    }
+
+    response
+}
+
+// stands in for a session so handlers don't care how the caller signed in
+async fn api_key_session(
+    state: &AppState,
+    key: &str,
+    headers: &HeaderMap,
+) -> Result<Option<(ApiKey, PopulatedSession)>, sqlx::Error> {
+    let mut connection = state.db.acquire().await?;
+    let now = Utc::now().naive_utc();
+
+    let key_hash = api_key::hash(key);
+
+    let api_key = match services::api_key::find_by_hash(&key_hash, now, &mut connection).await? {
+        Some(api_key) => api_key,
+        None => return Ok(None),
+    };
+
+    let owner = match services::api_key::find_owner(&api_key.user_id, &mut connection).await? {
+        Some(owner) => owner,
+        None => return Ok(None),
+    };
+
+    // the owner's authenticator as it is now, a key only counts as a second factor when it was
+    // made after the authenticator was confirmed
+    let second_factor_at = services::two_factor::find(&owner.user.id, &mut connection)
+        .await?
+        .and_then(|factor| factor.confirmed_at)
+        .filter(|confirmed_at| *confirmed_at <= api_key.created_at)
+        .map(|_| api_key.created_at);
+
+    services::api_key::touch(&api_key.id, now, &mut connection).await?;
+
+    let permissions = owner
+        .permissions
+        .into_iter()
+        .filter(|permission| api_key.scopes.contains(permission))
+        .collect();
+
+    let session = Session {
+        id: api_key.id,
+        session: api_key.id,
+        user_id: api_key.user_id,
+        created_at: api_key.created_at,
+        expires_at: api_key.expires_at.unwrap_or(NaiveDateTime::MAX),
+        last_seen_at: now,
+        user_agent: client::user_agent(headers),
+        ip: None,
+        second_factor_at,
+    };
+
+    Ok(Some((
+        api_key,
+        PopulatedSession {
+            session,
+            user: owner.user,
+            role: owner.role,
+            permissions,
+        },
+    )))
+}
+
+// personal routes act on the signed in user, a key only acts within its scopes
+pub async fn session_only_middleware(
+    api_key: Option<Extension<ApiKey>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if api_key.is_some() {
+        return (StatusCode::FORBIDDEN, Json("API keys can't be used here")).into_response();
+    }
+
     next.run(request).await
 }
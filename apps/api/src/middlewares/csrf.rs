@@ -0,0 +1,52 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::{
+    models::api_key::ApiKey,
+    utils::{
+        client,
+        constants::{CSRF_COOKIE_NAME, CSRF_HEADER_NAME},
+        signing,
+    },
+    AppState,
+};
+
+pub async fn middleware(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    request: Request,
+    next: Next,
+) -> Response {
+    if request.method().is_safe() {
+        return next.run(request).await;
+    }
+
+    // without cookies there is nothing a forged request could ride on, and a key the auth
+    // middleware already accepted is sent on purpose, a bare authorization header proves nothing
+    if !headers.contains_key(header::COOKIE) || request.extensions().get::<ApiKey>().is_some() {
+        return next.run(request).await;
+    }
+
+    let cookie = match client::cookie(&headers, CSRF_COOKIE_NAME) {
+        Some(cookie) if !cookie.is_empty() => cookie,
+        _ => return (StatusCode::FORBIDDEN, Json("Missing CSRF token")).into_response(),
+    };
+
+    let token = headers
+        .get(CSRF_HEADER_NAME)
+        .and_then(|token| token.to_str().ok())
+        .unwrap_or_default();
+
+    // compared through their macs so the check takes the same time wherever they differ
+    let secret = &state.env.app_secret;
+    if !signing::verify(secret, token, &signing::sign(secret, &cookie)) {
+        return (StatusCode::FORBIDDEN, Json("Invalid CSRF token")).into_response();
+    }
+
+    next.run(request).await
+}
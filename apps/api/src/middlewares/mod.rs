@@ -1,4 +1,9 @@
+// middlewares hand their connection back before running the next layer, a request holding one
+// for the whole handler while the handler waits on another starves the pool under load
 pub mod admin;
 pub mod auth;
+pub mod csrf;
 pub mod optional_auth;
+pub mod permission;
+pub mod rate_limit;
 pub mod setup;
@@ -1,74 +1,26 @@
 use axum::{
-    extract::{Request, State},
-    http::{header, HeaderMap, StatusCode},
+    extract::{FromRequestParts, Request, State},
+    http::{header, HeaderValue, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
 };
-use cookie::Cookie;
 use log::error;
-use uuid::Uuid;
 
-use crate::{models::session::PopulatedSession, services, AppState};
+use crate::{extractors::session::OptionalUser, services, utils::client, AppState};
 
-pub async fn middleware(
-    State(state): State<AppState>,
-    headers: HeaderMap,
-    mut request: Request,
-    next: Next,
-) -> Response {
-    let cookies = match headers.get(header::COOKIE) {
-        Some(cookies) => cookies,
-        None => {
-            let session: Option<PopulatedSession> = None;
-            request.extensions_mut().insert(session);
-            return next.run(request).await;
-        }
-    };
+pub async fn middleware(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let (mut parts, body) = request.into_parts();
 
-    let cookies = match cookies.to_str() {
-        Ok(cookies) => cookies,
-        Err(_) => {
-            let session: Option<PopulatedSession> = None;
-            request.extensions_mut().insert(session);
-            return next.run(request).await;
-        }
+    let session = match OptionalUser::from_request_parts(&mut parts, &state).await {
+        Ok(OptionalUser(session)) => session,
+        Err(rejection) => return rejection,
     };
 
-    let cookies: Vec<&str> = cookies.split("; ").collect();
-    let mut target_cookie: Option<Cookie> = None;
+    let mut request = Request::from_parts(parts, body);
 
-    for cookie in cookies {
-        let cookie = match Cookie::parse(cookie) {
-            Ok(cookie) => cookie,
-            Err(_) => {
-                let session: Option<PopulatedSession> = None;
-                request.extensions_mut().insert(session);
-                return next.run(request).await;
-            }
-        };
-
-        if cookie.name() == "session" {
-            target_cookie = Some(cookie);
-            break;
-        }
-    }
-
-    let session_cookie = match target_cookie {
-        Some(target_cookie) => target_cookie,
-        None => {
-            let session: Option<PopulatedSession> = None;
-            request.extensions_mut().insert(session);
-            return next.run(request).await;
-        }
-    };
-
-    let session = match Uuid::parse_str(session_cookie.value()) {
-        Ok(session) => session,
-        Err(_) => {
-            let session: Option<PopulatedSession> = None;
-            request.extensions_mut().insert(session);
-            return next.run(request).await;
-        }
+    let session = match session {
+        Some(session) => session,
+        None => return next.run(request).await,
     };
 
     let mut connection = match state.db.acquire().await {
@@ -79,15 +31,26 @@ pub async fn middleware(
         }
     };
 
-    let session = match services::sessions::find_by_session(&session, &mut connection).await {
-        Ok(session) => session,
+    let renewed = match services::sessions::renew(&session.session, &mut connection).await {
+        Ok(renewed) => renewed,
         Err(err) => {
             error!("{err}");
-            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+            false
         }
     };
 
-    request.extensions_mut().insert(session);
+    drop(connection);
+
+    let cookie = client::session_cookie(&state.env, &session.session.session);
+
+    request.extensions_mut().insert(Some(session));
+    let mut response = next.run(request).await;
+
+    if renewed {
+        if let Ok(cookie) = HeaderValue::from_str(&cookie) {
+            response.headers_mut().append(header::SET_COOKIE, cookie);
+        }
+    }
 
-    next.run(request).await
+    response
 }
@@ -0,0 +1,95 @@
+use std::marker::PhantomData;
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::{models::session::PopulatedSession, utils::constants::PERMISSIONS};
+
+pub trait Permission {
+    const NAME: &'static str;
+}
+
+// layered per route group with from_extractor, after auth has populated the session
+pub struct RequirePermission<P>(PhantomData<P>);
+
+#[async_trait]
+impl<P, S> FromRequestParts<S> for RequirePermission<P>
+where
+    P: Permission,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let session = match parts.extensions.get::<PopulatedSession>() {
+            Some(session) => session,
+            None => return Err((StatusCode::UNAUTHORIZED).into_response()),
+        };
+
+        if !session.permissions.iter().any(|name| name == P::NAME) {
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(format!("Missing permission {}", P::NAME)),
+            )
+                .into_response());
+        }
+
+        Ok(RequirePermission(PhantomData))
+    }
+}
+
+pub struct RolesManage;
+pub struct UsersManage;
+pub struct PricingWrite;
+pub struct CatalogWrite;
+pub struct ProductsWrite;
+pub struct ReviewsModerate;
+pub struct StockManage;
+pub struct OrdersManage;
+pub struct SettingsManage;
+pub struct ApiKeysManage;
+
+impl Permission for RolesManage {
+    const NAME: &'static str = PERMISSIONS.roles_manage;
+}
+
+impl Permission for UsersManage {
+    const NAME: &'static str = PERMISSIONS.users_manage;
+}
+
+impl Permission for PricingWrite {
+    const NAME: &'static str = PERMISSIONS.pricing_write;
+}
+
+impl Permission for CatalogWrite {
+    const NAME: &'static str = PERMISSIONS.catalog_write;
+}
+
+impl Permission for ProductsWrite {
+    const NAME: &'static str = PERMISSIONS.products_write;
+}
+
+impl Permission for ReviewsModerate {
+    const NAME: &'static str = PERMISSIONS.reviews_moderate;
+}
+
+impl Permission for StockManage {
+    const NAME: &'static str = PERMISSIONS.stock_manage;
+}
+
+impl Permission for OrdersManage {
+    const NAME: &'static str = PERMISSIONS.orders_manage;
+}
+
+impl Permission for SettingsManage {
+    const NAME: &'static str = PERMISSIONS.settings_manage;
+}
+
+impl Permission for ApiKeysManage {
+    const NAME: &'static str = PERMISSIONS.api_keys_manage;
+}
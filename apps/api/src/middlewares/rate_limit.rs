@@ -0,0 +1,116 @@
+use std::net::SocketAddr;
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{ConnectInfo, Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use log::error;
+
+use crate::{
+    models::session::PopulatedSession,
+    utils::{
+        client,
+        constants::RATE_LIMIT_BODY_LIMIT,
+        rate_limit::{RateLimitKey, RateLimitPolicy},
+    },
+    AppState,
+};
+
+#[derive(Clone)]
+pub struct RateLimited {
+    pub state: AppState,
+    pub policies: &'static [RateLimitPolicy],
+}
+
+impl RateLimited {
+    pub fn new(state: AppState, policies: &'static [RateLimitPolicy]) -> Self {
+        RateLimited { state, policies }
+    }
+}
+
+pub async fn middleware(
+    State(limited): State<RateLimited>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(peer)| *peer);
+    let ip =
+        client::trusted_ip(&limited.state.env, request.headers(), peer).map(|ip| ip.to_string());
+    let user = request
+        .extensions()
+        .get::<PopulatedSession>()
+        .map(|session| session.user.id.to_string());
+
+    let (request, email) = if limited
+        .policies
+        .iter()
+        .any(|policy| policy.key == RateLimitKey::Email)
+    {
+        let (parts, body) = request.into_parts();
+
+        let bytes = match to_bytes(body, RATE_LIMIT_BODY_LIMIT).await {
+            Ok(bytes) => bytes,
+            Err(_) => return (StatusCode::PAYLOAD_TOO_LARGE).into_response(),
+        };
+
+        // the handler still validates the body, this only needs the address if there is one
+        let email = serde_json::from_slice::<serde_json::Value>(&bytes)
+            .ok()
+            .and_then(|body| {
+                body.get("email")?
+                    .as_str()
+                    .map(|email| email.trim().to_lowercase())
+            })
+            .filter(|email| !email.is_empty());
+
+        (Request::from_parts(parts, Body::from(bytes)), email)
+    } else {
+        (request, None)
+    };
+
+    let now = Utc::now().naive_utc();
+    let mut retry_after: Option<i64> = None;
+
+    for policy in limited.policies {
+        let value = match policy.key {
+            RateLimitKey::Ip => ip.as_ref(),
+            RateLimitKey::Email => email.as_ref(),
+            RateLimitKey::User => user.as_ref(),
+        };
+
+        // nothing to tell this caller apart by
+        let Some(value) = value else {
+            continue;
+        };
+
+        let key = format!("{}:{}", policy.name, value);
+
+        match limited.state.rate_limit.take(&key, policy, now).await {
+            Ok(None) => {}
+            Ok(Some(wait)) => retry_after = Some(retry_after.unwrap_or(0).max(wait)),
+            Err(err) => {
+                error!("{err}");
+                return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+            }
+        }
+    }
+
+    if let Some(retry_after) = retry_after {
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, retry_after.to_string())],
+            Json("Too many requests"),
+        )
+            .into_response();
+    }
+
+    next.run(request).await
+}
@@ -23,6 +23,8 @@ pub async fn middleware(State(state): State<AppState>, request: Request, next: N
 
     let setup = services::settings::find_by_key(SETTINGS.setup, &mut connection).await;
 
+    drop(connection);
+
     if let Ok(None) = setup {
         return (StatusCode::FAILED_DEPENDENCY).into_response();
     }
@@ -0,0 +1,38 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow, Serialize, sqlx::Type)]
+pub struct Bundle {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub price: f32,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, sqlx::Type)]
+pub struct BundleItem {
+    pub id: Uuid,
+    pub quantity: i32,
+    pub bundle_id: Uuid,
+    pub variant_id: Uuid,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct BundleComponent {
+    pub bundle_id: Uuid,
+    pub variant_id: Uuid,
+    pub quantity: i32,
+    pub product_id: Uuid,
+    pub product_name: String,
+    pub sku: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PopulatedBundle {
+    #[serde(flatten)]
+    pub bundle: Bundle,
+    pub components: Vec<BundleComponent>,
+}
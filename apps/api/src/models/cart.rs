@@ -1,7 +1,10 @@
+use chrono::NaiveDateTime;
 use serde::Serialize;
 use sqlx::prelude::FromRow;
 use uuid::Uuid;
 
+use super::bundle::BundleComponent;
+
 #[derive(FromRow, Serialize, sqlx::Type)]
 #[sqlx(type_name = "carts")]
 pub struct Cart {
@@ -22,4 +25,25 @@ pub struct CartItem {
     pub quantity: Option<i32>,
     pub cart_id: Option<Uuid>,
     pub variant_id: Option<Uuid>,
+    pub bundle_id: Option<Uuid>,
+}
+
+#[derive(Serialize)]
+pub struct CartLine {
+    #[serde(flatten)]
+    pub item: CartItem,
+    // what a bundle line is made of, empty for single variants
+    pub components: Vec<BundleComponent>,
+    pub unit_price: f32,
+    pub total: f32,
+    pub currency: String,
+}
+
+#[derive(Serialize)]
+pub struct Checkout {
+    pub cart_id: Uuid,
+    pub expires_at: NaiveDateTime,
+    pub order_id: Uuid,
+    pub currency: String,
+    pub total: f32,
 }
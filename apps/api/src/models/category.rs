@@ -37,8 +37,8 @@ impl Category {
             .await
     }
 
-    pub async fn insert<'a>(
-        input: StoreCategorySchema<'a>,
+    pub async fn insert(
+        input: StoreCategorySchema,
         connection: &mut PgConnection,
     ) -> Result<Category, sqlx::Error> {
         sqlx::query_as!(
@@ -50,8 +50,8 @@ impl Category {
         .await
     }
 
-    pub async fn insert_many<'a>(
-        input: Vec<StoreCategorySchema<'a>>,
+    pub async fn insert_many(
+        input: Vec<StoreCategorySchema>,
         connection: &mut PgConnection,
     ) -> Result<Category, sqlx::Error> {
         sqlx::query_as!(
@@ -66,9 +66,9 @@ impl Category {
         .await
     }
 
-    pub async fn update<'a>(
+    pub async fn update(
         id: &Uuid,
-        input: StoreCategorySchema<'a>,
+        input: StoreCategorySchema,
         connection: &mut PgConnection,
     ) -> Result<PgQueryResult, sqlx::Error> {
         sqlx::query!(
@@ -92,7 +92,9 @@ impl Category {
     pub async fn save(&self, connection: &mut PgConnection) -> Result<PgQueryResult, sqlx::Error> {
         Category::update(
             &self.id,
-            StoreCategorySchema { name: &self.name },
+            StoreCategorySchema {
+                name: self.name.clone(),
+            },
             connection,
         )
         .await
@@ -0,0 +1,22 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+#[derive(Serialize, FromRow, Clone)]
+pub struct Credential {
+    pub user_id: Uuid,
+    #[serde(skip_serializing)]
+    pub password_hash: String,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Serialize, FromRow, Clone)]
+pub struct PasswordReset {
+    pub id: Uuid,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    pub user_id: Uuid,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
@@ -0,0 +1,75 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+use crate::utils::constants::ROUNDING_MODES;
+
+#[derive(Debug, Clone, FromRow, Serialize, sqlx::Type)]
+pub struct ExchangeRate {
+    pub id: Uuid,
+    pub currency: String,
+    pub rate: f64,
+    pub decimals: i16,
+    pub rounding: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Currencies {
+    pub base: String,
+    pub rates: Vec<ExchangeRate>,
+}
+
+// how amounts in the base currency turn into the requested one
+#[derive(Debug, Clone)]
+pub struct Conversion {
+    pub currency: String,
+    pub rate: f64,
+    pub decimals: i16,
+    pub rounding: String,
+}
+
+impl Conversion {
+    pub fn base(currency: String) -> Self {
+        Conversion {
+            currency,
+            rate: 1.0,
+            decimals: 2,
+            rounding: ROUNDING_MODES.nearest.to_string(),
+        }
+    }
+
+    pub fn convert(&self, amount: f32) -> f32 {
+        let factor = 10f64.powi(self.decimals as i32);
+        let scaled = amount as f64 * self.rate * factor;
+
+        let rounded = if self.rounding == ROUNDING_MODES.up {
+            scaled.ceil()
+        } else if self.rounding == ROUNDING_MODES.down {
+            scaled.floor()
+        } else {
+            scaled.round()
+        };
+
+        (rounded / factor) as f32
+    }
+
+    // a converted amount times a quantity, only trimmed back to the currency's decimals
+    pub fn multiply(&self, amount: f32, quantity: i32) -> f32 {
+        let factor = 10f64.powi(self.decimals as i32);
+
+        ((amount as f64 * quantity as f64 * factor).round() / factor) as f32
+    }
+}
+
+impl From<ExchangeRate> for Conversion {
+    fn from(rate: ExchangeRate) -> Self {
+        Conversion {
+            currency: rate.currency,
+            rate: rate.rate,
+            decimals: rate.decimals,
+            rounding: rate.rounding,
+        }
+    }
+}
@@ -0,0 +1,11 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow, Serialize, sqlx::Type)]
+pub struct CustomerGroup {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+}
@@ -0,0 +1,43 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow, Serialize, sqlx::Type)]
+pub struct DigitalFile {
+    pub id: Uuid,
+    pub name: String,
+    pub key: String,
+    pub content_type: String,
+    pub variant_id: Uuid,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, sqlx::Type)]
+pub struct Download {
+    pub id: Uuid,
+    pub downloads_left: i32,
+    pub expires_at: NaiveDateTime,
+    pub file_id: Uuid,
+    pub user_id: Uuid,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct UserDownload {
+    pub download: Download,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadLink {
+    #[serde(flatten)]
+    pub download: Download,
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CartShipping {
+    pub requires_shipping: bool,
+}
@@ -0,0 +1,31 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+#[derive(Serialize, FromRow, Clone)]
+pub struct Identity {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub provider: String,
+    pub subject: String,
+    pub email: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(FromRow, Clone)]
+pub struct OAuthState {
+    pub id: Uuid,
+    pub state_hash: String,
+    pub verifier: String,
+    pub expires_at: NaiveDateTime,
+}
+
+// the standard claims of an oidc userinfo response, plain oauth providers usually send the same names
+#[derive(Deserialize, Clone)]
+pub struct OAuthUser {
+    pub sub: String,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+}
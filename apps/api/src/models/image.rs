@@ -3,6 +3,8 @@ use serde::Serialize;
 use sqlx::prelude::{FromRow, Type};
 use uuid::Uuid;
 
+use super::product::Product;
+
 #[derive(FromRow, Debug, Serialize, Type, Clone)]
 pub struct Image {
     pub id: Uuid,
@@ -10,3 +12,31 @@ pub struct Image {
     pub src: String,
     pub created_at: NaiveDateTime,
 }
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ImageWithUrl {
+    #[serde(flatten)]
+    pub image: Image,
+    pub url: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PopulatedImage {
+    pub image: Image,
+    pub products: Vec<Product>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LibraryImage {
+    #[serde(flatten)]
+    pub image: ImageWithUrl,
+    pub references: usize,
+    pub products: Vec<Product>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct UploadResult {
+    pub name: String,
+    pub image: Option<ImageWithUrl>,
+    pub error: Option<String>,
+}
@@ -8,8 +8,13 @@ use super::user::User;
 #[derive(Serialize, FromRow, sqlx::Type, Clone)]
 pub struct MagicToken {
     pub id: Uuid,
-    pub token: Uuid,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    #[serde(skip_serializing)]
+    pub otp_hash: String,
+    pub attempts: i32,
     pub user_id: Uuid,
+    pub expires_at: NaiveDateTime,
     pub created_at: NaiveDateTime,
 }
 
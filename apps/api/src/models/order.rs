@@ -0,0 +1,18 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow, Serialize, sqlx::Type)]
+pub struct Order {
+    pub id: Uuid,
+    pub cart_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    pub currency: String,
+    pub total: f32,
+    pub base_currency: String,
+    pub base_total: f32,
+    pub rate: f64,
+    pub paid_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
@@ -0,0 +1,147 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+use crate::utils::constants::PRICE_RULE_KINDS;
+
+use super::sale::Sale;
+
+#[derive(Debug, Clone, FromRow, Serialize, sqlx::Type)]
+pub struct PriceList {
+    pub id: Uuid,
+    pub name: String,
+    pub customer_group_id: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, sqlx::Type)]
+pub struct PriceRule {
+    pub id: Uuid,
+    pub kind: String,
+    pub value: f32,
+    pub min_quantity: i32,
+    pub price_list_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub category_id: Option<Uuid>,
+}
+
+impl PriceRule {
+    pub fn apply(&self, price: f32) -> f32 {
+        if self.kind == PRICE_RULE_KINDS.percentage {
+            return price * (1.0 - self.value / 100.0);
+        }
+
+        self.value
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PopulatedPriceList {
+    #[serde(flatten)]
+    pub price_list: PriceList,
+    pub rules: Vec<PriceRule>,
+}
+
+// what a variant costs before any price list applies
+#[derive(Debug, Clone, FromRow)]
+pub struct BasePrice {
+    pub variant_id: Uuid,
+    pub category_id: Uuid,
+    pub price: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceTier {
+    pub min_quantity: i32,
+    pub price: f32,
+}
+
+pub struct Pricing {
+    pub bases: Vec<BasePrice>,
+    pub rules: Vec<PriceRule>,
+    // only the sales running when the pricing was loaded
+    pub sales: Vec<Sale>,
+}
+
+impl Pricing {
+    fn matching<'a>(&'a self, base: &'a BasePrice) -> impl Iterator<Item = &'a PriceRule> {
+        self.rules.iter().filter(|rule| {
+            rule.variant_id == Some(base.variant_id) || rule.category_id == Some(base.category_id)
+        })
+    }
+
+    // the cheapest running sale on the variant or its category, with its price,
+    // a sale that doesn't go below the base price isn't one
+    pub fn sale(&self, variant_id: &Uuid) -> Option<(&Sale, f32)> {
+        let base = self
+            .bases
+            .iter()
+            .find(|base| &base.variant_id == variant_id)?;
+
+        self.sales
+            .iter()
+            .filter(|sale| {
+                sale.variant_id == Some(base.variant_id)
+                    || sale.category_id == Some(base.category_id)
+            })
+            .map(|sale| (sale, sale.apply(base.price)))
+            .filter(|(_, price)| *price < base.price)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+
+    // the lowest price any matching rule or sale gives for the quantity, never above the base price
+    pub fn price(&self, variant_id: &Uuid, quantity: i32) -> Option<f32> {
+        let base = self
+            .bases
+            .iter()
+            .find(|base| &base.variant_id == variant_id)?;
+
+        let price = self
+            .matching(base)
+            .filter(|rule| rule.min_quantity <= quantity)
+            .map(|rule| rule.apply(base.price))
+            .fold(base.price, f32::min);
+
+        match self.sale(variant_id) {
+            Some((_, sale_price)) => Some(price.min(sale_price)),
+            None => Some(price),
+        }
+    }
+
+    // quantities from which the unit price drops further
+    pub fn tiers(&self, variant_id: &Uuid) -> Vec<PriceTier> {
+        let Some(base) = self
+            .bases
+            .iter()
+            .find(|base| &base.variant_id == variant_id)
+        else {
+            return Vec::new();
+        };
+
+        let mut quantities = self
+            .matching(base)
+            .map(|rule| rule.min_quantity)
+            .filter(|min_quantity| *min_quantity > 1)
+            .collect::<Vec<_>>();
+        quantities.sort();
+        quantities.dedup();
+
+        let mut tiers: Vec<PriceTier> = Vec::new();
+        let mut current = self.price(variant_id, 1).unwrap_or(base.price);
+
+        for min_quantity in quantities {
+            let price = self.price(variant_id, min_quantity).unwrap_or(base.price);
+
+            if price < current {
+                tiers.push(PriceTier {
+                    min_quantity,
+                    price,
+                });
+                current = price;
+            }
+        }
+
+        tiers
+    }
+}
@@ -1,11 +1,11 @@
 use chrono::NaiveDateTime;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sqlx::{postgres::PgQueryResult, prelude::FromRow, PgConnection};
 use uuid::Uuid;
 
 use crate::validations::product::Variant;
 
-use super::{category::Category, image::Image, unit::Unit};
+use super::{category::Category, image::Image, price_list::PriceTier, review::Rating, unit::Unit};
 
 #[derive(Debug, Clone, FromRow, Serialize, sqlx::Type)]
 pub struct Product {
@@ -15,6 +15,7 @@ pub struct Product {
     pub unit_id: Uuid,
     pub category_id: Uuid,
     pub created_at: NaiveDateTime,
+    pub slug: Option<String>,
 }
 
 impl Product {
@@ -22,6 +23,129 @@ impl Product {
         &self,
         input_variants: &Vec<Variant>,
         db: &mut PgConnection,
+    ) -> Result<(), sqlx::Error> {
+        // prepare variants
+        let mut prices: Vec<f32> = Vec::new();
+        let mut skus: Vec<Option<String>> = Vec::new();
+        input_variants.iter().for_each(|variant| {
+            prices.push(variant.price);
+            skus.push(variant.sku.clone());
+        });
+
+        // create variants
+        let variants = self.create_variants(&prices, &skus, db).await?;
+
+        let variant_ids = variants
+            .iter()
+            .map(|variant| variant.id)
+            .collect::<Vec<_>>();
+
+        self.attach_options(input_variants, &variant_ids, db).await
+    }
+
+    // keeps the variants whose sku is listed again so their stock, carts and downloads stay
+    // attached, the others are replaced
+    pub async fn upsert_variants(
+        &self,
+        input_variants: &[Variant],
+        db: &mut PgConnection,
+    ) -> Result<(), sqlx::Error> {
+        let existing = sqlx::query_as!(
+            ProductVariant,
+            "SELECT * FROM product_variants WHERE product_id = $1",
+            self.id
+        )
+        .fetch_all(&mut *db)
+        .await?;
+
+        let kept = input_variants
+            .iter()
+            .map(|variant| {
+                existing
+                    .iter()
+                    .find(|existing| variant.sku.is_some() && existing.sku == variant.sku)
+                    .map(|existing| existing.id)
+            })
+            .collect::<Vec<_>>();
+        let kept_ids = kept.iter().flatten().cloned().collect::<Vec<_>>();
+
+        sqlx::query!(
+            "DELETE FROM product_variants WHERE product_id = $1 AND id <> ALL($2)",
+            self.id,
+            &kept_ids
+        )
+        .execute(&mut *db)
+        .await?;
+
+        // options are rebuilt for every variant, kept ones included
+        sqlx::query!(
+            "DELETE FROM product_variant_collection_keys WHERE product_id = $1",
+            self.id
+        )
+        .execute(&mut *db)
+        .await?;
+
+        let mut prices: Vec<f32> = Vec::new();
+        let mut skus: Vec<Option<String>> = Vec::new();
+        let mut kept_prices: Vec<f32> = Vec::new();
+        input_variants
+            .iter()
+            .zip(kept.iter())
+            .for_each(|(variant, id)| match id {
+                Some(_) => kept_prices.push(variant.price),
+                None => {
+                    prices.push(variant.price);
+                    skus.push(variant.sku.clone());
+                }
+            });
+
+        sqlx::query!(
+            r#"
+              UPDATE product_variants SET price = input.price
+              FROM UNNEST($1::UUID[], $2::REAL[]) AS input(id, price)
+              WHERE product_variants.id = input.id
+            "#,
+            &kept_ids,
+            &kept_prices
+        )
+        .execute(&mut *db)
+        .await?;
+
+        let variants = self.create_variants(&prices, &skus, db).await?;
+
+        let mut inserted = variants.iter().map(|variant| variant.id);
+        let variant_ids = kept
+            .iter()
+            .filter_map(|id| id.or_else(|| inserted.next()))
+            .collect::<Vec<_>>();
+
+        self.attach_options(input_variants, &variant_ids, db).await
+    }
+
+    async fn create_variants(
+        &self,
+        prices: &[f32],
+        skus: &[Option<String>],
+        db: &mut PgConnection,
+    ) -> Result<Vec<ProductVariant>, sqlx::Error> {
+        sqlx::query_as!(
+        ProductVariant,
+        "INSERT INTO product_variants(price, product_id, sku) SELECT * FROM UNNEST($1::REAL[], $2::UUID[], $3::TEXT[]) RETURNING *",
+        prices,
+        &prices.iter().map(|_| {
+            self.id
+        }).collect::<Vec<Uuid>>(),
+        skus as &[Option<String>]
+    )
+    .fetch_all(&mut *db)
+    .await
+    }
+
+    async fn attach_options(
+        &self,
+        input_variants: &[Variant],
+        variant_ids: &[Uuid],
+        db: &mut PgConnection,
     ) -> Result<(), sqlx::Error> {
         // prepare keys
         let mut raw_keys: Vec<String> = Vec::new();
@@ -68,24 +192,6 @@ impl Product {
         &raw_values.iter().map(|(key_id, _)| key_id.clone()).collect::<Vec<Uuid>>(),
     )
     .fetch_all(&mut *db)
-    .await?;
-
-        // prepare variants
-        let mut prices: Vec<f32> = Vec::new();
-        input_variants.iter().for_each(|variant| {
-            prices.push(variant.price);
-        });
-
-        // create variants
-        let variants = sqlx::query_as!(
-        ProductVariant,
-        "INSERT INTO product_variants(price, product_id) SELECT * FROM UNNEST($1::REAL[], $2::UUID[]) RETURNING *",
-        &prices,
-        &prices.iter().map(|_| {
-            self.id
-        }).collect::<Vec<Uuid>>()
-    )
-    .fetch_all(&mut *db)
     .await?;
 
         // prepare collections variants
@@ -95,7 +201,7 @@ impl Product {
             .enumerate()
             .for_each(|(index, variant)| {
                 variant.options.iter().for_each(|_| {
-                    variants_ids.push(variants[index].id);
+                    variants_ids.push(variant_ids[index]);
                 })
             });
 
@@ -170,6 +276,15 @@ impl Product {
         .execute(connection)
         .await
     }
+
+    pub async fn detach_images(
+        &self,
+        connection: &mut PgConnection,
+    ) -> Result<PgQueryResult, sqlx::Error> {
+        sqlx::query!("DELETE FROM product_image WHERE product_id = $1", self.id)
+            .execute(connection)
+            .await
+    }
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -203,6 +318,7 @@ pub struct ProductVariant {
     pub id: Uuid,
     pub price: f32,
     pub product_id: Uuid,
+    pub sku: Option<String>,
 }
 
 #[derive(Debug, Clone, FromRow, Serialize, sqlx::Type)]
@@ -224,6 +340,15 @@ pub struct SCollection {
 pub struct SVariant {
     pub variant: ProductVariant,
     pub collections: Vec<SCollection>,
+    // unit price for the caller, after their price lists and running sales
+    pub price: f32,
+    pub regular_price: f32,
+    pub sale_price: Option<f32>,
+    pub sale_ends_at: Option<NaiveDateTime>,
+    pub tiers: Vec<PriceTier>,
+    pub currency: String,
+    // on hand over every warehouse minus active reservations
+    pub available: i64,
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -233,4 +358,37 @@ pub struct SProduct {
     pub unit: Unit,
     pub variants: Vec<SVariant>,
     pub images: Vec<Image>,
+    pub rating: Rating,
+}
+
+// one csv line per variant, products without variants get a single line with empty variant columns
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProductRow {
+    pub slug: Option<String>,
+    pub name: String,
+    pub description: Option<String>,
+    pub category: String,
+    pub unit: String,
+    pub sku: Option<String>,
+    pub price: Option<f32>,
+    // key=value pairs separated by ;
+    pub options: Option<String>,
+    // image srcs separated by ;
+    pub images: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ImportError {
+    pub line: u64,
+    pub error: String,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub created: usize,
+    pub updated: usize,
+    pub categories: Vec<String>,
+    pub units: Vec<String>,
+    pub errors: Vec<ImportError>,
 }
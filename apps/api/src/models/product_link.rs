@@ -0,0 +1,23 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+use super::product::Product;
+
+#[derive(Debug, Clone, FromRow, Serialize, sqlx::Type)]
+pub struct ProductLink {
+    pub id: Uuid,
+    pub kind: String,
+    pub position: i32,
+    pub product_id: Uuid,
+    pub linked_product_id: Uuid,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProductLinks {
+    pub related: Vec<Product>,
+    pub cross_sell: Vec<Product>,
+    pub up_sell: Vec<Product>,
+}
@@ -0,0 +1,36 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+
+#[derive(Serialize, FromRow, Clone)]
+pub struct RateLimitBucket {
+    pub key: String,
+    pub tokens: f64,
+    pub updated_at: NaiveDateTime,
+}
+
+impl RateLimitBucket {
+    pub fn full(key: &str, capacity: u32, now: NaiveDateTime) -> Self {
+        RateLimitBucket {
+            key: key.to_string(),
+            tokens: capacity as f64,
+            updated_at: now,
+        }
+    }
+
+    // refills for the time passed, then takes a token or tells how many seconds until one is back
+    pub fn take(&mut self, capacity: u32, period: i64, now: NaiveDateTime) -> Option<i64> {
+        let rate = capacity as f64 / period as f64;
+        let elapsed = (now - self.updated_at).num_milliseconds().max(0) as f64 / 1000.0;
+
+        self.tokens = (self.tokens + elapsed * rate).min(capacity as f64);
+        self.updated_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return None;
+        }
+
+        Some(((1.0 - self.tokens) / rate).ceil().max(1.0) as i64)
+    }
+}
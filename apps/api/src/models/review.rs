@@ -0,0 +1,37 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow, Serialize, sqlx::Type)]
+pub struct Review {
+    pub id: Uuid,
+    pub rating: i16,
+    pub title: String,
+    pub body: String,
+    pub verified: bool,
+    pub status: String,
+    pub product_id: Uuid,
+    pub user_id: Uuid,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PublicReview {
+    #[serde(flatten)]
+    pub review: Review,
+    pub author: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, FromRow, Serialize)]
+pub struct Rating {
+    pub average: Option<f64>,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct ProductRating {
+    pub product_id: Uuid,
+    pub average: Option<f64>,
+    pub count: i64,
+}
@@ -9,3 +9,10 @@ pub struct Role {
     pub name: String,
     pub created_at: NaiveDateTime,
 }
+
+#[derive(Serialize)]
+pub struct PopulatedRole {
+    #[serde(flatten)]
+    pub role: Role,
+    pub permissions: Vec<String>,
+}
@@ -0,0 +1,29 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+use crate::utils::constants::PRICE_RULE_KINDS;
+
+#[derive(Debug, Clone, FromRow, Serialize, sqlx::Type)]
+pub struct Sale {
+    pub id: Uuid,
+    pub name: String,
+    pub kind: String,
+    pub value: f32,
+    pub variant_id: Option<Uuid>,
+    pub category_id: Option<Uuid>,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+impl Sale {
+    pub fn apply(&self, price: f32) -> f32 {
+        if self.kind == PRICE_RULE_KINDS.percentage {
+            return price * (1.0 - self.value / 100.0);
+        }
+
+        self.value
+    }
+}
@@ -11,6 +11,11 @@ pub struct Session {
     pub session: Uuid,
     pub user_id: Uuid,
     pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub last_seen_at: NaiveDateTime,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub second_factor_at: Option<NaiveDateTime>,
 }
 
 #[derive(Serialize, FromRow, Clone)]
@@ -18,4 +23,17 @@ pub struct PopulatedSession {
     pub session: Session,
     pub user: User,
     pub role: Role,
+    pub permissions: Vec<String>,
+}
+
+// a session as shown to its owner, without the secret
+#[derive(Serialize, FromRow, Clone)]
+pub struct Device {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub last_seen_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub current: bool,
 }
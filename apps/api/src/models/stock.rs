@@ -0,0 +1,38 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow, Serialize, sqlx::Type)]
+pub struct Stock {
+    pub id: Uuid,
+    pub quantity: i32,
+    pub variant_id: Uuid,
+    pub warehouse_id: Uuid,
+    pub created_at: NaiveDateTime,
+    pub reorder_threshold: Option<i32>,
+    pub alerted_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct StockLevel {
+    #[serde(flatten)]
+    pub stock: Stock,
+    pub reserved: i64,
+    pub available: i64,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct VariantStock {
+    pub variant_id: Uuid,
+    pub available: i64,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct LowStock {
+    #[serde(flatten)]
+    pub stock: Stock,
+    pub sku: Option<String>,
+    pub product: String,
+    pub warehouse: String,
+}
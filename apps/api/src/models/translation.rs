@@ -0,0 +1,53 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow, Serialize, sqlx::Type)]
+pub struct ProductTranslation {
+    pub id: Uuid,
+    pub locale: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub product_id: Uuid,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, sqlx::Type)]
+pub struct OptionTranslation {
+    pub id: Uuid,
+    pub locale: String,
+    pub source: String,
+    pub name: String,
+    pub product_id: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PopulatedProductTranslation {
+    pub translation: ProductTranslation,
+    pub options: Vec<OptionTranslation>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, sqlx::Type)]
+pub struct CategoryTranslation {
+    pub id: Uuid,
+    pub locale: String,
+    pub name: String,
+    pub category_id: Uuid,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, sqlx::Type)]
+pub struct UnitTranslation {
+    pub id: Uuid,
+    pub locale: String,
+    pub name: String,
+    pub unit_id: Uuid,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Locales {
+    pub default: String,
+    pub available: Vec<String>,
+}
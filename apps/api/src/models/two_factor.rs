@@ -0,0 +1,36 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+#[derive(Serialize, FromRow, Clone)]
+pub struct TotpFactor {
+    pub user_id: Uuid,
+    #[serde(skip_serializing)]
+    pub secret: Vec<u8>,
+    #[serde(skip_serializing)]
+    pub sealed: bool,
+    pub last_step: Option<i64>,
+    pub confirmed_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+// shown once when enrolling, the secret is never sent again
+#[derive(Serialize)]
+pub struct Enrollment {
+    pub secret: String,
+    pub uri: String,
+}
+
+#[derive(Serialize)]
+pub struct RecoveryCodes {
+    pub codes: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    pub verified: bool,
+    pub required: bool,
+    pub recovery_codes: i64,
+}
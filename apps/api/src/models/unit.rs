@@ -37,8 +37,8 @@ impl Unit {
             .await
     }
 
-    pub async fn insert<'a>(
-        input: StoreUnitSchema<'a>,
+    pub async fn insert(
+        input: StoreUnitSchema,
         connection: &mut PgConnection,
     ) -> Result<Unit, sqlx::Error> {
         sqlx::query_as!(
@@ -50,8 +50,8 @@ impl Unit {
         .await
     }
 
-    pub async fn insert_many<'a>(
-        input: Vec<StoreUnitSchema<'a>>,
+    pub async fn insert_many(
+        input: Vec<StoreUnitSchema>,
         connection: &mut PgConnection,
     ) -> Result<Vec<Unit>, sqlx::Error> {
         sqlx::query_as!(
@@ -66,9 +66,9 @@ impl Unit {
         .await
     }
 
-    pub async fn update<'a>(
+    pub async fn update(
         id: &Uuid,
-        input: StoreUnitSchema<'a>,
+        input: StoreUnitSchema,
         connection: &mut PgConnection,
     ) -> Result<PgQueryResult, sqlx::Error> {
         sqlx::query!("UPDATE units SET name = $2 WHERE id = $1", id, input.name)
@@ -86,7 +86,14 @@ impl Unit {
     }
 
     pub async fn save(&self, connection: &mut PgConnection) -> Result<PgQueryResult, sqlx::Error> {
-        Unit::update(&self.id, StoreUnitSchema { name: &self.name }, connection).await
+        Unit::update(
+            &self.id,
+            StoreUnitSchema {
+                name: self.name.clone(),
+            },
+            connection,
+        )
+        .await
     }
 
     pub async fn delete(
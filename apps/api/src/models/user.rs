@@ -20,6 +20,7 @@ pub struct User {
     pub email: String,
     pub role_id: Uuid,
     pub created_at: NaiveDateTime,
+    pub customer_group_id: Option<Uuid>,
 }
 
 impl User {
@@ -47,8 +48,8 @@ impl User {
             .await
     }
 
-    pub async fn insert<'a>(
-        input: StoreUserSchema<'a>,
+    pub async fn insert(
+        input: StoreUserSchema,
         connection: &mut PgConnection,
     ) -> Result<User, sqlx::Error> {
         sqlx::query_as!(
@@ -62,14 +63,14 @@ impl User {
         .await
     }
 
-    pub async fn insert_many<'a>(
-        input: Vec<StoreUserSchema<'a>>,
+    pub async fn insert_many(
+        input: Vec<StoreUserSchema>,
         connection: &mut PgConnection,
     ) -> Result<Vec<User>, sqlx::Error> {
         sqlx::query_as!(
             User,
             "INSERT INTO users(name, email, role_id) SELECT * FROM UNNEST($1::TEXT[], $2:: TEXT[], $3::UUID[]) RETURNING *",
-            &input.iter().map(|user| match user.name {
+            &input.iter().map(|user| match &user.name {
                 Some(name) => Some(name.to_string()),
                 None => None
             } ).collect::<Vec<_>>() as &[Option<String>],
@@ -80,9 +81,9 @@ impl User {
         .await
     }
 
-    pub async fn update<'a>(
+    pub async fn update(
         id: &Uuid,
-        input: StoreUserSchema<'a>,
+        input: StoreUserSchema,
         connection: &mut PgConnection,
     ) -> Result<PgQueryResult, sqlx::Error> {
         sqlx::query!(
@@ -114,8 +115,8 @@ impl User {
         User::update(
             &self.id,
             StoreUserSchema {
-                name,
-                email: &self.email,
+                name: name.map(|name| name.to_string()),
+                email: self.email.clone(),
                 role_id: self.role_id,
             },
             connection,
@@ -37,8 +37,8 @@ impl Warehouse {
             .await
     }
 
-    pub async fn insert<'a>(
-        input: StoreWarehouseSchema<'a>,
+    pub async fn insert(
+        input: StoreWarehouseSchema,
         connection: &mut PgConnection,
     ) -> Result<Warehouse, sqlx::Error> {
         sqlx::query_as!(
@@ -50,8 +50,8 @@ impl Warehouse {
         .await
     }
 
-    pub async fn insert_many<'a>(
-        input: Vec<StoreWarehouseSchema<'a>>,
+    pub async fn insert_many(
+        input: Vec<StoreWarehouseSchema>,
         connection: &mut PgConnection,
     ) -> Result<Warehouse, sqlx::Error> {
         sqlx::query_as!(
@@ -66,9 +66,9 @@ impl Warehouse {
         .await
     }
 
-    pub async fn update<'a>(
+    pub async fn update(
         id: &Uuid,
-        input: StoreWarehouseSchema<'a>,
+        input: StoreWarehouseSchema,
         connection: &mut PgConnection,
     ) -> Result<PgQueryResult, sqlx::Error> {
         sqlx::query!(
@@ -80,7 +80,7 @@ impl Warehouse {
         .await
     }
 
-    pub async fn destroy<'a>(
+    pub async fn destroy(
         id: &Uuid,
         connection: &mut PgConnection,
     ) -> Result<PgQueryResult, sqlx::Error> {
@@ -92,7 +92,9 @@ impl Warehouse {
     pub async fn save(self, connection: &mut PgConnection) -> Result<PgQueryResult, sqlx::Error> {
         Warehouse::update(
             &self.id,
-            StoreWarehouseSchema { name: &self.name },
+            StoreWarehouseSchema {
+                name: self.name.clone(),
+            },
             connection,
         )
         .await
@@ -0,0 +1,29 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+use super::product::{Product, ProductVariant};
+
+#[derive(Debug, Clone, FromRow, Serialize, sqlx::Type)]
+pub struct Wishlist {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, sqlx::Type)]
+pub struct WishlistItem {
+    pub id: Uuid,
+    pub wishlist_id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PopulatedWishlistItem {
+    pub item: WishlistItem,
+    pub product: Product,
+    pub variant: Option<ProductVariant>,
+}
@@ -1,62 +1,219 @@
 use axum::{
-    middleware::{from_fn, from_fn_with_state},
-    routing::{delete, get, patch, post},
+    extract::DefaultBodyLimit,
+    middleware::{from_extractor, from_fn_with_state},
+    routing::{delete, get, patch, post, put},
     Router,
 };
 use tower_http::limit::RequestBodyLimitLayer;
 
 use crate::{
-    controllers::{category, image, product, role, unit, user, warehouse},
-    middlewares::{admin::admin_middleware, auth::auth_middleware},
+    controllers::{
+        api_key, bundle, category, checkout, currency, customer_group, download, image, order,
+        price_list, product, review, role, sale, stock, translation, two_factor, unit, user,
+        warehouse,
+    },
+    middlewares::{
+        admin::admin_middleware,
+        auth::auth_middleware,
+        csrf,
+        permission::{
+            ApiKeysManage, CatalogWrite, OrdersManage, PricingWrite, ProductsWrite,
+            RequirePermission, ReviewsModerate, RolesManage, SettingsManage, StockManage,
+            UsersManage,
+        },
+    },
     AppState,
 };
 
 pub fn init(state: AppState) -> Router<AppState> {
     let role_router = Router::new()
         .route("/roles", get(role::index))
-        .route("/roles/:id", get(role::show));
+        .route("/roles/:id", get(role::show))
+        .route("/roles", post(role::store))
+        .route("/roles/:id", patch(role::update))
+        .route("/roles/:id", delete(role::destroy))
+        .route("/permissions", get(role::permissions))
+        .route_layer(from_extractor::<RequirePermission<RolesManage>>());
 
     let user_router = Router::new()
         .route("/users", get(user::index))
         .route("/users/:id", get(user::show))
         .route("/users", post(user::store))
         .route("/users/:id", patch(user::update))
-        .route("/users/:id", delete(user::destroy));
+        .route("/users/:id", delete(user::destroy))
+        .route_layer(from_extractor::<RequirePermission<UsersManage>>());
+
+    let api_key_router = Router::new()
+        .route("/api-keys", get(api_key::index))
+        .route("/api-keys", post(api_key::store))
+        .route("/api-keys/:id", delete(api_key::destroy))
+        .route_layer(from_extractor::<RequirePermission<ApiKeysManage>>());
+
+    let settings_router = Router::new()
+        .route("/two-factor/required", put(two_factor::set_required))
+        .route("/currencies/base", put(currency::set_base))
+        .route("/locales/default", put(translation::set_default_locale))
+        .route_layer(from_extractor::<RequirePermission<SettingsManage>>());
+
+    let customer_group_router = Router::new()
+        .route("/customer-groups", get(customer_group::index))
+        .route("/customer-groups", post(customer_group::store))
+        .route("/customer-groups/:id", patch(customer_group::update))
+        .route("/customer-groups/:id", delete(customer_group::destroy))
+        .route("/users/:id/customer-group", put(customer_group::assign))
+        .route_layer(from_extractor::<RequirePermission<PricingWrite>>());
+
+    let price_list_router = Router::new()
+        .route("/price-lists", get(price_list::index))
+        .route("/price-lists/:id", get(price_list::show))
+        .route("/price-lists", post(price_list::store))
+        .route("/price-lists/:id", patch(price_list::update))
+        .route("/price-lists/:id", delete(price_list::destroy))
+        .route_layer(from_extractor::<RequirePermission<PricingWrite>>());
+
+    let sale_router = Router::new()
+        .route("/sales", get(sale::index))
+        .route("/sales", post(sale::store))
+        .route("/sales/:id", patch(sale::update))
+        .route("/sales/:id", delete(sale::destroy))
+        .route_layer(from_extractor::<RequirePermission<PricingWrite>>());
+
+    let currency_router = Router::new()
+        .route("/exchange-rates", put(currency::set_rate))
+        .route("/exchange-rates/:id", delete(currency::destroy_rate))
+        .route_layer(from_extractor::<RequirePermission<PricingWrite>>());
+
+    let translation_router = Router::new()
+        .route(
+            "/products/:id/translations",
+            get(translation::product_index),
+        )
+        .route("/products/:id/translations", put(translation::set_product))
+        .route(
+            "/products/:id/translations/:locale",
+            delete(translation::destroy_product),
+        )
+        .route(
+            "/categories/:id/translations",
+            get(translation::category_index),
+        )
+        .route(
+            "/categories/:id/translations",
+            put(translation::set_category),
+        )
+        .route(
+            "/categories/:id/translations/:locale",
+            delete(translation::destroy_category),
+        )
+        .route("/units/:id/translations", get(translation::unit_index))
+        .route("/units/:id/translations", put(translation::set_unit))
+        .route(
+            "/units/:id/translations/:locale",
+            delete(translation::destroy_unit),
+        )
+        .route_layer(from_extractor::<RequirePermission<CatalogWrite>>());
 
     let category_router = Router::new()
         .route("/categories", post(category::store))
         .route("/categories/:id", patch(category::update))
-        .route("/categories/:id", delete(category::destroy));
+        .route("/categories/:id", delete(category::destroy))
+        .route_layer(from_extractor::<RequirePermission<CatalogWrite>>());
 
     let unit_router = Router::new()
         .route("/units", post(unit::store))
         .route("/units/:id", patch(unit::update))
-        .route("/units/:id", delete(unit::destroy));
+        .route("/units/:id", delete(unit::destroy))
+        .route_layer(from_extractor::<RequirePermission<CatalogWrite>>());
 
     let warehouse_router = Router::new()
         .route("/warehouses", get(warehouse::index))
         .route("/warehouses/:id", get(warehouse::show))
         .route("/warehouses", post(warehouse::store))
         .route("/warehouses/:id", patch(warehouse::update))
-        .route("/warehouses/:id", delete(warehouse::destroy));
+        .route("/warehouses/:id", delete(warehouse::destroy))
+        .route_layer(from_extractor::<RequirePermission<StockManage>>());
 
     let product_router = Router::new()
         .route("/products", post(product::store))
+        .route("/products/export", get(product::export))
+        .route("/products/import", post(product::import))
         .route("/products/:id", patch(product::update))
-        .route("/products/:id", delete(product::destroy));
+        .route("/products/:id/links", put(product::update_links))
+        .route("/products/:id", delete(product::destroy))
+        .route_layer(from_extractor::<RequirePermission<ProductsWrite>>());
+
+    let review_router = Router::new()
+        .route("/reviews", get(review::index))
+        .route("/reviews/:id", patch(review::update))
+        .route("/reviews/:id", delete(review::destroy))
+        .route_layer(from_extractor::<RequirePermission<ReviewsModerate>>());
+
+    let bundle_router = Router::new()
+        .route("/bundles", post(bundle::store))
+        .route("/bundles/:id", patch(bundle::update))
+        .route("/bundles/:id", delete(bundle::destroy))
+        .route_layer(from_extractor::<RequirePermission<ProductsWrite>>());
+
+    let stock_router = Router::new()
+        .route("/stocks", get(stock::index))
+        .route("/stocks", put(stock::set))
+        .route("/stocks/low", get(stock::low))
+        .route("/stocks/threshold", put(stock::set_threshold))
+        .route("/stocks/alert-recipients", get(stock::alert_recipients))
+        .route("/stocks/alert-recipients", put(stock::set_alert_recipients))
+        .route_layer(from_extractor::<RequirePermission<StockManage>>());
+
+    let order_router = Router::new()
+        .route("/orders", get(order::index))
+        .route("/checkouts/:id/paid", post(checkout::paid))
+        .route("/checkouts/:id/failed", post(checkout::failed))
+        .route("/downloads", post(download::grant))
+        .route_layer(from_extractor::<RequirePermission<OrdersManage>>());
+
+    let download_router = Router::new()
+        .route("/variants/:id/file", get(download::show_file))
+        .route("/variants/:id/file", post(download::upload_file))
+        .route("/variants/:id/file", delete(download::destroy_file))
+        .route_layer(from_extractor::<RequirePermission<ProductsWrite>>())
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(
+            state.env.upload_max_request_size,
+        ));
 
     let image_router = Router::new()
+        .route("/images", get(image::index))
+        .route("/images/:id", get(image::show))
         .route("/images", post(image::upload))
-        .layer(RequestBodyLimitLayer::new(2 * 1024 * 1024));
+        .route("/images/import", post(image::import))
+        .route("/images/:id", patch(image::update))
+        .route("/images/:id", delete(image::destroy))
+        .route_layer(from_extractor::<RequirePermission<ProductsWrite>>())
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(
+            state.env.upload_max_request_size,
+        ));
 
     Router::new()
         .merge(role_router)
         .merge(user_router)
+        .merge(api_key_router)
+        .merge(settings_router)
+        .merge(customer_group_router)
+        .merge(price_list_router)
+        .merge(sale_router)
+        .merge(currency_router)
+        .merge(translation_router)
         .merge(category_router)
         .merge(unit_router)
         .merge(warehouse_router)
         .merge(product_router)
+        .merge(review_router)
+        .merge(bundle_router)
+        .merge(stock_router)
+        .merge(order_router)
+        .merge(download_router)
         .merge(image_router)
-        .route_layer(from_fn(admin_middleware))
+        .route_layer(from_fn_with_state(state.clone(), admin_middleware))
+        .route_layer(from_fn_with_state(state.clone(), csrf::middleware))
         .route_layer(from_fn_with_state(state, auth_middleware))
 }
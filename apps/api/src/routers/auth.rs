@@ -1,17 +1,60 @@
-use crate::{controllers::auth, middlewares::auth::auth_middleware, AppState};
+use crate::{
+    controllers::{auth, download, password, review, session, two_factor},
+    middlewares::{
+        auth::{auth_middleware, session_only_middleware},
+        csrf,
+        rate_limit::{self, RateLimited},
+    },
+    utils::constants::TWO_FACTOR_RATE_LIMITS,
+    AppState,
+};
 
 use axum::{
-    middleware::from_fn_with_state,
-    routing::{get, post},
+    middleware::{from_fn, from_fn_with_state},
+    routing::{delete, get, post, put},
     Router,
 };
 
 pub fn init(state: AppState) -> Router<AppState> {
     let auth_router = Router::new()
         .route("/sign-out", post(auth::sign_out))
-        .route("/profile", get(auth::profile));
+        .route("/profile", get(auth::profile))
+        .route("/password", put(password::update));
+
+    let session_router = Router::new()
+        .route("/sessions", get(session::index))
+        .route("/sessions/:id", delete(session::destroy));
+
+    let two_factor_router = Router::new()
+        .route("/two-factor", get(two_factor::status))
+        .route("/two-factor", post(two_factor::enroll));
+
+    // every route here takes a code, guesses are capped per account
+    let two_factor_code_router = Router::new()
+        .route("/two-factor/confirm", post(two_factor::confirm))
+        .route("/two-factor/verify", post(two_factor::verify))
+        .route(
+            "/two-factor/recovery-codes",
+            post(two_factor::recovery_codes),
+        )
+        .route("/two-factor", delete(two_factor::disable))
+        .route_layer(from_fn_with_state(
+            RateLimited::new(state.clone(), TWO_FACTOR_RATE_LIMITS),
+            rate_limit::middleware,
+        ));
+
+    let review_router = Router::new().route("/products/:id/reviews", post(review::store));
+
+    let download_router = Router::new().route("/downloads", get(download::index));
 
     Router::new()
         .merge(auth_router)
+        .merge(session_router)
+        .merge(two_factor_router)
+        .merge(two_factor_code_router)
+        .merge(review_router)
+        .merge(download_router)
+        .route_layer(from_fn(session_only_middleware))
+        .route_layer(from_fn_with_state(state.clone(), csrf::middleware))
         .route_layer(from_fn_with_state(state, auth_middleware))
 }
@@ -6,8 +6,18 @@ use axum::{
 use tower_http::services::ServeDir;
 
 use crate::{
-    controllers::{auth, cart, category, product, settings, unit},
-    middlewares::optional_auth,
+    controllers::{
+        auth, bundle, cart, category, csrf, currency, download, image, oauth, password, product,
+        review, settings, translation, unit, wishlist,
+    },
+    middlewares::{
+        csrf::middleware as csrf_middleware,
+        optional_auth,
+        rate_limit::{self, RateLimited},
+    },
+    utils::constants::{
+        AUTH_RATE_LIMITS, PUBLIC_FOLDER_NAME, PUBLIC_RATE_LIMITS, SIGN_IN_RATE_LIMITS,
+    },
     AppState,
 };
 
@@ -20,33 +30,94 @@ pub fn init(state: AppState) -> Router<AppState> {
         .route("/units", get(unit::index))
         .route("/units/:id", get(unit::show));
 
+    let currency_router = Router::new().route("/currencies", get(currency::index));
+
+    let locale_router = Router::new().route("/locales", get(translation::locales));
+
     let product_router = Router::new()
         .route("/products", get(product::index))
-        .route("/products/:id", get(product::show));
+        .route("/products/:id", get(product::show))
+        .route("/products/:id/reviews", get(review::product_reviews))
+        .route("/products/:id/links", get(product::links));
+
+    let bundle_router = Router::new()
+        .route("/bundles", get(bundle::index))
+        .route("/bundles/:id", get(bundle::show));
+
+    let download_router = Router::new().route("/downloads/:id", get(download::download));
 
     let cart_router = Router::new()
         .route("/carts", get(cart::list_items))
         .route("/carts", post(cart::add_item))
+        .route("/carts/checkout", post(cart::checkout))
+        .route("/carts/cross-sells", get(cart::cross_sells))
+        .route("/carts/shipping", get(cart::shipping))
         .route("/carts/:item_id", patch(cart::update_item))
-        .route("/carts/:item_id", delete(cart::delete_item));
+        .route("/carts/:item_id", delete(cart::delete_item))
+        .route_layer(from_fn_with_state(state.clone(), csrf_middleware));
 
-    let auth_router = Router::new()
+    let csrf_router = Router::new().route("/csrf", get(csrf::token));
+
+    let wishlist_router = Router::new()
+        .route("/wishlists", get(wishlist::index))
+        .route("/wishlists", post(wishlist::add_item))
+        .route("/wishlists/:item_id", delete(wishlist::delete_item))
+        .route("/wishlists/:item_id/cart", post(wishlist::move_to_cart))
+        .route_layer(from_fn_with_state(state.clone(), csrf_middleware));
+
+    let sign_in_router = Router::new()
         .route("/sign-in", post(auth::sign_in))
-        .route("/auth", get(auth::auth));
+        .route("/password/forgot", post(password::forgot))
+        .route_layer(from_fn_with_state(state.clone(), csrf_middleware))
+        .route_layer(from_fn_with_state(
+            RateLimited::new(state.clone(), SIGN_IN_RATE_LIMITS),
+            rate_limit::middleware,
+        ));
+
+    let auth_router = Router::new()
+        .route("/auth", get(auth::auth))
+        .route("/auth/otp", post(auth::verify_otp))
+        .route("/sign-in/password", post(password::sign_in))
+        .route("/password/reset", post(password::reset))
+        .route("/oauth/authorize", get(oauth::authorize))
+        .route("/oauth/callback", get(oauth::callback))
+        .route_layer(from_fn_with_state(state.clone(), csrf_middleware))
+        .route_layer(from_fn_with_state(
+            RateLimited::new(state.clone(), AUTH_RATE_LIMITS),
+            rate_limit::middleware,
+        ));
 
     let setup_router = Router::new()
         .route("/setup", post(settings::setup))
-        .route("/setup", get(settings::setup_check));
+        .route("/setup", get(settings::setup_check))
+        .route_layer(from_fn_with_state(state.clone(), csrf_middleware));
 
-    let public_router = Router::new().nest_service("/public", ServeDir::new("public"));
+    // files on disk are served directly, remote storages redirect to the object url
+    let public_router = match state.storage.local_root() {
+        Some(root) => {
+            Router::new().nest_service(&format!("/{}", PUBLIC_FOLDER_NAME), ServeDir::new(root))
+        }
+        None => Router::new().route(&format!("/{}/*src", PUBLIC_FOLDER_NAME), get(image::file)),
+    };
 
     Router::new()
         .merge(category_router)
         .merge(unit_router)
+        .merge(currency_router)
+        .merge(locale_router)
         .merge(product_router)
         .merge(public_router)
+        .merge(bundle_router)
+        .merge(download_router)
         .merge(cart_router)
+        .merge(csrf_router)
+        .merge(wishlist_router)
+        .merge(sign_in_router)
         .merge(auth_router)
         .merge(setup_router)
-        .route_layer(from_fn_with_state(state, optional_auth::middleware))
+        .route_layer(from_fn_with_state(state.clone(), optional_auth::middleware))
+        .route_layer(from_fn_with_state(
+            RateLimited::new(state, PUBLIC_RATE_LIMITS),
+            rate_limit::middleware,
+        ))
 }
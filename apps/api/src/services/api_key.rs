@@ -0,0 +1,100 @@
+use chrono::{Duration, NaiveDateTime};
+use sqlx::{postgres::PgQueryResult, PgConnection};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        api_key::{ApiKey, ApiKeyOwner},
+        role::Role,
+        user::User,
+    },
+    utils::constants::API_KEY_TOUCH_INTERVAL,
+    validations::api_key::StoreApiKeySchema,
+};
+
+pub async fn all(db: &mut PgConnection) -> Result<Vec<ApiKey>, sqlx::Error> {
+    sqlx::query_as!(ApiKey, "SELECT * FROM api_keys ORDER BY created_at DESC")
+        .fetch_all(&mut *db)
+        .await
+}
+
+// expired keys are kept so they still show up in the list, they just stop working
+pub async fn find_by_hash(
+    key_hash: &str,
+    now: NaiveDateTime,
+    db: &mut PgConnection,
+) -> Result<Option<ApiKey>, sqlx::Error> {
+    sqlx::query_as!(
+        ApiKey,
+        "SELECT * FROM api_keys WHERE key_hash = $1 AND (expires_at IS NULL OR expires_at > $2)",
+        key_hash,
+        now
+    )
+    .fetch_optional(&mut *db)
+    .await
+}
+
+pub async fn find_owner(
+    user_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<Option<ApiKeyOwner>, sqlx::Error> {
+    sqlx::query_as!(
+        ApiKeyOwner,
+        r#"
+            SELECT
+                (users.id, users.name, users.email, users.role_id, users.created_at, users.customer_group_id) as "user!: User",
+                (roles.id, roles.name, roles.created_at) as "role!: Role",
+                ARRAY(SELECT permission FROM role_permissions WHERE role_id = roles.id ORDER BY permission) as "permissions!"
+            FROM users
+            JOIN roles ON users.role_id = roles.id
+            WHERE users.id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(&mut *db)
+    .await
+}
+
+pub async fn insert(
+    input: &StoreApiKeySchema,
+    db: &mut PgConnection,
+) -> Result<ApiKey, sqlx::Error> {
+    sqlx::query_as!(
+        ApiKey,
+        r#"
+            INSERT INTO api_keys (name, prefix, key_hash, scopes, user_id, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+        "#,
+        input.name,
+        input.prefix,
+        input.key_hash,
+        &input.scopes,
+        input.user_id,
+        input.expires_at
+    )
+    .fetch_one(&mut *db)
+    .await
+}
+
+// written at most once per interval so busy integrations don't update the row on every call
+pub async fn touch(
+    id: &Uuid,
+    now: NaiveDateTime,
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        "UPDATE api_keys SET last_used_at = $2 WHERE id = $1 AND (last_used_at IS NULL OR last_used_at < $3)",
+        id,
+        now,
+        now - Duration::seconds(API_KEY_TOUCH_INTERVAL)
+    )
+    .execute(&mut *db)
+    .await
+}
+
+pub async fn destroy(id: &Uuid, db: &mut PgConnection) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!("DELETE FROM api_keys WHERE id = $1", id)
+        .execute(&mut *db)
+        .await
+}
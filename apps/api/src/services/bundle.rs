@@ -0,0 +1,170 @@
+use sqlx::{postgres::PgQueryResult, PgConnection};
+use uuid::Uuid;
+
+use crate::{
+    models::bundle::{Bundle, BundleComponent, BundleItem, PopulatedBundle},
+    validations::bundle::StoreBundleSchema,
+};
+
+pub async fn components(
+    bundle_ids: &Vec<Uuid>,
+    db: &mut PgConnection,
+) -> Result<Vec<BundleComponent>, sqlx::Error> {
+    sqlx::query_as!(
+        BundleComponent,
+        r#"
+          SELECT
+            bundle_items.bundle_id,
+            bundle_items.variant_id,
+            bundle_items.quantity,
+            products.id AS product_id,
+            products.name AS product_name,
+            product_variants.sku
+          FROM bundle_items
+          JOIN product_variants ON product_variants.id = bundle_items.variant_id
+          JOIN products ON products.id = product_variants.product_id
+          WHERE bundle_items.bundle_id = ANY($1)
+          ORDER BY products.name
+        "#,
+        bundle_ids
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
+async fn populate(
+    bundles: Vec<Bundle>,
+    db: &mut PgConnection,
+) -> Result<Vec<PopulatedBundle>, sqlx::Error> {
+    let components = components(
+        &bundles.iter().map(|bundle| bundle.id).collect::<Vec<_>>(),
+        db,
+    )
+    .await?;
+
+    Ok(bundles
+        .into_iter()
+        .map(|bundle| PopulatedBundle {
+            components: components
+                .iter()
+                .filter(|component| component.bundle_id == bundle.id)
+                .cloned()
+                .collect(),
+            bundle,
+        })
+        .collect())
+}
+
+pub async fn all(db: &mut PgConnection) -> Result<Vec<PopulatedBundle>, sqlx::Error> {
+    let bundles = sqlx::query_as!(Bundle, "SELECT * FROM bundles ORDER BY created_at")
+        .fetch_all(&mut *db)
+        .await?;
+
+    populate(bundles, db).await
+}
+
+pub async fn find(
+    id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<Option<PopulatedBundle>, sqlx::Error> {
+    let bundle = sqlx::query_as!(Bundle, "SELECT * FROM bundles WHERE id = $1", id)
+        .fetch_optional(&mut *db)
+        .await?;
+
+    match bundle {
+        Some(bundle) => Ok(populate(vec![bundle], db).await?.pop()),
+        None => Ok(None),
+    }
+}
+
+pub async fn find_many(ids: &Vec<Uuid>, db: &mut PgConnection) -> Result<Vec<Bundle>, sqlx::Error> {
+    sqlx::query_as!(Bundle, "SELECT * FROM bundles WHERE id = ANY($1)", ids)
+        .fetch_all(&mut *db)
+        .await
+}
+
+pub async fn items(
+    bundle_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<Vec<BundleItem>, sqlx::Error> {
+    sqlx::query_as!(
+        BundleItem,
+        "SELECT * FROM bundle_items WHERE bundle_id = $1",
+        bundle_id
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
+async fn attach_items(
+    bundle_id: &Uuid,
+    input: &StoreBundleSchema,
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+          INSERT INTO bundle_items(bundle_id, variant_id, quantity)
+          SELECT $1, * FROM UNNEST($2::UUID[], $3::INTEGER[])
+        "#,
+        bundle_id,
+        &input
+            .items
+            .iter()
+            .map(|item| item.variant_id)
+            .collect::<Vec<_>>(),
+        &input
+            .items
+            .iter()
+            .map(|item| item.quantity)
+            .collect::<Vec<_>>()
+    )
+    .execute(&mut *db)
+    .await
+}
+
+pub async fn insert(
+    input: &StoreBundleSchema,
+    db: &mut PgConnection,
+) -> Result<Bundle, sqlx::Error> {
+    let bundle = sqlx::query_as!(
+        Bundle,
+        "INSERT INTO bundles(name, description, price) VALUES ($1, $2, $3) RETURNING *",
+        input.name,
+        input.description,
+        input.price
+    )
+    .fetch_one(&mut *db)
+    .await?;
+
+    attach_items(&bundle.id, input, db).await?;
+
+    Ok(bundle)
+}
+
+pub async fn update(
+    id: &Uuid,
+    input: &StoreBundleSchema,
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        "UPDATE bundles SET name = $2, description = $3, price = $4 WHERE id = $1",
+        id,
+        input.name,
+        input.description,
+        input.price
+    )
+    .execute(&mut *db)
+    .await?;
+
+    sqlx::query!("DELETE FROM bundle_items WHERE bundle_id = $1", id)
+        .execute(&mut *db)
+        .await?;
+
+    attach_items(id, input, db).await
+}
+
+pub async fn destroy(id: &Uuid, db: &mut PgConnection) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!("DELETE FROM bundles WHERE id = $1", id)
+        .execute(&mut *db)
+        .await
+}
@@ -1,18 +1,30 @@
-use sqlx::{postgres::PgQueryResult, PgConnection};
+use chrono::NaiveDateTime;
+use sqlx::{postgres::PgQueryResult, Acquire, PgConnection};
+use thiserror::Error;
 use uuid::Uuid;
 
 use crate::{
-    models::cart::{Cart, CartItem, PopulatedCart},
+    models::cart::{Cart, CartItem, CartLine, PopulatedCart},
+    services,
     validations::cart::{StoreCartItemSchema, StoreCartSchema},
 };
 
+#[derive(Debug, Error)]
+pub enum CartError {
+    #[error("Not enough stock")]
+    OutOfStock,
+
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
 pub async fn all(db: &mut PgConnection) -> Result<Vec<PopulatedCart>, sqlx::Error> {
     let carts = sqlx::query_as!(
         PopulatedCart,
         r#"
             SELECT 
                 (carts.id, carts.user_id) as "cart!: Cart",
-                array_agg((cart_items.id, cart_items.quantity, cart_items.cart_id, cart_items.variant_id)) as "items!: Vec<CartItem>"
+                array_agg((cart_items.id, cart_items.quantity, cart_items.cart_id, cart_items.variant_id, cart_items.bundle_id)) as "items!: Vec<CartItem>"
             FROM carts
             LEFT JOIN cart_items ON carts.id = cart_items.cart_id
             GROUP BY carts.id, "cart!: Cart"
@@ -39,7 +51,7 @@ pub async fn find(id: &Uuid, db: &mut PgConnection) -> Result<Option<PopulatedCa
         r#"
             SELECT 
                 (carts.id, carts.user_id) as "cart!: Cart",
-                array_agg((cart_items.id, cart_items.quantity, cart_items.cart_id, cart_items.variant_id)) as "items!: Vec<CartItem>"
+                array_agg((cart_items.id, cart_items.quantity, cart_items.cart_id, cart_items.variant_id, cart_items.bundle_id)) as "items!: Vec<CartItem>"
             FROM carts
             LEFT JOIN cart_items ON carts.id = cart_items.cart_id
             WHERE carts.id = $1
@@ -72,7 +84,7 @@ pub async fn find_by_user_id(
         r#"
             SELECT 
                 (carts.id, carts.user_id) as "cart!: Cart",
-                array_agg((cart_items.id, cart_items.quantity, cart_items.cart_id, cart_items.variant_id)) as "items!: Vec<CartItem>"
+                array_agg((cart_items.id, cart_items.quantity, cart_items.cart_id, cart_items.variant_id, cart_items.bundle_id)) as "items!: Vec<CartItem>"
             FROM carts
             LEFT JOIN cart_items ON carts.id = cart_items.cart_id
             WHERE carts.user_id = $1
@@ -108,7 +120,7 @@ pub async fn insert(
             ) 
             SELECT 
                 (new_cart.id, new_cart.user_id) as "cart!: Cart",
-                array_agg((cart_items.id, cart_items.quantity, cart_items.cart_id, cart_items.variant_id)) as "items!: Vec<CartItem>"
+                array_agg((cart_items.id, cart_items.quantity, cart_items.cart_id, cart_items.variant_id, cart_items.bundle_id)) as "items!: Vec<CartItem>"
             FROM new_cart
             LEFT JOIN cart_items ON new_cart.id = cart_items.cart_id
             GROUP BY new_cart.id, "cart!: Cart"
@@ -139,26 +151,70 @@ pub async fn destroy(id: &Uuid, db: &mut PgConnection) -> Result<PgQueryResult,
         .await
 }
 
+// bundle lines hold their components' stock as long as a checkout would
+async fn reserve_bundle(
+    item: &CartItem,
+    expires_at: NaiveDateTime,
+    connection: &mut PgConnection,
+) -> Result<(), CartError> {
+    let (Some(id), Some(bundle_id), Some(quantity)) = (item.id, item.bundle_id, item.quantity)
+    else {
+        return Ok(());
+    };
+
+    for component in services::bundle::items(&bundle_id, connection).await? {
+        let reserved = services::stock::reserve(
+            &id,
+            &component.variant_id,
+            component.quantity * quantity,
+            expires_at,
+            connection,
+        )
+        .await?;
+
+        if !reserved {
+            return Err(CartError::OutOfStock);
+        }
+    }
+
+    Ok(())
+}
+
 pub async fn add_item(
     cart_id: &Uuid,
     input: &StoreCartItemSchema,
+    expires_at: NaiveDateTime,
     connection: &mut PgConnection,
-) -> Result<PgQueryResult, sqlx::Error> {
-    sqlx::query!(
-        "INSERT INTO cart_items(variant_id, cart_id, quantity) VALUES ($1, $2, $3)",
+) -> Result<CartItem, CartError> {
+    let mut tx = connection.begin().await?;
+
+    let item = sqlx::query_as!(
+        CartItem,
+        "INSERT INTO cart_items(variant_id, bundle_id, cart_id, quantity) VALUES ($1, $2, $3, $4) RETURNING *",
         input.variant_id,
+        input.bundle_id,
         cart_id,
         input.quantity
     )
-    .execute(connection)
-    .await
+    .fetch_one(&mut *tx)
+    .await?;
+
+    reserve_bundle(&item, expires_at, &mut tx).await?;
+    services::stock::release_checkout(cart_id, &mut tx).await?;
+
+    tx.commit().await?;
+
+    Ok(item)
 }
 
+// any change to the cart gives up a running checkout
 pub async fn delete_item(
     cart_id: &Uuid,
     item_id: &Uuid,
     connection: &mut PgConnection,
 ) -> Result<PgQueryResult, sqlx::Error> {
+    services::stock::release_checkout(cart_id, connection).await?;
+
     sqlx::query!(
         "DELETE FROM cart_items WHERE cart_id = $1 AND id = $2",
         cart_id,
@@ -168,20 +224,129 @@ pub async fn delete_item(
     .await
 }
 
+// holds stock for every line until expires_at, bundle lines renew theirs
+pub async fn checkout(
+    cart: &PopulatedCart,
+    expires_at: NaiveDateTime,
+    connection: &mut PgConnection,
+) -> Result<(), CartError> {
+    let mut tx = connection.begin().await?;
+
+    services::stock::release_checkout(&cart.cart.id, &mut tx).await?;
+
+    for item in cart.items.iter() {
+        if let (Some(id), Some(_)) = (item.id, item.bundle_id) {
+            services::stock::release(&id, &mut tx).await?;
+            reserve_bundle(item, expires_at, &mut tx).await?;
+        }
+
+        let (Some(id), Some(variant_id), Some(quantity)) =
+            (item.id, item.variant_id, item.quantity)
+        else {
+            continue;
+        };
+
+        // files don't run out, digital variants hold no stock
+        if services::download::find_file(&variant_id, &mut tx)
+            .await?
+            .is_some()
+        {
+            continue;
+        }
+
+        let reserved =
+            services::stock::reserve(&id, &variant_id, quantity, expires_at, &mut tx).await?;
+
+        if !reserved {
+            return Err(CartError::OutOfStock);
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
 pub async fn update_item(
     cart_id: &Uuid,
     item_id: &Uuid,
     input: &StoreCartItemSchema,
+    expires_at: NaiveDateTime,
     connection: &mut PgConnection,
-) -> Result<PgQueryResult, sqlx::Error> {
-    sqlx::query!(
-        "UPDATE cart_items SET variant_id = $3, cart_id = $4, quantity = $5 WHERE cart_id = $1 AND id = $2",
+) -> Result<(), CartError> {
+    let mut tx = connection.begin().await?;
+
+    let item = sqlx::query_as!(
+        CartItem,
+        "UPDATE cart_items SET variant_id = $3, bundle_id = $4, quantity = $5 WHERE cart_id = $1 AND id = $2 RETURNING *",
         cart_id,
         item_id,
         input.variant_id,
-        cart_id,
+        input.bundle_id,
         input.quantity
     )
-    .execute(connection)
-    .await
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    if let Some(item) = item {
+        services::stock::release(item_id, &mut tx).await?;
+        reserve_bundle(&item, expires_at, &mut tx).await?;
+        services::stock::release_checkout(cart_id, &mut tx).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+// prices resolve on every read so lines follow price lists and sales as they start and end,
+// bundles keep their own price
+pub async fn lines(
+    items: Vec<CartItem>,
+    customer_group_id: Option<Uuid>,
+    connection: &mut PgConnection,
+) -> Result<Vec<CartLine>, sqlx::Error> {
+    let bundle_ids = items
+        .iter()
+        .filter_map(|item| item.bundle_id)
+        .collect::<Vec<_>>();
+
+    let components = services::bundle::components(&bundle_ids, connection).await?;
+    let bundles = services::bundle::find_many(&bundle_ids, connection).await?;
+
+    let pricing = services::price_list::pricing(
+        &items.iter().filter_map(|item| item.variant_id).collect(),
+        customer_group_id,
+        connection,
+    )
+    .await?;
+
+    Ok(items
+        .into_iter()
+        .map(|item| {
+            let quantity = item.quantity.unwrap_or(0);
+
+            let unit_price = match (item.variant_id, item.bundle_id) {
+                (Some(variant_id), _) => pricing.price(&variant_id, quantity),
+                (_, Some(bundle_id)) => bundles
+                    .iter()
+                    .find(|bundle| bundle.id == bundle_id)
+                    .map(|bundle| bundle.price),
+                _ => None,
+            }
+            .unwrap_or_default();
+
+            CartLine {
+                components: components
+                    .iter()
+                    .filter(|component| Some(component.bundle_id) == item.bundle_id)
+                    .cloned()
+                    .collect(),
+                unit_price,
+                total: unit_price * quantity as f32,
+                currency: String::new(),
+                item,
+            }
+        })
+        .collect())
 }
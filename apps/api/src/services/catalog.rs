@@ -0,0 +1,405 @@
+use std::collections::{HashMap, HashSet};
+
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        category::Category,
+        product::{ImportError, ImportReport, Product, ProductRow},
+        unit::Unit,
+    },
+    services,
+    validations::product::{StoreProductSchema, Variant, VariantOption},
+};
+
+// separates options and images inside a single csv cell
+const LIST_SEPARATOR: char = ';';
+
+const HEADERS: [&str; 9] = [
+    "slug",
+    "name",
+    "description",
+    "category",
+    "unit",
+    "sku",
+    "price",
+    "options",
+    "images",
+];
+
+pub async fn export(db: &mut PgConnection) -> Result<Vec<ProductRow>, sqlx::Error> {
+    sqlx::query_as!(
+        ProductRow,
+        r#"
+          SELECT
+            products.slug,
+            products.name,
+            products.description,
+            categories.name AS category,
+            units.name AS unit,
+            product_variants.sku AS "sku?",
+            product_variants.price AS "price?",
+            (
+              SELECT string_agg(product_variant_collection_keys.name || '=' || product_variant_collection_values.name, ';' ORDER BY product_variant_collection_keys.name)
+              FROM product_variant_collections
+              JOIN product_variant_collection_keys ON product_variant_collection_keys.id = product_variant_collections.key_id
+              JOIN product_variant_collection_values ON product_variant_collection_values.id = product_variant_collections.value_id
+              WHERE product_variant_collections.variant_id = product_variants.id
+            ) AS options,
+            (
+              SELECT string_agg(images.src, ';' ORDER BY images.src)
+              FROM product_image
+              JOIN images ON images.id = product_image.image_id
+              WHERE product_image.product_id = products.id
+            ) AS images
+          FROM products
+          JOIN categories ON categories.id = products.category_id
+          JOIN units ON units.id = products.unit_id
+          LEFT JOIN product_variants ON product_variants.product_id = products.id
+          ORDER BY products.created_at, products.id, product_variants.sku
+        "#
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
+pub fn to_csv(rows: &Vec<ProductRow>) -> Result<Vec<u8>, csv::Error> {
+    // headers are written by hand so an empty catalog still exports a usable template
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(Vec::new());
+
+    writer.write_record(HEADERS)?;
+
+    for row in rows {
+        writer.serialize(row)?;
+    }
+
+    writer
+        .into_inner()
+        .map_err(|err| csv::Error::from(err.into_error()))
+}
+
+fn split(cell: &Option<String>) -> Vec<String> {
+    cell.as_deref()
+        .unwrap_or_default()
+        .split(LIST_SEPARATOR)
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn parse_options(cell: &Option<String>) -> Result<Vec<VariantOption>, String> {
+    split(cell)
+        .into_iter()
+        .map(|option| match option.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() && !value.trim().is_empty() => {
+                Ok(VariantOption {
+                    key: key.trim().to_string(),
+                    value: value.trim().to_string(),
+                })
+            }
+            _ => Err(format!("Invalid option {}, expected key=value", option)),
+        })
+        .collect()
+}
+
+// checks a single row on its own, returns the variant it describes if any
+fn validate_row(row: &ProductRow) -> Result<Option<Variant>, String> {
+    if row.name.trim().is_empty() {
+        return Err("Name is required".to_string());
+    }
+
+    if row.category.trim().is_empty() {
+        return Err("Category is required".to_string());
+    }
+
+    if row.unit.trim().is_empty() {
+        return Err("Unit is required".to_string());
+    }
+
+    let options = parse_options(&row.options)?;
+
+    let price = match row.price {
+        Some(price) => price,
+        None if options.is_empty() && row.sku.is_none() => return Ok(None),
+        None => return Err("Price is required".to_string()),
+    };
+
+    if price < 1.0 {
+        return Err("Price must be at least 1".to_string());
+    }
+
+    if options.is_empty() {
+        return Err("At least one option is required".to_string());
+    }
+
+    Ok(Some(Variant {
+        options,
+        price,
+        sku: row.sku.clone(),
+    }))
+}
+
+struct Group {
+    slug: String,
+    // products matched by sku keep their slug unless the csv sets one
+    explicit_slug: bool,
+    line: u64,
+    row: ProductRow,
+    variants: Vec<Variant>,
+    images: Vec<(u64, String)>,
+    product: Option<Product>,
+}
+
+// imports the csv into the given connection, nothing is written when the report holds errors
+pub async fn import(csv: &str, db: &mut PgConnection) -> Result<ImportReport, sqlx::Error> {
+    let mut report = ImportReport::default();
+    let mut groups: Vec<Group> = Vec::new();
+    let mut skus: HashMap<String, u64> = HashMap::new();
+
+    let mut reader = csv::Reader::from_reader(csv.as_bytes());
+
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(err) => {
+            report.errors.push(ImportError {
+                line: 1,
+                error: err.to_string(),
+            });
+            return Ok(report);
+        }
+    };
+
+    for record in reader.records() {
+        let (line, row) = match record {
+            Ok(record) => {
+                let line = record
+                    .position()
+                    .map(|position| position.line())
+                    .unwrap_or(0);
+
+                match record.deserialize::<ProductRow>(Some(&headers)) {
+                    Ok(row) => (line, row),
+                    Err(err) => {
+                        report.errors.push(ImportError {
+                            line,
+                            error: err.to_string(),
+                        });
+                        continue;
+                    }
+                }
+            }
+            Err(err) => {
+                report.errors.push(ImportError {
+                    line: err.position().map(|position| position.line()).unwrap_or(0),
+                    error: err.to_string(),
+                });
+                continue;
+            }
+        };
+
+        let variant = match validate_row(&row) {
+            Ok(variant) => variant,
+            Err(error) => {
+                report.errors.push(ImportError { line, error });
+                continue;
+            }
+        };
+
+        if let Some(sku) = &row.sku {
+            if let Some(first) = skus.insert(sku.clone(), line) {
+                report.errors.push(ImportError {
+                    line,
+                    error: format!("SKU {} is already used on line {}", sku, first),
+                });
+                continue;
+            }
+        }
+
+        let slug = match &row.slug {
+            Some(slug) => slug.clone(),
+            None => slug::slugify(&row.name),
+        };
+
+        // rows sharing a slug describe the same product, the first one holds its details
+        let index = match groups.iter().position(|group| group.slug == slug) {
+            Some(index) => index,
+            None => {
+                groups.push(Group {
+                    slug,
+                    explicit_slug: row.slug.is_some(),
+                    line,
+                    row: row.clone(),
+                    variants: Vec::new(),
+                    images: Vec::new(),
+                    product: None,
+                });
+                groups.len() - 1
+            }
+        };
+
+        for src in split(&row.images) {
+            groups[index].images.push((line, src));
+        }
+
+        if let Some(variant) = variant {
+            groups[index].variants.push(variant);
+        }
+    }
+
+    // images are referenced by src and must already be uploaded
+    let srcs = groups
+        .iter()
+        .flat_map(|group| group.images.iter().map(|(_, src)| src.clone()))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    let images = services::image::find_by_srcs(&srcs, &mut *db).await?;
+
+    // existing variants decide which product a sku belongs to
+    let variants = services::product::find_variants_by_skus(
+        &skus.keys().cloned().collect::<Vec<_>>(),
+        &mut *db,
+    )
+    .await?;
+
+    for group in groups.iter_mut() {
+        for (line, src) in &group.images {
+            if !images.iter().any(|image| &image.src == src) {
+                report.errors.push(ImportError {
+                    line: *line,
+                    error: format!("Image {} not found", src),
+                });
+            }
+        }
+
+        let owners = group
+            .variants
+            .iter()
+            .filter_map(|variant| variant.sku.as_ref())
+            .filter_map(|sku| variants.iter().find(|v| v.sku.as_ref() == Some(sku)))
+            .map(|variant| variant.product_id)
+            .collect::<HashSet<_>>();
+
+        let product = match services::product::find_by_slug(&group.slug, &mut *db).await? {
+            Some(product) => Some(product),
+            None if owners.len() == 1 => {
+                let id = owners.iter().next().unwrap();
+                sqlx::query_as!(Product, "SELECT * FROM products WHERE id = $1", id)
+                    .fetch_optional(&mut *db)
+                    .await?
+            }
+            None => None,
+        };
+
+        let foreign = owners
+            .iter()
+            .any(|owner| Some(owner) != product.as_ref().map(|product| &product.id));
+
+        if foreign {
+            report.errors.push(ImportError {
+                line: group.line,
+                error: "SKUs belong to another product".to_string(),
+            });
+        }
+
+        group.product = product;
+    }
+
+    if !report.errors.is_empty() {
+        report.errors.sort_by_key(|error| error.line);
+        return Ok(report);
+    }
+
+    let mut categories: HashMap<String, Uuid> = HashMap::new();
+    let mut units: HashMap<String, Uuid> = HashMap::new();
+
+    for group in groups {
+        let category_id = match categories.get(&group.row.category) {
+            Some(id) => *id,
+            None => {
+                let category =
+                    match services::category::find_by_name(&group.row.category, &mut *db).await? {
+                        Some(category) => category,
+                        None => {
+                            report.categories.push(group.row.category.clone());
+                            sqlx::query_as!(
+                                Category,
+                                "INSERT INTO categories(name) VALUES ($1) RETURNING *",
+                                group.row.category
+                            )
+                            .fetch_one(&mut *db)
+                            .await?
+                        }
+                    };
+
+                categories.insert(group.row.category.clone(), category.id);
+                category.id
+            }
+        };
+
+        let unit_id = match units.get(&group.row.unit) {
+            Some(id) => *id,
+            None => {
+                let unit = match services::unit::find_by_name(&group.row.unit, &mut *db).await? {
+                    Some(unit) => unit,
+                    None => {
+                        report.units.push(group.row.unit.clone());
+                        sqlx::query_as!(
+                            Unit,
+                            "INSERT INTO units(name) VALUES ($1) RETURNING *",
+                            group.row.unit
+                        )
+                        .fetch_one(&mut *db)
+                        .await?
+                    }
+                };
+
+                units.insert(group.row.unit.clone(), unit.id);
+                unit.id
+            }
+        };
+
+        let slug = match (&group.product, group.explicit_slug) {
+            (Some(product), false) => product.slug.clone().or(Some(group.slug)),
+            _ => Some(group.slug),
+        };
+
+        let input = StoreProductSchema {
+            name: group.row.name.clone(),
+            slug,
+            description: group.row.description.clone(),
+            unit_id,
+            category_id,
+            variants: group.variants,
+            images: images
+                .iter()
+                .filter(|image| group.images.iter().any(|(_, src)| src == &image.src))
+                .map(|image| image.id)
+                .collect(),
+        };
+
+        let product = match group.product {
+            Some(product) => {
+                services::product::update(&product.id, &input, &mut *db).await?;
+                product.upsert_variants(&input.variants, &mut *db).await?;
+                product.detach_images(&mut *db).await?;
+                report.updated += 1;
+                product
+            }
+            None => {
+                let product = services::product::insert(&input, &mut *db).await?;
+                product.attach_variants(&input.variants, &mut *db).await?;
+                report.created += 1;
+                product
+            }
+        };
+
+        product.attach_images(&input.images, &mut *db).await?;
+    }
+
+    Ok(report)
+}
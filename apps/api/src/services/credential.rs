@@ -0,0 +1,83 @@
+use chrono::NaiveDateTime;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::models::credential::{Credential, PasswordReset};
+
+pub async fn find(
+    user_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<Option<Credential>, sqlx::Error> {
+    sqlx::query_as!(
+        Credential,
+        "SELECT user_id, password_hash, updated_at FROM credentials WHERE user_id = $1",
+        user_id
+    )
+    .fetch_optional(db)
+    .await
+}
+
+pub async fn set(
+    user_id: &Uuid,
+    password_hash: &str,
+    now: &NaiveDateTime,
+    db: &mut PgConnection,
+) -> Result<Credential, sqlx::Error> {
+    sqlx::query_as!(
+        Credential,
+        r#"
+            INSERT INTO credentials(user_id, password_hash, updated_at) VALUES ($1, $2, $3)
+            ON CONFLICT (user_id) DO UPDATE SET password_hash = $2, updated_at = $3
+            RETURNING user_id, password_hash, updated_at
+        "#,
+        user_id,
+        password_hash,
+        now
+    )
+    .fetch_one(db)
+    .await
+}
+
+// a user only ever holds the reset link they asked for last
+pub async fn insert_reset(
+    user_id: &Uuid,
+    token_hash: &str,
+    expires_at: &NaiveDateTime,
+    db: &mut PgConnection,
+) -> Result<PasswordReset, sqlx::Error> {
+    sqlx::query!("DELETE FROM password_resets WHERE user_id = $1", user_id)
+        .execute(&mut *db)
+        .await?;
+
+    sqlx::query_as!(
+        PasswordReset,
+        r#"
+            INSERT INTO password_resets(token_hash, user_id, expires_at) VALUES ($1, $2, $3)
+            RETURNING id, token_hash, user_id, expires_at, created_at
+        "#,
+        token_hash,
+        user_id,
+        expires_at
+    )
+    .fetch_one(db)
+    .await
+}
+
+// deleting is the check, so a reset link can only ever be used once
+pub async fn consume_reset(
+    token_hash: &str,
+    now: &NaiveDateTime,
+    db: &mut PgConnection,
+) -> Result<Option<PasswordReset>, sqlx::Error> {
+    sqlx::query_as!(
+        PasswordReset,
+        r#"
+            DELETE FROM password_resets WHERE token_hash = $1 AND expires_at > $2
+            RETURNING id, token_hash, user_id, expires_at, created_at
+        "#,
+        token_hash,
+        now
+    )
+    .fetch_optional(db)
+    .await
+}
@@ -0,0 +1,129 @@
+use sqlx::{postgres::PgQueryResult, PgConnection};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        cart::CartLine,
+        currency::{Conversion, ExchangeRate},
+        product::SProduct,
+    },
+    services,
+    utils::constants::{DEFAULT_CURRENCY, SETTINGS},
+    validations::{currency::StoreExchangeRateSchema, settings::StoreSettingsSchema},
+};
+
+pub async fn base(db: &mut PgConnection) -> Result<String, sqlx::Error> {
+    Ok(services::settings::find_by_key(SETTINGS.base_currency, db)
+        .await?
+        .map(|setting| setting.value)
+        .unwrap_or(DEFAULT_CURRENCY.to_string()))
+}
+
+// prices are stored in the base currency, changing it doesn't convert them
+pub async fn set_base(currency: &str, db: &mut PgConnection) -> Result<(), sqlx::Error> {
+    let input = StoreSettingsSchema {
+        key: SETTINGS.base_currency.to_string(),
+        value: currency.to_string(),
+    };
+
+    match services::settings::find_by_key(SETTINGS.base_currency, db).await? {
+        Some(setting) => {
+            services::settings::update(&setting.id, &input, db).await?;
+        }
+        None => {
+            services::settings::insert(&input, db).await?;
+        }
+    };
+
+    Ok(())
+}
+
+pub async fn rates(db: &mut PgConnection) -> Result<Vec<ExchangeRate>, sqlx::Error> {
+    sqlx::query_as!(
+        ExchangeRate,
+        "SELECT * FROM exchange_rates ORDER BY currency"
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
+pub async fn find_rate(
+    currency: &str,
+    db: &mut PgConnection,
+) -> Result<Option<ExchangeRate>, sqlx::Error> {
+    sqlx::query_as!(
+        ExchangeRate,
+        "SELECT * FROM exchange_rates WHERE currency = $1",
+        currency
+    )
+    .fetch_optional(&mut *db)
+    .await
+}
+
+pub async fn set_rate(
+    input: &StoreExchangeRateSchema,
+    db: &mut PgConnection,
+) -> Result<ExchangeRate, sqlx::Error> {
+    sqlx::query_as!(
+        ExchangeRate,
+        r#"
+          INSERT INTO exchange_rates(currency, rate, decimals, rounding)
+          VALUES ($1, $2, $3, $4)
+          ON CONFLICT (currency) DO UPDATE
+          SET rate = EXCLUDED.rate, decimals = EXCLUDED.decimals, rounding = EXCLUDED.rounding
+          RETURNING *
+        "#,
+        input.currency,
+        input.rate,
+        input.decimals,
+        input.rounding
+    )
+    .fetch_one(&mut *db)
+    .await
+}
+
+pub async fn destroy_rate(id: &Uuid, db: &mut PgConnection) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!("DELETE FROM exchange_rates WHERE id = $1", id)
+        .execute(&mut *db)
+        .await
+}
+
+// nothing is returned for a currency without an exchange rate
+pub async fn conversion(
+    currency: Option<String>,
+    db: &mut PgConnection,
+) -> Result<Option<Conversion>, sqlx::Error> {
+    let base = base(db).await?;
+
+    match currency {
+        Some(currency) if currency != base => {
+            Ok(find_rate(&currency, db).await?.map(Conversion::from))
+        }
+        _ => Ok(Some(Conversion::base(base))),
+    }
+}
+
+pub fn convert_products(products: &mut [SProduct], conversion: &Conversion) {
+    for variant in products
+        .iter_mut()
+        .flat_map(|product| product.variants.iter_mut())
+    {
+        variant.currency = conversion.currency.clone();
+        variant.price = conversion.convert(variant.price);
+        variant.regular_price = conversion.convert(variant.regular_price);
+        variant.sale_price = variant.sale_price.map(|price| conversion.convert(price));
+
+        for tier in variant.tiers.iter_mut() {
+            tier.price = conversion.convert(tier.price);
+        }
+    }
+}
+
+// the total follows the converted unit price so a line always adds up
+pub fn convert_lines(lines: &mut [CartLine], conversion: &Conversion) {
+    for line in lines.iter_mut() {
+        line.currency = conversion.currency.clone();
+        line.unit_price = conversion.convert(line.unit_price);
+        line.total = conversion.multiply(line.unit_price, line.item.quantity.unwrap_or(0));
+    }
+}
@@ -0,0 +1,82 @@
+use sqlx::{postgres::PgQueryResult, PgConnection};
+use uuid::Uuid;
+
+use crate::{
+    models::customer_group::CustomerGroup, validations::customer_group::StoreCustomerGroupSchema,
+};
+
+pub async fn all(db: &mut PgConnection) -> Result<Vec<CustomerGroup>, sqlx::Error> {
+    sqlx::query_as!(CustomerGroup, "SELECT * FROM customer_groups ORDER BY name")
+        .fetch_all(&mut *db)
+        .await
+}
+
+pub async fn find(id: &Uuid, db: &mut PgConnection) -> Result<Option<CustomerGroup>, sqlx::Error> {
+    sqlx::query_as!(
+        CustomerGroup,
+        "SELECT * FROM customer_groups WHERE id = $1",
+        id
+    )
+    .fetch_optional(&mut *db)
+    .await
+}
+
+pub async fn find_by_name(
+    name: &str,
+    db: &mut PgConnection,
+) -> Result<Option<CustomerGroup>, sqlx::Error> {
+    sqlx::query_as!(
+        CustomerGroup,
+        "SELECT * FROM customer_groups WHERE name = $1",
+        name
+    )
+    .fetch_optional(&mut *db)
+    .await
+}
+
+pub async fn insert(
+    input: &StoreCustomerGroupSchema,
+    db: &mut PgConnection,
+) -> Result<CustomerGroup, sqlx::Error> {
+    sqlx::query_as!(
+        CustomerGroup,
+        "INSERT INTO customer_groups(name) VALUES ($1) RETURNING *",
+        input.name
+    )
+    .fetch_one(&mut *db)
+    .await
+}
+
+pub async fn update(
+    id: &Uuid,
+    input: &StoreCustomerGroupSchema,
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        "UPDATE customer_groups SET name = $2 WHERE id = $1",
+        id,
+        input.name
+    )
+    .execute(&mut *db)
+    .await
+}
+
+pub async fn destroy(id: &Uuid, db: &mut PgConnection) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!("DELETE FROM customer_groups WHERE id = $1", id)
+        .execute(&mut *db)
+        .await
+}
+
+pub async fn assign(
+    user_id: &Uuid,
+    customer_group_id: Option<Uuid>,
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        "UPDATE users SET customer_group_id = $2 WHERE id = $1",
+        user_id,
+        customer_group_id
+    )
+    .execute(&mut *db)
+    .await
+}
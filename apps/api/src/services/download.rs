@@ -0,0 +1,165 @@
+use chrono::{NaiveDateTime, Utc};
+use sqlx::{postgres::PgQueryResult, PgConnection};
+use uuid::Uuid;
+
+use crate::{
+    models::download::{DigitalFile, Download, UserDownload},
+    utils::storage::Storage,
+};
+
+pub async fn find_file(
+    variant_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<Option<DigitalFile>, sqlx::Error> {
+    sqlx::query_as!(
+        DigitalFile,
+        "SELECT * FROM digital_files WHERE variant_id = $1",
+        variant_id
+    )
+    .fetch_optional(&mut *db)
+    .await
+}
+
+// stores the file privately and makes the variant digital, replacing any previous file
+pub async fn attach_file(
+    variant_id: &Uuid,
+    name: &str,
+    key: &str,
+    content_type: &str,
+    bytes: &[u8],
+    storage: &dyn Storage,
+    db: &mut PgConnection,
+) -> Result<DigitalFile, Box<dyn std::error::Error>> {
+    let previous = find_file(variant_id, db).await?;
+
+    storage.put(key, bytes, content_type).await?;
+
+    let file = sqlx::query_as!(
+        DigitalFile,
+        r#"
+          INSERT INTO digital_files(name, key, content_type, variant_id)
+          VALUES ($1, $2, $3, $4)
+          ON CONFLICT (variant_id) DO UPDATE
+          SET name = EXCLUDED.name, key = EXCLUDED.key, content_type = EXCLUDED.content_type
+          RETURNING *
+        "#,
+        name,
+        key,
+        content_type,
+        variant_id
+    )
+    .fetch_one(&mut *db)
+    .await?;
+
+    if let Some(previous) = previous {
+        storage.delete(&previous.key).await?;
+    }
+
+    Ok(file)
+}
+
+pub async fn detach_file(
+    file: &DigitalFile,
+    storage: &dyn Storage,
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, Box<dyn std::error::Error>> {
+    let result = sqlx::query!("DELETE FROM digital_files WHERE id = $1", file.id)
+        .execute(&mut *db)
+        .await?;
+
+    storage.delete(&file.key).await?;
+
+    Ok(result)
+}
+
+// one download per digital file among the variants, other variants are ignored
+pub async fn grant(
+    user_id: &Uuid,
+    variant_ids: &Vec<Uuid>,
+    downloads_left: i32,
+    expires_at: NaiveDateTime,
+    db: &mut PgConnection,
+) -> Result<Vec<Download>, sqlx::Error> {
+    sqlx::query_as!(
+        Download,
+        r#"
+          INSERT INTO downloads(file_id, user_id, downloads_left, expires_at)
+          SELECT id, $1, $3, $4 FROM digital_files WHERE variant_id = ANY($2)
+          RETURNING *
+        "#,
+        user_id,
+        variant_ids,
+        downloads_left,
+        expires_at
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
+pub async fn by_user(
+    user_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<Vec<UserDownload>, sqlx::Error> {
+    sqlx::query_as!(
+        UserDownload,
+        r#"
+          SELECT
+            (downloads.id, downloads.downloads_left, downloads.expires_at, downloads.file_id, downloads.user_id, downloads.created_at) AS "download!: Download",
+            digital_files.name
+          FROM downloads
+          JOIN digital_files ON digital_files.id = downloads.file_id
+          WHERE downloads.user_id = $1
+          ORDER BY downloads.created_at DESC
+        "#,
+        user_id
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
+// uses up one download, nothing is returned once the link expired or ran out
+pub async fn consume(id: &Uuid, db: &mut PgConnection) -> Result<Option<DigitalFile>, sqlx::Error> {
+    let file_id = sqlx::query_scalar!(
+        r#"
+          UPDATE downloads SET downloads_left = downloads_left - 1
+          WHERE id = $1 AND downloads_left > 0 AND expires_at > $2
+          RETURNING file_id
+        "#,
+        id,
+        Utc::now().naive_utc()
+    )
+    .fetch_optional(&mut *db)
+    .await?;
+
+    match file_id {
+        Some(file_id) => {
+            sqlx::query_as!(
+                DigitalFile,
+                "SELECT * FROM digital_files WHERE id = $1",
+                file_id
+            )
+            .fetch_optional(&mut *db)
+            .await
+        }
+        None => Ok(None),
+    }
+}
+
+// a cart only needs shipping when some line holds a physical variant
+pub async fn requires_shipping(cart_id: &Uuid, db: &mut PgConnection) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+          SELECT EXISTS (
+            SELECT 1
+            FROM cart_items
+            LEFT JOIN bundle_items ON bundle_items.bundle_id = cart_items.bundle_id
+            LEFT JOIN digital_files
+              ON digital_files.variant_id = COALESCE(cart_items.variant_id, bundle_items.variant_id)
+            WHERE cart_items.cart_id = $1 AND digital_files.id IS NULL
+          ) AS "exists!"
+        "#,
+        cart_id
+    )
+    .fetch_one(&mut *db)
+    .await
+}
@@ -0,0 +1,72 @@
+use chrono::NaiveDateTime;
+use sqlx::PgConnection;
+
+use crate::{
+    models::identity::{Identity, OAuthState},
+    validations::identity::{StoreIdentitySchema, StoreOAuthStateSchema},
+};
+
+pub async fn find(
+    provider: &str,
+    subject: &str,
+    db: &mut PgConnection,
+) -> Result<Option<Identity>, sqlx::Error> {
+    sqlx::query_as!(
+        Identity,
+        "SELECT * FROM user_identities WHERE provider = $1 AND subject = $2",
+        provider,
+        subject
+    )
+    .fetch_optional(db)
+    .await
+}
+
+pub async fn insert(
+    input: &StoreIdentitySchema,
+    db: &mut PgConnection,
+) -> Result<Identity, sqlx::Error> {
+    sqlx::query_as!(
+        Identity,
+        "INSERT INTO user_identities(user_id, provider, subject, email) VALUES ($1, $2, $3, $4) RETURNING *",
+        input.user_id,
+        input.provider,
+        input.subject,
+        input.email
+    )
+    .fetch_one(db)
+    .await
+}
+
+pub async fn insert_state(
+    input: &StoreOAuthStateSchema,
+    db: &mut PgConnection,
+) -> Result<OAuthState, sqlx::Error> {
+    sqlx::query_as!(
+        OAuthState,
+        "INSERT INTO oauth_states(state_hash, verifier, expires_at) VALUES ($1, $2, $3) RETURNING *",
+        input.state_hash,
+        input.verifier,
+        input.expires_at
+    )
+    .fetch_one(db)
+    .await
+}
+
+// a state is good for one callback, expired ones are cleared on the way
+pub async fn consume_state(
+    state_hash: &str,
+    now: &NaiveDateTime,
+    db: &mut PgConnection,
+) -> Result<Option<OAuthState>, sqlx::Error> {
+    sqlx::query!("DELETE FROM oauth_states WHERE expires_at <= $1", now)
+        .execute(&mut *db)
+        .await?;
+
+    sqlx::query_as!(
+        OAuthState,
+        "DELETE FROM oauth_states WHERE state_hash = $1 RETURNING *",
+        state_hash
+    )
+    .fetch_optional(db)
+    .await
+}
@@ -1,13 +1,49 @@
-use std::path::Path;
+use std::{
+    collections::HashSet,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::Arc,
+    time::Duration,
+};
 
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use log::error;
 use sqlx::{postgres::PgQueryResult, Error, PgConnection};
-use tokio::{
-    fs::{self, File},
-    io::AsyncWriteExt,
-};
+use thiserror::Error;
+use url::Url;
 use uuid::Uuid;
 
-use crate::{models::image::Image, validations::image::StoreImageSchema};
+use crate::{
+    models::{
+        image::{Image, PopulatedImage},
+        product::Product,
+    },
+    utils::{constants::IMAGE_IMPORT_TIMEOUT, storage::Storage},
+    validations::image::StoreImageSchema,
+};
+
+#[derive(Debug, Error)]
+pub enum DownloadError {
+    #[error("Only http and https urls are allowed")]
+    InvalidScheme,
+
+    #[error("Only public addresses are allowed")]
+    PrivateAddress,
+
+    #[error("Couldn't fetch file")]
+    Request(#[from] reqwest::Error),
+
+    #[error("Remote server responded with {0}")]
+    Status(reqwest::StatusCode),
+
+    #[error("File too large")]
+    TooLarge,
+}
+
+pub struct Download {
+    pub name: String,
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
 
 pub async fn all(connection: &mut PgConnection) -> Result<Vec<Image>, Error> {
     sqlx::query_as!(Image, "SELECT * FROM images")
@@ -21,12 +57,92 @@ pub async fn find(id: &Uuid, connection: &mut PgConnection) -> Result<Option<Ima
         .await
 }
 
+pub async fn all_populated(connection: &mut PgConnection) -> Result<Vec<PopulatedImage>, Error> {
+    sqlx::query_as!(
+        PopulatedImage,
+        r#"
+            SELECT
+                (images.id, images.name, images.src, images.created_at) AS "image!: Image",
+                COALESCE(
+                    array_agg((products.id, products.name, products.description, products.unit_id, products.category_id, products.created_at, products.slug))
+                        FILTER (WHERE products.id IS NOT NULL),
+                    '{}'
+                ) AS "products!: Vec<Product>"
+            FROM images
+            LEFT JOIN product_image ON product_image.image_id = images.id
+            LEFT JOIN products ON products.id = product_image.product_id
+            GROUP BY images.id
+            ORDER BY images.created_at DESC
+        "#
+    )
+    .fetch_all(connection)
+    .await
+}
+
+pub async fn find_populated(
+    id: &Uuid,
+    connection: &mut PgConnection,
+) -> Result<Option<PopulatedImage>, Error> {
+    sqlx::query_as!(
+        PopulatedImage,
+        r#"
+            SELECT
+                (images.id, images.name, images.src, images.created_at) AS "image!: Image",
+                COALESCE(
+                    array_agg((products.id, products.name, products.description, products.unit_id, products.category_id, products.created_at, products.slug))
+                        FILTER (WHERE products.id IS NOT NULL),
+                    '{}'
+                ) AS "products!: Vec<Product>"
+            FROM images
+            LEFT JOIN product_image ON product_image.image_id = images.id
+            LEFT JOIN products ON products.id = product_image.product_id
+            WHERE images.id = $1
+            GROUP BY images.id
+        "#,
+        id
+    )
+    .fetch_optional(connection)
+    .await
+}
+
+pub async fn find_orphans(
+    created_before: &NaiveDateTime,
+    connection: &mut PgConnection,
+) -> Result<Vec<Image>, Error> {
+    sqlx::query_as!(
+        Image,
+        r#"
+            SELECT images.* FROM images
+            WHERE images.created_at < $1
+            AND NOT EXISTS (SELECT 1 FROM product_image WHERE product_image.image_id = images.id)
+        "#,
+        created_before
+    )
+    .fetch_all(connection)
+    .await
+}
+
+pub async fn all_srcs(connection: &mut PgConnection) -> Result<Vec<String>, Error> {
+    sqlx::query_scalar!("SELECT src FROM images")
+        .fetch_all(connection)
+        .await
+}
+
 pub async fn find_by_src(src: &str, connection: &mut PgConnection) -> Result<Option<Image>, Error> {
     sqlx::query_as!(Image, "SELECT * FROM images WHERE src = $1", src)
         .fetch_optional(connection)
         .await
 }
 
+pub async fn find_by_srcs(
+    srcs: &Vec<String>,
+    connection: &mut PgConnection,
+) -> Result<Vec<Image>, Error> {
+    sqlx::query_as!(Image, "SELECT * FROM images WHERE src = ANY($1)", srcs)
+        .fetch_all(connection)
+        .await
+}
+
 pub async fn insert(
     input: &StoreImageSchema,
     connection: &mut PgConnection,
@@ -44,24 +160,11 @@ pub async fn insert(
 pub async fn upload(
     input: &StoreImageSchema,
     bytes: &[u8],
+    content_type: &str,
+    storage: &dyn Storage,
     connection: &mut PgConnection,
 ) -> Result<Image, Box<dyn std::error::Error>> {
-    let public_path = Path::new("public");
-
-    if let Err(err) = fs::create_dir_all(&public_path).await {
-        return Err(err.into());
-    };
-
-    let file_path = public_path.join(&input.src);
-
-    let mut file = match File::create(file_path).await {
-        Ok(file) => file,
-        Err(err) => {
-            return Err(err.into());
-        }
-    };
-
-    if let Err(err) = file.write_all(bytes).await {
+    if let Err(err) = storage.put(&input.src, bytes, content_type).await {
         return Err(err.into());
     }
 
@@ -101,16 +204,12 @@ pub async fn destroy_many(
         .await
 }
 
-pub async fn unload(image: &Image, connection: &mut PgConnection) -> Result<PgQueryResult, Error> {
-    let public_path = Path::new("public");
-
-    if let Err(err) = fs::create_dir_all(&public_path).await {
-        return Err(err.into());
-    };
-
-    let file_path = public_path.join(&image.src);
-
-    if let Err(err) = fs::remove_file(file_path).await {
+pub async fn unload(
+    image: &Image,
+    storage: &dyn Storage,
+    connection: &mut PgConnection,
+) -> Result<PgQueryResult, Box<dyn std::error::Error>> {
+    if let Err(err) = storage.delete(&image.src).await {
         return Err(err.into());
     }
 
@@ -119,3 +218,179 @@ pub async fn unload(image: &Image, connection: &mut PgConnection) -> Result<PgQu
         Err(err) => Err(err.into()),
     }
 }
+
+pub async fn sweep(
+    created_before: &NaiveDateTime,
+    storage: &dyn Storage,
+    connection: &mut PgConnection,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut removed = 0;
+
+    // rows no product links to, a file we fail to delete is picked up below on the next run
+    for image in find_orphans(created_before, connection).await? {
+        if let Err(err) = storage.delete(&image.src).await {
+            error!("{err}");
+        }
+
+        destroy(&image.id, connection).await?;
+        removed += 1;
+    }
+
+    // files without a row, one we fail to delete is left for the next run
+    let srcs = all_srcs(connection)
+        .await?
+        .into_iter()
+        .collect::<HashSet<_>>();
+    let created_before = Utc.from_utc_datetime(created_before);
+
+    for object in storage.list().await? {
+        if object.modified_at >= created_before || srcs.contains(&object.key) {
+            continue;
+        }
+
+        if let Err(err) = storage.delete(&object.key).await {
+            error!("{err}");
+            continue;
+        }
+
+        removed += 1;
+    }
+
+    Ok(removed)
+}
+
+fn is_public_v4(ip: &Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // this network, carrier-grade nat, protocol assignments, benchmarking and reserved
+        || a == 0
+        || (a == 100 && (64..128).contains(&b))
+        || (a == 192 && b == 0 && c == 0)
+        || (a == 198 && (18..20).contains(&b))
+        || a >= 240)
+}
+
+fn is_public_v6(ip: &Ipv6Addr) -> bool {
+    if let Some(ip) = ip.to_ipv4_mapped() {
+        return is_public_v4(&ip);
+    }
+
+    let [first, second, ..] = ip.segments();
+
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // unique local, link local and documentation
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80
+        || (first == 0x2001 && second == 0x0db8))
+}
+
+fn is_public(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => is_public_v6(ip),
+    }
+}
+
+// resolves like the system does but never hands out an internal address, checked on every
+// connection so a host can't switch to one between lookups
+struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        Box::pin(async move {
+            let addrs = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_public(&addr.ip()))
+                .collect::<Vec<_>>();
+
+            if addrs.is_empty() {
+                return Err(DownloadError::PrivateAddress.into());
+            }
+
+            let addrs: reqwest::dns::Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+// redirects aren't followed, each hop would need the same address check
+pub fn download_client() -> Result<reqwest::Client, reqwest::Error> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(IMAGE_IMPORT_TIMEOUT))
+        .redirect(reqwest::redirect::Policy::none())
+        .dns_resolver(Arc::new(PublicResolver))
+        .build()
+}
+
+pub async fn download(
+    client: &reqwest::Client,
+    url: &Url,
+    limit: usize,
+) -> Result<Download, DownloadError> {
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(DownloadError::InvalidScheme);
+    }
+
+    // literal addresses never reach the resolver
+    let literal = match url.host() {
+        Some(url::Host::Ipv4(ip)) => Some(IpAddr::V4(ip)),
+        Some(url::Host::Ipv6(ip)) => Some(IpAddr::V6(ip)),
+        _ => None,
+    };
+
+    if literal.is_some_and(|ip| !is_public(&ip)) {
+        return Err(DownloadError::PrivateAddress);
+    }
+
+    let mut response = client.get(url.clone()).send().await?;
+
+    if !response.status().is_success() {
+        return Err(DownloadError::Status(response.status()));
+    }
+
+    if let Some(length) = response.content_length() {
+        if length > limit as u64 {
+            return Err(DownloadError::TooLarge);
+        }
+    }
+
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .and_then(|content_type| content_type.split(';').next())
+        .map(|content_type| content_type.trim().to_string())
+        .unwrap_or_default();
+
+    // the length header can lie, keep counting while reading
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if bytes.len() + chunk.len() > limit {
+            return Err(DownloadError::TooLarge);
+        }
+
+        bytes.extend_from_slice(&chunk);
+    }
+
+    let name = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("image")
+        .to_string();
+
+    Ok(Download {
+        name,
+        content_type,
+        bytes,
+    })
+}
@@ -1,3 +1,4 @@
+use chrono::{NaiveDateTime, Utc};
 use sqlx::{postgres::PgQueryResult, PgConnection};
 use uuid::Uuid;
 
@@ -14,8 +15,8 @@ pub async fn all(connection: &mut PgConnection) -> Result<Vec<PopulatedMagicToke
         PopulatedMagicToken,
         r#"
       SELECT 
-        (users.id, users.name, users.email, users.role_id, users.created_at) as "user!: User",
-        (magic_tokens.id, magic_tokens.token, magic_tokens.user_id, magic_tokens.expires_at) as "token!: MagicToken"
+        (users.id, users.name, users.email, users.role_id, users.created_at, users.customer_group_id) as "user!: User",
+        (magic_tokens.id, magic_tokens.token_hash, magic_tokens.otp_hash, magic_tokens.attempts, magic_tokens.user_id, magic_tokens.expires_at, magic_tokens.created_at) as "token!: MagicToken"
       FROM
         magic_tokens
       JOIN users ON users.id = magic_tokens.user_id
@@ -31,8 +32,8 @@ pub async fn find(
         PopulatedMagicToken,
         r#"
       SELECT 
-        (users.id, users.name, users.email, users.role_id, users.created_at) as "user!: User",
-        (magic_tokens.id, magic_tokens.token, magic_tokens.user_id, magic_tokens.expires_at) as "token!: MagicToken"
+        (users.id, users.name, users.email, users.role_id, users.created_at, users.customer_group_id) as "user!: User",
+        (magic_tokens.id, magic_tokens.token_hash, magic_tokens.otp_hash, magic_tokens.attempts, magic_tokens.user_id, magic_tokens.expires_at, magic_tokens.created_at) as "token!: MagicToken"
       FROM
         magic_tokens
       JOIN users ON users.id = magic_tokens.user_id
@@ -42,25 +43,6 @@ pub async fn find(
     ).fetch_optional(connection).await
 }
 
-pub async fn find_by_token(
-    token: &Uuid,
-    connection: &mut PgConnection,
-) -> Result<Option<PopulatedMagicToken>, sqlx::Error> {
-    sqlx::query_as!(
-        PopulatedMagicToken,
-        r#"
-      SELECT 
-        (users.id, users.name, users.email, users.role_id, users.created_at) as "user!: User",
-        (magic_tokens.id, magic_tokens.token, magic_tokens.user_id, magic_tokens.expires_at) as "token!: MagicToken"
-      FROM
-        magic_tokens
-      JOIN users ON users.id = magic_tokens.user_id
-      WHERE magic_tokens.token = $1
-    "#,
-    token
-    ).fetch_optional(connection).await
-}
-
 pub async fn find_by_user(
     user_id: &Uuid,
     connection: &mut PgConnection,
@@ -69,8 +51,8 @@ pub async fn find_by_user(
         PopulatedMagicToken,
         r#"
           SELECT 
-            (users.id, users.name, users.email, users.role_id, users.created_at) as "user!: User",
-            (magic_tokens.id, magic_tokens.token, magic_tokens.user_id, magic_tokens.expires_at) as "token!: MagicToken"
+            (users.id, users.name, users.email, users.role_id, users.created_at, users.customer_group_id) as "user!: User",
+            (magic_tokens.id, magic_tokens.token_hash, magic_tokens.otp_hash, magic_tokens.attempts, magic_tokens.user_id, magic_tokens.expires_at, magic_tokens.created_at) as "token!: MagicToken"
           FROM
             magic_tokens
           JOIN users ON users.id = magic_tokens.user_id
@@ -80,29 +62,119 @@ pub async fn find_by_user(
     ).fetch_optional(connection).await
 }
 
+// a user only ever holds the token they asked for last, wrong codes on a live token carry
+// over so asking for a new one doesn't hand out fresh guesses
 pub async fn insert(
     input: &StoreMagicTokenSchema,
     connection: &mut PgConnection,
 ) -> Result<PopulatedMagicToken, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+
+    let attempts = sqlx::query_scalar!(
+        r#"DELETE FROM magic_tokens WHERE user_id = $1 RETURNING CASE WHEN expires_at > $2 THEN attempts ELSE 0 END AS "attempts!""#,
+        input.user_id,
+        now
+    )
+    .fetch_all(&mut *connection)
+    .await?
+    .into_iter()
+    .max()
+    .unwrap_or(0);
+
     sqlx::query_as!(
         PopulatedMagicToken,
         r#"
         WITH new_token AS (
-          INSERT INTO magic_tokens(token, user_id,expires_at) VALUES ($1, $2, $3) RETURNING *
+          INSERT INTO magic_tokens(token_hash, otp_hash, user_id, expires_at, attempts) VALUES ($1, $2, $3, $4, $5) RETURNING *
         )
         SELECT 
-          (users.id, users.name, users.email, users.role_id, users.created_at) as "user!: User",
-          (new_token.id, new_token.token, new_token.user_id, new_token.expires_at) as "token!: MagicToken"
+          (users.id, users.name, users.email, users.role_id, users.created_at, users.customer_group_id) as "user!: User",
+          (new_token.id, new_token.token_hash, new_token.otp_hash, new_token.attempts, new_token.user_id, new_token.expires_at, new_token.created_at) as "token!: MagicToken"
         FROM
           new_token
         JOIN users ON users.id = new_token.user_id
     "#,
-    input.token,
+    input.token_hash,
+    input.otp_hash,
     input.user_id,
-    input.expires_at
+    input.expires_at,
+    attempts
     ).fetch_one(connection).await
 }
 
+// deleting is the check, so two clicks on the same link can't both get a row back
+pub async fn consume(
+    token_hash: &str,
+    now: &NaiveDateTime,
+    connection: &mut PgConnection,
+) -> Result<Option<PopulatedMagicToken>, sqlx::Error> {
+    sqlx::query_as!(
+        PopulatedMagicToken,
+        r#"
+        WITH consumed AS (
+          DELETE FROM magic_tokens WHERE token_hash = $1 AND expires_at > $2 RETURNING *
+        )
+        SELECT 
+          (users.id, users.name, users.email, users.role_id, users.created_at, users.customer_group_id) as "user!: User",
+          (consumed.id, consumed.token_hash, consumed.otp_hash, consumed.attempts, consumed.user_id, consumed.expires_at, consumed.created_at) as "token!: MagicToken"
+        FROM
+          consumed
+        JOIN users ON users.id = consumed.user_id
+    "#,
+    token_hash,
+    now
+    ).fetch_optional(connection).await
+}
+
+// the attempt is counted before the code is compared, the row lock makes concurrent guesses
+// wait for each other so no more than max_attempts of them ever get compared
+pub async fn attempt_otp(
+    email: &str,
+    now: &NaiveDateTime,
+    max_attempts: i32,
+    connection: &mut PgConnection,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        UPDATE magic_tokens SET attempts = attempts + 1
+        FROM users
+        WHERE users.id = magic_tokens.user_id
+          AND users.email = $1
+          AND magic_tokens.expires_at > $2
+          AND magic_tokens.attempts < $3
+        RETURNING magic_tokens.id
+        "#,
+        email,
+        now,
+        max_attempts
+    )
+    .fetch_optional(connection)
+    .await
+}
+
+pub async fn consume_otp(
+    id: &Uuid,
+    otp_hashes: &[String],
+    connection: &mut PgConnection,
+) -> Result<Option<PopulatedMagicToken>, sqlx::Error> {
+    sqlx::query_as!(
+        PopulatedMagicToken,
+        r#"
+        WITH consumed AS (
+          DELETE FROM magic_tokens WHERE id = $1 AND otp_hash = ANY($2) RETURNING *
+        )
+        SELECT 
+          (users.id, users.name, users.email, users.role_id, users.created_at, users.customer_group_id) as "user!: User",
+          (consumed.id, consumed.token_hash, consumed.otp_hash, consumed.attempts, consumed.user_id, consumed.expires_at, consumed.created_at) as "token!: MagicToken"
+        FROM
+          consumed
+        JOIN users ON users.id = consumed.user_id
+    "#,
+    id,
+    otp_hashes
+    ).fetch_optional(connection).await
+}
+
 pub async fn destroy(
     id: &Uuid,
     connection: &mut PgConnection,
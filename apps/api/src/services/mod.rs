@@ -1,11 +1,29 @@
+pub mod api_key;
+pub mod bundle;
 pub mod cart;
+pub mod catalog;
 pub mod category;
+pub mod credential;
+pub mod currency;
+pub mod customer_group;
+pub mod download;
+pub mod identity;
 pub mod image;
 pub mod magic_tokens;
+pub mod order;
+pub mod price_list;
 pub mod product;
+pub mod product_link;
+pub mod rate_limit;
+pub mod review;
 pub mod role;
+pub mod sale;
 pub mod sessions;
 pub mod settings;
+pub mod stock;
+pub mod translation;
+pub mod two_factor;
 pub mod unit;
 pub mod user;
 pub mod warehouse;
+pub mod wishlist;
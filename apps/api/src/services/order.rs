@@ -0,0 +1,122 @@
+use chrono::NaiveDateTime;
+use sqlx::{Acquire, PgConnection};
+use uuid::Uuid;
+
+use crate::models::{cart::CartLine, currency::Conversion, order::Order};
+
+pub async fn all(db: &mut PgConnection) -> Result<Vec<Order>, sqlx::Error> {
+    sqlx::query_as!(Order, "SELECT * FROM orders ORDER BY created_at DESC")
+        .fetch_all(&mut *db)
+        .await
+}
+
+// placing again replaces the cart's unpaid order, the customer may have switched currency,
+// the lines are already converted
+pub async fn place(
+    cart_id: &Uuid,
+    user_id: Option<Uuid>,
+    base_currency: &str,
+    base_total: f32,
+    conversion: &Conversion,
+    lines: &[CartLine],
+    connection: &mut PgConnection,
+) -> Result<Order, sqlx::Error> {
+    let total = lines.iter().map(|line| line.total).sum::<f32>();
+
+    let mut tx = connection.begin().await?;
+
+    sqlx::query!(
+        "DELETE FROM orders WHERE cart_id = $1 AND paid_at IS NULL",
+        cart_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let order = sqlx::query_as!(
+        Order,
+        r#"
+          INSERT INTO orders(cart_id, user_id, currency, total, base_currency, base_total, rate)
+          VALUES ($1, $2, $3, $4, $5, $6, $7)
+          RETURNING *
+        "#,
+        cart_id,
+        user_id,
+        conversion.currency,
+        total,
+        base_currency,
+        base_total,
+        conversion.rate
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let mut variant_ids = vec![];
+    let mut bundle_ids = vec![];
+    let mut quantities = vec![];
+    let mut unit_prices = vec![];
+
+    for line in lines {
+        variant_ids.push(line.item.variant_id);
+        bundle_ids.push(line.item.bundle_id);
+        quantities.push(line.item.quantity.unwrap_or(0));
+        unit_prices.push(line.unit_price);
+    }
+
+    sqlx::query!(
+        r#"
+          INSERT INTO order_lines(order_id, variant_id, bundle_id, quantity, unit_price)
+          SELECT $1, * FROM UNNEST($2::UUID[], $3::UUID[], $4::INTEGER[], $5::REAL[])
+        "#,
+        order.id,
+        &variant_ids as &[Option<Uuid>],
+        &bundle_ids as &[Option<Uuid>],
+        &quantities,
+        &unit_prices
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(order)
+}
+
+pub async fn pay(
+    cart_id: &Uuid,
+    now: &NaiveDateTime,
+    db: &mut PgConnection,
+) -> Result<Option<Order>, sqlx::Error> {
+    sqlx::query_as!(
+        Order,
+        "UPDATE orders SET paid_at = $2 WHERE cart_id = $1 AND paid_at IS NULL RETURNING *",
+        cart_id,
+        now
+    )
+    .fetch_optional(&mut *db)
+    .await
+}
+
+// whether the user paid for one of the product's variants, on its own or inside a bundle
+pub async fn purchased(
+    user_id: &Uuid,
+    product_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+          SELECT EXISTS(
+            SELECT 1 FROM orders
+            JOIN order_lines ON order_lines.order_id = orders.id
+            LEFT JOIN bundle_items ON bundle_items.bundle_id = order_lines.bundle_id
+            JOIN product_variants
+              ON product_variants.id = COALESCE(order_lines.variant_id, bundle_items.variant_id)
+            WHERE orders.user_id = $1 AND orders.paid_at IS NOT NULL
+              AND product_variants.product_id = $2
+          ) AS "purchased!"
+        "#,
+        user_id,
+        product_id
+    )
+    .fetch_one(&mut *db)
+    .await
+}
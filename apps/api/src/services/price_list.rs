@@ -0,0 +1,233 @@
+use sqlx::{postgres::PgQueryResult, PgConnection};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        price_list::{BasePrice, PopulatedPriceList, PriceList, PriceRule, Pricing},
+        product::SProduct,
+    },
+    services,
+    validations::price_list::StorePriceListSchema,
+};
+
+async fn populate(
+    price_lists: Vec<PriceList>,
+    db: &mut PgConnection,
+) -> Result<Vec<PopulatedPriceList>, sqlx::Error> {
+    let rules = sqlx::query_as!(
+        PriceRule,
+        "SELECT * FROM price_rules WHERE price_list_id = ANY($1) ORDER BY min_quantity",
+        &price_lists
+            .iter()
+            .map(|price_list| price_list.id)
+            .collect::<Vec<_>>()
+    )
+    .fetch_all(&mut *db)
+    .await?;
+
+    Ok(price_lists
+        .into_iter()
+        .map(|price_list| PopulatedPriceList {
+            rules: rules
+                .iter()
+                .filter(|rule| rule.price_list_id == price_list.id)
+                .cloned()
+                .collect(),
+            price_list,
+        })
+        .collect())
+}
+
+pub async fn all(db: &mut PgConnection) -> Result<Vec<PopulatedPriceList>, sqlx::Error> {
+    let price_lists = sqlx::query_as!(PriceList, "SELECT * FROM price_lists ORDER BY created_at")
+        .fetch_all(&mut *db)
+        .await?;
+
+    populate(price_lists, db).await
+}
+
+pub async fn find(
+    id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<Option<PopulatedPriceList>, sqlx::Error> {
+    let price_list = sqlx::query_as!(PriceList, "SELECT * FROM price_lists WHERE id = $1", id)
+        .fetch_optional(&mut *db)
+        .await?;
+
+    match price_list {
+        Some(price_list) => Ok(populate(vec![price_list], db).await?.pop()),
+        None => Ok(None),
+    }
+}
+
+async fn attach_rules(
+    price_list_id: &Uuid,
+    input: &StorePriceListSchema,
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+          INSERT INTO price_rules(price_list_id, kind, value, min_quantity, variant_id, category_id)
+          SELECT $1, * FROM UNNEST($2::TEXT[], $3::REAL[], $4::INTEGER[], $5::UUID[], $6::UUID[])
+        "#,
+        price_list_id,
+        &input
+            .rules
+            .iter()
+            .map(|rule| rule.kind.clone())
+            .collect::<Vec<_>>(),
+        &input
+            .rules
+            .iter()
+            .map(|rule| rule.value)
+            .collect::<Vec<_>>(),
+        &input
+            .rules
+            .iter()
+            .map(|rule| rule.min_quantity)
+            .collect::<Vec<_>>(),
+        &input
+            .rules
+            .iter()
+            .map(|rule| rule.variant_id)
+            .collect::<Vec<_>>() as &[Option<Uuid>],
+        &input
+            .rules
+            .iter()
+            .map(|rule| rule.category_id)
+            .collect::<Vec<_>>() as &[Option<Uuid>]
+    )
+    .execute(&mut *db)
+    .await
+}
+
+pub async fn insert(
+    input: &StorePriceListSchema,
+    db: &mut PgConnection,
+) -> Result<PriceList, sqlx::Error> {
+    let price_list = sqlx::query_as!(
+        PriceList,
+        "INSERT INTO price_lists(name, customer_group_id) VALUES ($1, $2) RETURNING *",
+        input.name,
+        input.customer_group_id
+    )
+    .fetch_one(&mut *db)
+    .await?;
+
+    attach_rules(&price_list.id, input, db).await?;
+
+    Ok(price_list)
+}
+
+pub async fn update(
+    id: &Uuid,
+    input: &StorePriceListSchema,
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        "UPDATE price_lists SET name = $2, customer_group_id = $3 WHERE id = $1",
+        id,
+        input.name,
+        input.customer_group_id
+    )
+    .execute(&mut *db)
+    .await?;
+
+    sqlx::query!("DELETE FROM price_rules WHERE price_list_id = $1", id)
+        .execute(&mut *db)
+        .await?;
+
+    attach_rules(id, input, db).await
+}
+
+pub async fn destroy(id: &Uuid, db: &mut PgConnection) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!("DELETE FROM price_lists WHERE id = $1", id)
+        .execute(&mut *db)
+        .await
+}
+
+// base prices and every rule a customer of the group could get on the variants
+pub async fn pricing(
+    variant_ids: &Vec<Uuid>,
+    customer_group_id: Option<Uuid>,
+    db: &mut PgConnection,
+) -> Result<Pricing, sqlx::Error> {
+    let bases = sqlx::query_as!(
+        BasePrice,
+        r#"
+          SELECT
+            product_variants.id AS variant_id,
+            products.category_id,
+            product_variants.price
+          FROM product_variants
+          JOIN products ON products.id = product_variants.product_id
+          WHERE product_variants.id = ANY($1)
+        "#,
+        variant_ids
+    )
+    .fetch_all(&mut *db)
+    .await?;
+
+    let category_ids = bases
+        .iter()
+        .map(|base| base.category_id)
+        .collect::<Vec<_>>();
+
+    let rules = sqlx::query_as!(
+        PriceRule,
+        r#"
+          SELECT price_rules.*
+          FROM price_rules
+          JOIN price_lists ON price_lists.id = price_rules.price_list_id
+          WHERE (price_lists.customer_group_id IS NULL OR price_lists.customer_group_id = $1)
+            AND (
+              price_rules.variant_id = ANY($2)
+              OR price_rules.category_id = ANY($3)
+            )
+        "#,
+        customer_group_id,
+        variant_ids,
+        &category_ids
+    )
+    .fetch_all(&mut *db)
+    .await?;
+
+    let sales = services::sale::active(variant_ids, &category_ids, db).await?;
+
+    Ok(Pricing {
+        bases,
+        rules,
+        sales,
+    })
+}
+
+pub async fn apply(
+    products: &mut [SProduct],
+    customer_group_id: Option<Uuid>,
+    db: &mut PgConnection,
+) -> Result<(), sqlx::Error> {
+    let variant_ids = products
+        .iter()
+        .flat_map(|product| product.variants.iter().map(|variant| variant.variant.id))
+        .collect::<Vec<_>>();
+
+    let pricing = pricing(&variant_ids, customer_group_id, db).await?;
+
+    for variant in products
+        .iter_mut()
+        .flat_map(|product| product.variants.iter_mut())
+    {
+        if let Some(price) = pricing.price(&variant.variant.id, 1) {
+            variant.price = price;
+        }
+
+        if let Some((sale, sale_price)) = pricing.sale(&variant.variant.id) {
+            variant.sale_price = Some(sale_price);
+            variant.sale_ends_at = Some(sale.ends_at);
+        }
+
+        variant.tiers = pricing.tiers(&variant.variant.id);
+    }
+
+    Ok(())
+}
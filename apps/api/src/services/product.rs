@@ -12,20 +12,78 @@ use crate::{
             ProductVariantCollectionKey, ProductVariantCollectionValue, SCollection, SProduct,
             SVariant,
         },
+        review::Rating,
         unit::Unit,
     },
+    services,
     validations::product::StoreProductSchema,
 };
 
+async fn with_ratings(
+    mut products: Vec<SProduct>,
+    db: &mut PgConnection,
+) -> Result<Vec<SProduct>, sqlx::Error> {
+    let ratings = services::review::ratings(
+        &products
+            .iter()
+            .map(|product| product.product.id)
+            .collect::<Vec<_>>(),
+        db,
+    )
+    .await?;
+
+    for product in products.iter_mut() {
+        if let Some(rating) = ratings
+            .iter()
+            .find(|rating| rating.product_id == product.product.id)
+        {
+            product.rating = Rating {
+                average: rating.average,
+                count: rating.count,
+            };
+        }
+    }
+
+    Ok(products)
+}
+
+async fn with_availability(
+    mut products: Vec<SProduct>,
+    db: &mut PgConnection,
+) -> Result<Vec<SProduct>, sqlx::Error> {
+    let stocks = services::stock::available(
+        &products
+            .iter()
+            .flat_map(|product| product.variants.iter().map(|variant| variant.variant.id))
+            .collect::<Vec<_>>(),
+        db,
+    )
+    .await?;
+
+    for variant in products
+        .iter_mut()
+        .flat_map(|product| product.variants.iter_mut())
+    {
+        if let Some(stock) = stocks
+            .iter()
+            .find(|stock| stock.variant_id == variant.variant.id)
+        {
+            variant.available = stock.available;
+        }
+    }
+
+    Ok(products)
+}
+
 pub async fn all(db: &mut PgConnection) -> Result<Vec<SProduct>, sqlx::Error> {
     let raw_products = sqlx::query_as!(
         PopulatedProduct,
         r#"
             SELECT 
-                (products.id, products.name, products.description, products.category_id, products.unit_id, products.created_at) AS "product!: Product",
+                (products.id, products.name, products.description, products.unit_id, products.category_id, products.created_at, products.slug) AS "product!: Product",
                 (units.id, units.name, units.created_at) AS "unit!: Unit",
                 (categories.id, categories.name, categories.created_at) AS "category!: Category",
-                (product_variants.id, product_variants.price, product_variants.product_id) AS "variant!: ProductVariant",
+                (product_variants.id, product_variants.price, product_variants.product_id, product_variants.sku) AS "variant!: ProductVariant",
                 (product_variant_collections.id, product_variant_collections.variant_id, product_variant_collections.key_id, product_variant_collections.value_id) AS "collection!: ProductVariantCollection",
                 (product_variant_collection_keys.id, product_variant_collection_keys.name, product_variant_collection_keys.product_id, product_variant_collections.value_id) AS "key!: ProductVariantCollectionKey",
                 (product_variant_collection_values.id, product_variant_collection_values.name, product_variant_collection_values.key_id) AS "value!: ProductVariantCollectionValue",
@@ -88,6 +146,13 @@ pub async fn all(db: &mut PgConnection) -> Result<Vec<SProduct>, sqlx::Error> {
                     SVariant {
                         variant: product.variant.clone(),
                         collections,
+                        price: product.variant.price,
+                        regular_price: product.variant.price,
+                        sale_price: None,
+                        sale_ends_at: None,
+                        tiers: Vec::new(),
+                        currency: String::new(),
+                        available: 0,
                     }
                 })
                 .collect();
@@ -98,11 +163,14 @@ pub async fn all(db: &mut PgConnection) -> Result<Vec<SProduct>, sqlx::Error> {
                 unit: product.unit.clone(),
                 images: product.images.clone(),
                 variants,
+                rating: Rating::default(),
             }
         })
         .collect();
 
-    return Ok(results);
+    let results = with_ratings(results, db).await?;
+
+    with_availability(results, db).await
 }
 
 pub async fn find(id: &Uuid, db: &mut PgConnection) -> Result<Option<SProduct>, sqlx::Error> {
@@ -110,10 +178,10 @@ pub async fn find(id: &Uuid, db: &mut PgConnection) -> Result<Option<SProduct>,
         PopulatedProduct,
         r#"
           SELECT 
-            (products.id, products.name, products.description, products.category_id, products.unit_id, products.created_at) AS "product!: Product",
+            (products.id, products.name, products.description, products.unit_id, products.category_id, products.created_at, products.slug) AS "product!: Product",
             (units.id, units.name, units.created_at) AS "unit!: Unit",
             (categories.id, categories.name, categories.created_at) AS "category!: Category",
-            (product_variants.id, product_variants.price, product_variants.product_id) AS "variant!: ProductVariant",
+            (product_variants.id, product_variants.price, product_variants.product_id, product_variants.sku) AS "variant!: ProductVariant",
             (product_variant_collections.id, product_variant_collections.variant_id, product_variant_collections.key_id, product_variant_collections.value_id) AS "collection!: ProductVariantCollection",
             (product_variant_collection_keys.id, product_variant_collection_keys.name, product_variant_collection_keys.product_id, product_variant_collections.value_id) AS "key!: ProductVariantCollectionKey",
             (product_variant_collection_values.id, product_variant_collection_values.name, product_variant_collection_values.key_id) AS "value!: ProductVariantCollectionValue",
@@ -178,6 +246,13 @@ pub async fn find(id: &Uuid, db: &mut PgConnection) -> Result<Option<SProduct>,
                     SVariant {
                         variant: product.variant.clone(),
                         collections,
+                        price: product.variant.price,
+                        regular_price: product.variant.price,
+                        sale_price: None,
+                        sale_ends_at: None,
+                        tiers: Vec::new(),
+                        currency: String::new(),
+                        available: 0,
                     }
                 })
                 .collect();
@@ -188,16 +263,97 @@ pub async fn find(id: &Uuid, db: &mut PgConnection) -> Result<Option<SProduct>,
                 unit: product.unit.clone(),
                 images: product.images.clone(),
                 variants,
+                rating: Rating::default(),
             }
         })
         .collect();
 
+    let results = with_ratings(results, db).await?;
+    let results = with_availability(results, db).await?;
+
     match results.get(0) {
         Some(product) => Ok(Some(product.clone())),
         None => Ok(None),
     }
 }
 
+pub async fn exists(id: &Uuid, db: &mut PgConnection) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM products WHERE id = $1) AS "exists!""#,
+        id
+    )
+    .fetch_one(&mut *db)
+    .await
+}
+
+pub async fn count_existing(ids: &Vec<Uuid>, db: &mut PgConnection) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM products WHERE id = ANY($1)"#,
+        ids
+    )
+    .fetch_one(&mut *db)
+    .await
+}
+
+pub async fn count_existing_variants(
+    ids: &Vec<Uuid>,
+    db: &mut PgConnection,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM product_variants WHERE id = ANY($1)"#,
+        ids
+    )
+    .fetch_one(&mut *db)
+    .await
+}
+
+pub async fn find_by_slug(
+    slug: &str,
+    db: &mut PgConnection,
+) -> Result<Option<Product>, sqlx::Error> {
+    sqlx::query_as!(Product, "SELECT * FROM products WHERE slug = $1", slug)
+        .fetch_optional(&mut *db)
+        .await
+}
+
+pub async fn find_variant(
+    id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<Option<ProductVariant>, sqlx::Error> {
+    sqlx::query_as!(
+        ProductVariant,
+        "SELECT * FROM product_variants WHERE id = $1",
+        id
+    )
+    .fetch_optional(&mut *db)
+    .await
+}
+
+pub async fn product_ids_of_variants(
+    variant_ids: &Vec<Uuid>,
+    db: &mut PgConnection,
+) -> Result<Vec<Uuid>, sqlx::Error> {
+    sqlx::query_scalar!(
+        "SELECT DISTINCT product_id FROM product_variants WHERE id = ANY($1)",
+        variant_ids
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
+pub async fn find_variants_by_skus(
+    skus: &Vec<String>,
+    db: &mut PgConnection,
+) -> Result<Vec<ProductVariant>, sqlx::Error> {
+    sqlx::query_as!(
+        ProductVariant,
+        "SELECT * FROM product_variants WHERE sku = ANY($1)",
+        skus
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
 pub async fn insert(
     input: &StoreProductSchema,
     db: &mut PgConnection,
@@ -210,20 +366,23 @@ pub async fn insert(
             name,
             description,
             category_id,
-            unit_id
+            unit_id,
+            slug
           ) 
           VALUES (
             $1,
             $2,
             $3,
-            $4
+            $4,
+            $5
           )
           RETURNING products.*
         "#,
         input.name,
         input.description,
         input.category_id,
-        input.unit_id
+        input.unit_id,
+        input.slug
     )
     .fetch_one(&mut *db)
     .await
@@ -236,13 +395,14 @@ pub async fn update(
 ) -> Result<PgQueryResult, sqlx::Error> {
     sqlx::query!(
         r#"
-          UPDATE products SET name = $2, description = $3, category_id = $4,  unit_id = $5 WHERE id = $1
+          UPDATE products SET name = $2, description = $3, category_id = $4,  unit_id = $5, slug = COALESCE($6, slug) WHERE id = $1
         "#,
         id,
         input.name,
         input.description,
         input.category_id,
         input.unit_id,
+        input.slug,
     )
     .execute(&mut *db)
     .await
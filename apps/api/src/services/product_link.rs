@@ -0,0 +1,125 @@
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    models::{product::Product, product_link::ProductLinks},
+    utils::constants::{LINK_KINDS, RELATED_PRODUCTS_LIMIT},
+    validations::product_link::StoreProductLinksSchema,
+};
+
+// products linked from any of the given ones, the given products themselves are left out
+pub async fn linked(
+    product_ids: &Vec<Uuid>,
+    kind: &str,
+    db: &mut PgConnection,
+) -> Result<Vec<Product>, sqlx::Error> {
+    sqlx::query_as!(
+        Product,
+        r#"
+          SELECT products.*
+          FROM products
+          JOIN (
+            SELECT linked_product_id, MIN(position) AS position
+            FROM product_links
+            WHERE product_id = ANY($1) AND kind = $2
+            GROUP BY linked_product_id
+          ) AS links ON links.linked_product_id = products.id
+          WHERE NOT products.id = ANY($1)
+          ORDER BY links.position, products.name
+        "#,
+        product_ids,
+        kind
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
+pub async fn same_category(
+    product_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<Vec<Product>, sqlx::Error> {
+    sqlx::query_as!(
+        Product,
+        r#"
+          SELECT products.*
+          FROM products
+          WHERE products.id <> $1
+            AND products.category_id = (SELECT category_id FROM products WHERE id = $1)
+          ORDER BY products.created_at DESC
+          LIMIT $2
+        "#,
+        product_id,
+        RELATED_PRODUCTS_LIMIT
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
+pub async fn links(product_id: &Uuid, db: &mut PgConnection) -> Result<ProductLinks, sqlx::Error> {
+    let product_ids = vec![*product_id];
+
+    let mut related = linked(&product_ids, LINK_KINDS.related, db).await?;
+
+    // fall back to the rest of the category when nothing is curated
+    if related.is_empty() {
+        related = same_category(product_id, db).await?;
+    }
+
+    Ok(ProductLinks {
+        related,
+        cross_sell: linked(&product_ids, LINK_KINDS.cross_sell, db).await?,
+        up_sell: linked(&product_ids, LINK_KINDS.up_sell, db).await?,
+    })
+}
+
+pub async fn replace(
+    product_id: &Uuid,
+    input: &StoreProductLinksSchema,
+    db: &mut PgConnection,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM product_links WHERE product_id = $1",
+        product_id
+    )
+    .execute(&mut *db)
+    .await?;
+
+    let mut kinds: Vec<String> = Vec::new();
+    let mut positions: Vec<i32> = Vec::new();
+    let mut linked_ids: Vec<Uuid> = Vec::new();
+
+    for (kind, ids) in [
+        (LINK_KINDS.related, &input.related),
+        (LINK_KINDS.cross_sell, &input.cross_sell),
+        (LINK_KINDS.up_sell, &input.up_sell),
+    ] {
+        for (position, id) in ids.iter().enumerate() {
+            if linked_ids
+                .iter()
+                .zip(kinds.iter())
+                .any(|(linked_id, linked_kind)| linked_id == id && linked_kind == kind)
+            {
+                continue;
+            }
+
+            kinds.push(kind.to_string());
+            positions.push(position as i32);
+            linked_ids.push(*id);
+        }
+    }
+
+    sqlx::query!(
+        r#"
+          INSERT INTO product_links(product_id, kind, position, linked_product_id)
+          SELECT $1, * FROM UNNEST($2::TEXT[], $3::INTEGER[], $4::UUID[])
+        "#,
+        product_id,
+        &kinds,
+        &positions,
+        &linked_ids
+    )
+    .execute(&mut *db)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use chrono::NaiveDateTime;
+use sqlx::{Acquire, PgConnection};
+
+use crate::models::rate_limit::RateLimitBucket;
+
+// the row is locked while the bucket is refilled, so instances sharing the table can't both spend the last token
+pub async fn take(
+    key: &str,
+    capacity: u32,
+    period: i64,
+    now: &NaiveDateTime,
+    connection: &mut PgConnection,
+) -> Result<Option<i64>, sqlx::Error> {
+    let mut tx = connection.begin().await?;
+
+    sqlx::query!(
+        r#"INSERT INTO rate_limits(key, tokens, updated_at) VALUES ($1, $2, $3) ON CONFLICT (key) DO NOTHING"#,
+        key,
+        capacity as f64,
+        now
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let mut bucket = sqlx::query_as!(
+        RateLimitBucket,
+        r#"SELECT key, tokens, updated_at FROM rate_limits WHERE key = $1 FOR UPDATE"#,
+        key
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let retry_after = bucket.take(capacity, period, *now);
+
+    sqlx::query!(
+        r#"UPDATE rate_limits SET tokens = $2, updated_at = $3 WHERE key = $1"#,
+        bucket.key,
+        bucket.tokens,
+        bucket.updated_at
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(retry_after)
+}
+
+// buckets untouched since before are full again and can be forgotten
+pub async fn sweep(
+    before: &NaiveDateTime,
+    connection: &mut PgConnection,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(r#"DELETE FROM rate_limits WHERE updated_at < $1"#, before)
+        .execute(connection)
+        .await?;
+
+    Ok(result.rows_affected())
+}
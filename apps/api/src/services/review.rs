@@ -0,0 +1,125 @@
+use sqlx::{postgres::PgQueryResult, PgConnection};
+use uuid::Uuid;
+
+use crate::{
+    models::review::{ProductRating, PublicReview, Review},
+    utils::constants::REVIEW_STATUSES,
+    validations::review::StoreReviewSchema,
+};
+
+pub async fn all(status: Option<&str>, db: &mut PgConnection) -> Result<Vec<Review>, sqlx::Error> {
+    sqlx::query_as!(
+        Review,
+        "SELECT * FROM reviews WHERE $1::TEXT IS NULL OR status = $1 ORDER BY created_at DESC",
+        status
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
+pub async fn find(id: &Uuid, db: &mut PgConnection) -> Result<Option<Review>, sqlx::Error> {
+    sqlx::query_as!(Review, "SELECT * FROM reviews WHERE id = $1", id)
+        .fetch_optional(&mut *db)
+        .await
+}
+
+pub async fn find_by_author(
+    product_id: &Uuid,
+    user_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<Option<Review>, sqlx::Error> {
+    sqlx::query_as!(
+        Review,
+        "SELECT * FROM reviews WHERE product_id = $1 AND user_id = $2",
+        product_id,
+        user_id
+    )
+    .fetch_optional(&mut *db)
+    .await
+}
+
+pub async fn approved(
+    product_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<Vec<PublicReview>, sqlx::Error> {
+    sqlx::query_as!(
+        PublicReview,
+        r#"
+          SELECT
+            (reviews.id, reviews.rating, reviews.title, reviews.body, reviews.verified, reviews.status, reviews.product_id, reviews.user_id, reviews.created_at) AS "review!: Review",
+            users.name AS author
+          FROM reviews
+          JOIN users ON users.id = reviews.user_id
+          WHERE reviews.product_id = $1 AND reviews.status = $2
+          ORDER BY reviews.created_at DESC
+        "#,
+        product_id,
+        REVIEW_STATUSES.approved
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
+// only approved reviews count towards a product rating
+pub async fn ratings(
+    product_ids: &Vec<Uuid>,
+    db: &mut PgConnection,
+) -> Result<Vec<ProductRating>, sqlx::Error> {
+    sqlx::query_as!(
+        ProductRating,
+        r#"
+          SELECT
+            product_id,
+            AVG(rating)::FLOAT8 AS average,
+            COUNT(*) AS "count!"
+          FROM reviews
+          WHERE product_id = ANY($1) AND status = $2
+          GROUP BY product_id
+        "#,
+        product_ids,
+        REVIEW_STATUSES.approved
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
+pub async fn insert(
+    product_id: &Uuid,
+    user_id: &Uuid,
+    verified: bool,
+    input: &StoreReviewSchema,
+    db: &mut PgConnection,
+) -> Result<Review, sqlx::Error> {
+    sqlx::query_as!(
+        Review,
+        r#"
+          INSERT INTO reviews(rating, title, body, verified, product_id, user_id)
+          VALUES ($1, $2, $3, $4, $5, $6)
+          RETURNING *
+        "#,
+        input.rating,
+        input.title,
+        input.body,
+        verified,
+        product_id,
+        user_id
+    )
+    .fetch_one(&mut *db)
+    .await
+}
+
+pub async fn update_status(
+    id: &Uuid,
+    status: &str,
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!("UPDATE reviews SET status = $2 WHERE id = $1", id, status)
+        .execute(&mut *db)
+        .await
+}
+
+pub async fn destroy(id: &Uuid, db: &mut PgConnection) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!("DELETE FROM reviews WHERE id = $1", id)
+        .execute(&mut *db)
+        .await
+}
@@ -1,4 +1,7 @@
-use crate::{models::role::Role, validations::role::StoreRoleSchema};
+use crate::{
+    models::role::{PopulatedRole, Role},
+    validations::role::StoreRoleSchema,
+};
 use sqlx::{postgres::PgQueryResult, PgConnection};
 use uuid::Uuid;
 
@@ -20,10 +23,7 @@ pub async fn find_by_name(name: &str, db: &mut PgConnection) -> Result<Option<Ro
         .await
 }
 
-pub async fn insert<'a>(
-    input: &StoreRoleSchema<'a>,
-    db: &mut PgConnection,
-) -> Result<Role, sqlx::Error> {
+pub async fn insert(input: &StoreRoleSchema, db: &mut PgConnection) -> Result<Role, sqlx::Error> {
     sqlx::query_as!(
         Role,
         "INSERT INTO roles(name) VALUES ($1) RETURNING *",
@@ -33,8 +33,8 @@ pub async fn insert<'a>(
     .await
 }
 
-pub async fn insert_many<'a>(
-    inputs: &Vec<StoreRoleSchema<'a>>,
+pub async fn insert_many(
+    inputs: &Vec<StoreRoleSchema>,
     db: &mut PgConnection,
 ) -> Result<Vec<Role>, sqlx::Error> {
     sqlx::query_as!(
@@ -49,9 +49,9 @@ pub async fn insert_many<'a>(
     .await
 }
 
-pub async fn update<'a>(
+pub async fn update(
     id: &Uuid,
-    input: &StoreRoleSchema<'a>,
+    input: &StoreRoleSchema,
     db: &mut PgConnection,
 ) -> Result<PgQueryResult, sqlx::Error> {
     sqlx::query!("UPDATE roles SET name = $1 WHERE id = $2", input.name, id)
@@ -64,3 +64,87 @@ pub async fn destroy(id: &Uuid, db: &mut PgConnection) -> Result<PgQueryResult,
         .execute(&mut *db)
         .await
 }
+
+pub async fn all_populated(db: &mut PgConnection) -> Result<Vec<PopulatedRole>, sqlx::Error> {
+    sqlx::query_as!(
+        PopulatedRole,
+        r#"
+            SELECT
+                (roles.id, roles.name, roles.created_at) AS "role!: Role",
+                ARRAY(SELECT permission FROM role_permissions WHERE role_id = roles.id ORDER BY permission) AS "permissions!"
+            FROM roles
+            ORDER BY roles.created_at
+        "#
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
+pub async fn find_populated(
+    id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<Option<PopulatedRole>, sqlx::Error> {
+    sqlx::query_as!(
+        PopulatedRole,
+        r#"
+            SELECT
+                (roles.id, roles.name, roles.created_at) AS "role!: Role",
+                ARRAY(SELECT permission FROM role_permissions WHERE role_id = roles.id ORDER BY permission) AS "permissions!"
+            FROM roles
+            WHERE roles.id = $1
+        "#,
+        id
+    )
+    .fetch_optional(&mut *db)
+    .await
+}
+
+pub async fn insert_named(name: &str, db: &mut PgConnection) -> Result<Role, sqlx::Error> {
+    sqlx::query_as!(
+        Role,
+        "INSERT INTO roles(name) VALUES ($1) RETURNING *",
+        name
+    )
+    .fetch_one(&mut *db)
+    .await
+}
+
+pub async fn rename(
+    id: &Uuid,
+    name: &str,
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!("UPDATE roles SET name = $1 WHERE id = $2", name, id)
+        .execute(&mut *db)
+        .await
+}
+
+// replaces the whole set, run inside a transaction
+pub async fn set_permissions(
+    id: &Uuid,
+    permissions: &[String],
+    db: &mut PgConnection,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!("DELETE FROM role_permissions WHERE role_id = $1", id)
+        .execute(&mut *db)
+        .await?;
+
+    sqlx::query!(
+        "INSERT INTO role_permissions(role_id, permission) SELECT $1, * FROM UNNEST($2::TEXT[]) ON CONFLICT DO NOTHING",
+        id,
+        permissions
+    )
+    .execute(&mut *db)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn count_users(id: &Uuid, db: &mut PgConnection) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM users WHERE role_id = $1"#,
+        id
+    )
+    .fetch_one(&mut *db)
+    .await
+}
@@ -0,0 +1,88 @@
+use chrono::Utc;
+use sqlx::{postgres::PgQueryResult, PgConnection};
+use uuid::Uuid;
+
+use crate::{models::sale::Sale, validations::sale::StoreSaleSchema};
+
+pub async fn all(db: &mut PgConnection) -> Result<Vec<Sale>, sqlx::Error> {
+    sqlx::query_as!(Sale, "SELECT * FROM sales ORDER BY starts_at DESC")
+        .fetch_all(&mut *db)
+        .await
+}
+
+pub async fn find(id: &Uuid, db: &mut PgConnection) -> Result<Option<Sale>, sqlx::Error> {
+    sqlx::query_as!(Sale, "SELECT * FROM sales WHERE id = $1", id)
+        .fetch_optional(&mut *db)
+        .await
+}
+
+// sales running right now on the variants or their categories
+pub async fn active(
+    variant_ids: &Vec<Uuid>,
+    category_ids: &Vec<Uuid>,
+    db: &mut PgConnection,
+) -> Result<Vec<Sale>, sqlx::Error> {
+    sqlx::query_as!(
+        Sale,
+        r#"
+          SELECT * FROM sales
+          WHERE starts_at <= $3 AND ends_at > $3
+            AND (variant_id = ANY($1) OR category_id = ANY($2))
+        "#,
+        variant_ids,
+        category_ids,
+        Utc::now().naive_utc()
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
+pub async fn insert(input: &StoreSaleSchema, db: &mut PgConnection) -> Result<Sale, sqlx::Error> {
+    sqlx::query_as!(
+        Sale,
+        r#"
+          INSERT INTO sales(name, kind, value, variant_id, category_id, starts_at, ends_at)
+          VALUES ($1, $2, $3, $4, $5, $6, $7)
+          RETURNING *
+        "#,
+        input.name,
+        input.kind,
+        input.value,
+        input.variant_id,
+        input.category_id,
+        input.starts_at,
+        input.ends_at
+    )
+    .fetch_one(&mut *db)
+    .await
+}
+
+pub async fn update(
+    id: &Uuid,
+    input: &StoreSaleSchema,
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+          UPDATE sales
+          SET name = $2, kind = $3, value = $4, variant_id = $5, category_id = $6, starts_at = $7, ends_at = $8
+          WHERE id = $1
+        "#,
+        id,
+        input.name,
+        input.kind,
+        input.value,
+        input.variant_id,
+        input.category_id,
+        input.starts_at,
+        input.ends_at
+    )
+    .execute(&mut *db)
+    .await
+}
+
+pub async fn destroy(id: &Uuid, db: &mut PgConnection) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!("DELETE FROM sales WHERE id = $1", id)
+        .execute(&mut *db)
+        .await
+}
@@ -1,9 +1,11 @@
+use chrono::{Duration, NaiveDateTime, Utc};
 use sqlx::{postgres::PgQueryResult, PgConnection};
 use uuid::Uuid;
 
 use crate::{
-    models::session::{PopulatedSession, Session},
+    models::session::{Device, PopulatedSession, Session},
     models::{role::Role, user::User},
+    utils::constants::{SESSION_MAX_AGE, SESSION_RENEW_INTERVAL},
     validations::auth::StoreSessionSchema,
 };
 
@@ -12,9 +14,10 @@ pub async fn all(db: &mut PgConnection) -> Result<Vec<PopulatedSession>, sqlx::E
         PopulatedSession,
         r#"
             SELECT 
-                (sessions.id, sessions.session, sessions.user_id, sessions.created_at) as "session!: Session" ,
-                (users.id, users.name, users.email, users.role_id, users.created_at) as "user!: User",
-                (roles.id, roles.name, roles.created_at) as "role!: Role"
+                (sessions.id, sessions.session, sessions.user_id, sessions.created_at, sessions.expires_at, sessions.last_seen_at, sessions.user_agent, sessions.ip, sessions.second_factor_at) as "session!: Session" ,
+                (users.id, users.name, users.email, users.role_id, users.created_at, users.customer_group_id) as "user!: User",
+                (roles.id, roles.name, roles.created_at) as "role!: Role",
+                ARRAY(SELECT permission FROM role_permissions WHERE role_id = roles.id ORDER BY permission) as "permissions!"
             FROM sessions
             JOIN users ON users.id = sessions.user_id
             JOIN roles ON users.role_id = roles.id
@@ -30,9 +33,10 @@ pub async fn find(
         PopulatedSession,
         r#"
             SELECT 
-                (sessions.id, sessions.session, sessions.user_id, sessions.created_at) as "session!: Session" ,
-                (users.id, users.name, users.email, users.role_id, users.created_at) as "user!: User",
-                (roles.id, roles.name, roles.created_at) as "role!: Role"
+                (sessions.id, sessions.session, sessions.user_id, sessions.created_at, sessions.expires_at, sessions.last_seen_at, sessions.user_agent, sessions.ip, sessions.second_factor_at) as "session!: Session" ,
+                (users.id, users.name, users.email, users.role_id, users.created_at, users.customer_group_id) as "user!: User",
+                (roles.id, roles.name, roles.created_at) as "role!: Role",
+                ARRAY(SELECT permission FROM role_permissions WHERE role_id = roles.id ORDER BY permission) as "permissions!"
             FROM sessions
             JOIN users ON users.id = sessions.user_id
             JOIN roles ON users.role_id = roles.id
@@ -50,15 +54,17 @@ pub async fn find_by_session(
         PopulatedSession,
         r#"
             SELECT 
-                (sessions.id, sessions.session, sessions.user_id, sessions.created_at) as "session!: Session" ,
-                (users.id, users.name, users.email, users.role_id, users.created_at) as "user!: User",
-                (roles.id, roles.name, roles.created_at) as "role!: Role"
+                (sessions.id, sessions.session, sessions.user_id, sessions.created_at, sessions.expires_at, sessions.last_seen_at, sessions.user_agent, sessions.ip, sessions.second_factor_at) as "session!: Session" ,
+                (users.id, users.name, users.email, users.role_id, users.created_at, users.customer_group_id) as "user!: User",
+                (roles.id, roles.name, roles.created_at) as "role!: Role",
+                ARRAY(SELECT permission FROM role_permissions WHERE role_id = roles.id ORDER BY permission) as "permissions!"
             FROM sessions
             JOIN users ON users.id = sessions.user_id
             JOIN roles ON users.role_id = roles.id
-            WHERE sessions.session = $1
+            WHERE sessions.session = $1 AND sessions.expires_at > $2
         "#,
-        session
+        session,
+        Utc::now().naive_utc()
     ).fetch_optional(db).await
 }
 
@@ -70,9 +76,10 @@ pub async fn find_by_user_id(
         PopulatedSession,
         r#"
             SELECT 
-                (sessions.id, sessions.session, sessions.user_id, sessions.created_at) as "session!: Session" ,
-                (users.id, users.name, users.email, users.role_id, users.created_at) as "user!: User",
-                (roles.id, roles.name, roles.created_at) as "role!: Role"
+                (sessions.id, sessions.session, sessions.user_id, sessions.created_at, sessions.expires_at, sessions.last_seen_at, sessions.user_agent, sessions.ip, sessions.second_factor_at) as "session!: Session" ,
+                (users.id, users.name, users.email, users.role_id, users.created_at, users.customer_group_id) as "user!: User",
+                (roles.id, roles.name, roles.created_at) as "role!: Role",
+                ARRAY(SELECT permission FROM role_permissions WHERE role_id = roles.id ORDER BY permission) as "permissions!"
             FROM sessions
             JOIN users ON users.id = sessions.user_id
             JOIN roles ON users.role_id = roles.id
@@ -88,9 +95,12 @@ pub async fn insert(
 ) -> Result<Session, sqlx::Error> {
     sqlx::query_as!(
         Session,
-        "INSERT INTO sessions(session, user_id) VALUES ($1, $2) RETURNING *",
+        "INSERT INTO sessions(session, user_id, expires_at, user_agent, ip) VALUES ($1, $2, $3, $4, $5) RETURNING *",
         input.session,
-        input.user_id
+        input.user_id,
+        input.expires_at,
+        input.user_agent,
+        input.ip
     )
     .fetch_one(db)
     .await
@@ -110,3 +120,82 @@ pub async fn delete_by_session(
         .execute(db)
         .await
 }
+
+// pushes the expiry back while the session is in use, at most once per renew interval,
+// returns whether the cookie needs to be sent again
+pub async fn renew(session: &Session, db: &mut PgConnection) -> Result<bool, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+
+    if now - session.last_seen_at < Duration::seconds(SESSION_RENEW_INTERVAL) {
+        return Ok(false);
+    }
+
+    sqlx::query!(
+        "UPDATE sessions SET last_seen_at = $2, expires_at = $3 WHERE id = $1",
+        session.id,
+        now,
+        now + Duration::seconds(SESSION_MAX_AGE)
+    )
+    .execute(db)
+    .await?;
+
+    Ok(true)
+}
+
+pub async fn verify_second_factor(
+    id: &Uuid,
+    now: &NaiveDateTime,
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        "UPDATE sessions SET second_factor_at = $2 WHERE id = $1",
+        id,
+        now
+    )
+    .execute(db)
+    .await
+}
+
+pub async fn devices(
+    user_id: &Uuid,
+    current: &Uuid,
+    db: &mut PgConnection,
+) -> Result<Vec<Device>, sqlx::Error> {
+    sqlx::query_as!(
+        Device,
+        r#"
+            SELECT id, user_agent, ip, created_at, last_seen_at, expires_at, id = $2 AS "current!"
+            FROM sessions
+            WHERE user_id = $1 AND expires_at > $3
+            ORDER BY last_seen_at DESC
+        "#,
+        user_id,
+        current,
+        Utc::now().naive_utc()
+    )
+    .fetch_all(db)
+    .await
+}
+
+pub async fn delete_for_user(
+    id: &Uuid,
+    user_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM sessions WHERE id = $1 AND user_id = $2",
+        id,
+        user_id
+    )
+    .execute(db)
+    .await
+}
+
+pub async fn delete_by_user(
+    user_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!("DELETE FROM sessions WHERE user_id = $1", user_id)
+        .execute(db)
+        .await
+}
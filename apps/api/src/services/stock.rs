@@ -0,0 +1,448 @@
+use std::error::Error;
+
+use chrono::{NaiveDateTime, Utc};
+use resend_rs::types::CreateEmailBaseOptions;
+use sqlx::{postgres::PgQueryResult, Acquire, PgConnection};
+use uuid::Uuid;
+
+use crate::{
+    models::stock::{LowStock, Stock, StockLevel, VariantStock},
+    services,
+    utils::{constants::SETTINGS, mailer::Mail},
+    validations::{
+        settings::StoreSettingsSchema,
+        stock::{StoreStockSchema, StoreThresholdSchema},
+    },
+};
+
+pub async fn all(db: &mut PgConnection) -> Result<Vec<StockLevel>, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+
+    sqlx::query_as!(
+        StockLevel,
+        r#"
+          SELECT
+            (stocks.id, stocks.quantity, stocks.variant_id, stocks.warehouse_id, stocks.created_at, stocks.reorder_threshold, stocks.alerted_at) AS "stock!: Stock",
+            COALESCE(SUM(stock_reservations.quantity), 0) AS "reserved!",
+            stocks.quantity - COALESCE(SUM(stock_reservations.quantity), 0) AS "available!"
+          FROM stocks
+          LEFT JOIN stock_reservations ON stock_reservations.stock_id = stocks.id
+            AND (stock_reservations.expires_at IS NULL OR stock_reservations.expires_at > $1)
+          GROUP BY stocks.id
+          ORDER BY stocks.created_at
+        "#,
+        now
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
+// sets the quantity on hand of a variant in a warehouse
+pub async fn set(input: &StoreStockSchema, db: &mut PgConnection) -> Result<Stock, sqlx::Error> {
+    sqlx::query_as!(
+        Stock,
+        r#"
+          INSERT INTO stocks(variant_id, warehouse_id, quantity)
+          VALUES ($1, $2, $3)
+          ON CONFLICT (variant_id, warehouse_id) DO UPDATE SET quantity = EXCLUDED.quantity
+          RETURNING *
+        "#,
+        input.variant_id,
+        input.warehouse_id,
+        input.quantity
+    )
+    .fetch_one(&mut *db)
+    .await
+}
+
+pub async fn set_threshold(
+    input: &StoreThresholdSchema,
+    db: &mut PgConnection,
+) -> Result<Option<Stock>, sqlx::Error> {
+    sqlx::query_as!(
+        Stock,
+        "UPDATE stocks SET reorder_threshold = $3 WHERE variant_id = $1 AND warehouse_id = $2 RETURNING *",
+        input.variant_id,
+        input.warehouse_id,
+        input.reorder_threshold
+    )
+    .fetch_optional(&mut *db)
+    .await
+}
+
+pub async fn low(db: &mut PgConnection) -> Result<Vec<LowStock>, sqlx::Error> {
+    sqlx::query_as!(
+        LowStock,
+        r#"
+          SELECT
+            (stocks.id, stocks.quantity, stocks.variant_id, stocks.warehouse_id, stocks.created_at, stocks.reorder_threshold, stocks.alerted_at) AS "stock!: Stock",
+            product_variants.sku,
+            products.name AS product,
+            warehouses.name AS warehouse
+          FROM stocks
+          JOIN product_variants ON product_variants.id = stocks.variant_id
+          JOIN products ON products.id = product_variants.product_id
+          JOIN warehouses ON warehouses.id = stocks.warehouse_id
+          WHERE stocks.quantity < stocks.reorder_threshold
+          ORDER BY stocks.quantity - stocks.reorder_threshold, products.name
+        "#
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
+pub async fn alert_recipients(db: &mut PgConnection) -> Result<Vec<String>, sqlx::Error> {
+    Ok(
+        services::settings::find_by_key(SETTINGS.stock_alert_recipients, db)
+            .await?
+            .map(|setting| {
+                setting
+                    .value
+                    .split(',')
+                    .filter(|email| !email.is_empty())
+                    .map(|email| email.to_string())
+                    .collect()
+            })
+            .unwrap_or_default(),
+    )
+}
+
+pub async fn set_alert_recipients(
+    emails: &[String],
+    db: &mut PgConnection,
+) -> Result<(), sqlx::Error> {
+    let input = StoreSettingsSchema {
+        key: SETTINGS.stock_alert_recipients.to_string(),
+        value: emails.join(","),
+    };
+
+    match services::settings::find_by_key(SETTINGS.stock_alert_recipients, db).await? {
+        Some(setting) => {
+            services::settings::update(&setting.id, &input, db).await?;
+        }
+        None => {
+            services::settings::insert(&input, db).await?;
+        }
+    };
+
+    Ok(())
+}
+
+// mails staff about stocks that went below their threshold since the last run,
+// a stock is only mentioned again after it went back up
+pub async fn alert_low(
+    mailer: &dyn Mail,
+    domain: &str,
+    db: &mut PgConnection,
+) -> Result<usize, Box<dyn Error>> {
+    let recipients = alert_recipients(db).await?;
+    let now = Utc::now().naive_utc();
+
+    // marking commits before mailing so no lock is held while the mail goes out,
+    // concurrent runs each claim their own crossings
+    let mut tx = db.begin().await?;
+
+    sqlx::query!(
+        r#"
+          UPDATE stocks SET alerted_at = NULL
+          WHERE alerted_at IS NOT NULL
+            AND (reorder_threshold IS NULL OR quantity >= reorder_threshold)
+        "#
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // with nobody to tell crossings stay unmarked, they go out once someone is listed
+    if recipients.is_empty() {
+        tx.commit().await?;
+        return Ok(0);
+    }
+
+    let crossed = sqlx::query_scalar!(
+        r#"
+          UPDATE stocks SET alerted_at = $1
+          WHERE alerted_at IS NULL AND quantity < reorder_threshold
+          RETURNING id
+        "#,
+        now
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    if crossed.is_empty() {
+        return Ok(0);
+    }
+
+    let items = low(db)
+        .await?
+        .into_iter()
+        .filter(|item| crossed.contains(&item.stock.id))
+        .map(|item| {
+            format!(
+                "<li>{} {} at {}: {} left, reorder at {}</li>",
+                item.product,
+                item.sku.unwrap_or_default(),
+                item.warehouse,
+                item.stock.quantity,
+                item.stock.reorder_threshold.unwrap_or_default()
+            )
+        })
+        .collect::<String>();
+
+    let email =
+        CreateEmailBaseOptions::new(format!("{}@{}", "stock", domain), recipients, "Low stock")
+            .with_html(&format!("<ul>{}</ul>", items));
+
+    // the marks are taken back when the mail doesn't go out, the next change tries again
+    if let Err(err) = mailer.mail(email).await {
+        sqlx::query!(
+            "UPDATE stocks SET alerted_at = NULL WHERE id = ANY($1) AND alerted_at = $2",
+            &crossed,
+            now
+        )
+        .execute(&mut *db)
+        .await?;
+
+        return Err(err.into());
+    }
+
+    Ok(crossed.len())
+}
+
+// available to sell per variant over every warehouse
+pub async fn available(
+    variant_ids: &[Uuid],
+    db: &mut PgConnection,
+) -> Result<Vec<VariantStock>, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+
+    sqlx::query_as!(
+        VariantStock,
+        r#"
+          SELECT
+            stocks.variant_id,
+            SUM(stocks.quantity - COALESCE((
+              SELECT SUM(stock_reservations.quantity)
+              FROM stock_reservations
+              WHERE stock_reservations.stock_id = stocks.id
+                AND (stock_reservations.expires_at IS NULL OR stock_reservations.expires_at > $2)
+            ), 0))::BIGINT AS "available!"
+          FROM stocks
+          WHERE stocks.variant_id = ANY($1)
+          GROUP BY stocks.variant_id
+        "#,
+        variant_ids,
+        now
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
+// reserves quantity units of the variant for a cart item, spreading them over warehouses,
+// returns false without reserving anything when there isn't enough available stock
+pub async fn reserve(
+    cart_item_id: &Uuid,
+    variant_id: &Uuid,
+    quantity: i32,
+    expires_at: NaiveDateTime,
+    db: &mut PgConnection,
+) -> Result<bool, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+
+    let stocks = sqlx::query!(
+        r#"
+          SELECT
+            stocks.id,
+            stocks.quantity - COALESCE((
+              SELECT SUM(stock_reservations.quantity)
+              FROM stock_reservations
+              WHERE stock_reservations.stock_id = stocks.id
+                AND (stock_reservations.expires_at IS NULL OR stock_reservations.expires_at > $2)
+            ), 0)::INTEGER AS "available!"
+          FROM stocks
+          WHERE stocks.variant_id = $1
+          ORDER BY stocks.quantity DESC
+          FOR UPDATE
+        "#,
+        variant_id,
+        now
+    )
+    .fetch_all(&mut *db)
+    .await?;
+
+    let mut stock_ids: Vec<Uuid> = Vec::new();
+    let mut quantities: Vec<i32> = Vec::new();
+    let mut remaining = quantity;
+
+    for stock in stocks {
+        if remaining == 0 {
+            break;
+        }
+
+        let taken = remaining.min(stock.available);
+
+        if taken <= 0 {
+            continue;
+        }
+
+        stock_ids.push(stock.id);
+        quantities.push(taken);
+        remaining -= taken;
+    }
+
+    if remaining > 0 {
+        return Ok(false);
+    }
+
+    sqlx::query!(
+        r#"
+          INSERT INTO stock_reservations(cart_item_id, expires_at, stock_id, quantity)
+          SELECT $1, $2, * FROM UNNEST($3::UUID[], $4::INTEGER[])
+        "#,
+        cart_item_id,
+        expires_at,
+        &stock_ids,
+        &quantities
+    )
+    .execute(&mut *db)
+    .await?;
+
+    Ok(true)
+}
+
+pub async fn release(
+    cart_item_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM stock_reservations WHERE cart_item_id = $1",
+        cart_item_id
+    )
+    .execute(&mut *db)
+    .await
+}
+
+// drops the checkout holds of a cart's variant lines, bundle lines keep theirs until they run out
+pub async fn release_checkout(
+    cart_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+          DELETE FROM stock_reservations
+          USING cart_items
+          WHERE cart_items.id = stock_reservations.cart_item_id
+            AND cart_items.cart_id = $1
+            AND cart_items.bundle_id IS NULL
+        "#,
+        cart_id
+    )
+    .execute(&mut *db)
+    .await
+}
+
+// locks the cart and its holds until the transaction ends so nothing releases them while
+// a payment is settled, returns false when the cart doesn't exist
+pub async fn lock_checkout(cart_id: &Uuid, db: &mut PgConnection) -> Result<bool, sqlx::Error> {
+    let cart = sqlx::query_scalar!("SELECT id FROM carts WHERE id = $1 FOR UPDATE", cart_id)
+        .fetch_optional(&mut *db)
+        .await?;
+
+    if cart.is_none() {
+        return Ok(false);
+    }
+
+    sqlx::query!(
+        r#"
+          SELECT stock_reservations.id
+          FROM stock_reservations
+          JOIN cart_items ON cart_items.id = stock_reservations.cart_item_id
+          WHERE cart_items.cart_id = $1
+          FOR UPDATE OF stock_reservations
+        "#,
+        cart_id
+    )
+    .fetch_all(&mut *db)
+    .await?;
+
+    Ok(true)
+}
+
+// every bundle and physical variant line of the cart is still fully held by its checkout,
+// a variant line needs its own quantity and a bundle line that many of each component
+pub async fn checkout_active(
+    cart_id: &Uuid,
+    now: &NaiveDateTime,
+    db: &mut PgConnection,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+          SELECT
+            EXISTS(SELECT 1 FROM cart_items WHERE cart_id = $1)
+            AND NOT EXISTS(
+              SELECT 1 FROM cart_items
+              WHERE cart_items.cart_id = $1
+                AND (cart_items.bundle_id IS NOT NULL OR (
+                  cart_items.variant_id IS NOT NULL
+                  AND NOT EXISTS(SELECT 1 FROM digital_files WHERE digital_files.variant_id = cart_items.variant_id)
+                ))
+                AND cart_items.quantity * COALESCE((
+                  SELECT SUM(bundle_items.quantity)
+                  FROM bundle_items
+                  WHERE bundle_items.bundle_id = cart_items.bundle_id
+                ), 1) > COALESCE((
+                  SELECT SUM(stock_reservations.quantity)
+                  FROM stock_reservations
+                  WHERE stock_reservations.cart_item_id = cart_items.id
+                    AND stock_reservations.expires_at > $2
+                ), 0)
+            ) AS "active!"
+        "#,
+        cart_id,
+        now
+    )
+    .fetch_one(&mut *db)
+    .await
+}
+
+// turns the cart's reservations into stock deductions and empties the cart
+pub async fn deduct(
+    cart_id: &Uuid,
+    now: &NaiveDateTime,
+    db: &mut PgConnection,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+          UPDATE stocks SET quantity = stocks.quantity - reserved.quantity
+          FROM (
+            SELECT stock_reservations.stock_id, SUM(stock_reservations.quantity)::INTEGER AS quantity
+            FROM stock_reservations
+            JOIN cart_items ON cart_items.id = stock_reservations.cart_item_id
+            WHERE cart_items.cart_id = $1
+              AND (stock_reservations.expires_at IS NULL OR stock_reservations.expires_at > $2)
+            GROUP BY stock_reservations.stock_id
+          ) AS reserved
+          WHERE stocks.id = reserved.stock_id
+        "#,
+        cart_id,
+        now
+    )
+    .execute(&mut *db)
+    .await?;
+
+    sqlx::query!("DELETE FROM cart_items WHERE cart_id = $1", cart_id)
+        .execute(&mut *db)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn sweep(now: &NaiveDateTime, db: &mut PgConnection) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!("DELETE FROM stock_reservations WHERE expires_at <= $1", now)
+        .execute(&mut *db)
+        .await?;
+
+    Ok(result.rows_affected())
+}
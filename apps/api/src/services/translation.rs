@@ -0,0 +1,447 @@
+use sqlx::{postgres::PgQueryResult, PgConnection};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        category::Category,
+        product::SProduct,
+        translation::{
+            CategoryTranslation, OptionTranslation, PopulatedProductTranslation,
+            ProductTranslation, UnitTranslation,
+        },
+        unit::Unit,
+    },
+    services,
+    utils::constants::{DEFAULT_LOCALE, SETTINGS},
+    validations::{
+        settings::StoreSettingsSchema,
+        translation::{StoreProductTranslationSchema, StoreTranslationSchema},
+    },
+};
+
+pub async fn default_locale(db: &mut PgConnection) -> Result<String, sqlx::Error> {
+    Ok(services::settings::find_by_key(SETTINGS.default_locale, db)
+        .await?
+        .map(|setting| setting.value)
+        .unwrap_or(DEFAULT_LOCALE.to_string()))
+}
+
+pub async fn set_default_locale(locale: &str, db: &mut PgConnection) -> Result<(), sqlx::Error> {
+    let input = StoreSettingsSchema {
+        key: SETTINGS.default_locale.to_string(),
+        value: locale.to_lowercase(),
+    };
+
+    match services::settings::find_by_key(SETTINGS.default_locale, db).await? {
+        Some(setting) => {
+            services::settings::update(&setting.id, &input, db).await?;
+        }
+        None => {
+            services::settings::insert(&input, db).await?;
+        }
+    };
+
+    Ok(())
+}
+
+pub async fn available(db: &mut PgConnection) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+          SELECT locale AS "locale!" FROM product_translations
+          UNION SELECT locale FROM category_translations
+          UNION SELECT locale FROM unit_translations
+          ORDER BY 1
+        "#
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
+// locales worth looking up, anything after the default one is never needed
+pub async fn candidates(
+    preferred: Vec<String>,
+    db: &mut PgConnection,
+) -> Result<Vec<String>, sqlx::Error> {
+    if preferred.is_empty() {
+        return Ok(preferred);
+    }
+
+    let default = default_locale(db).await?;
+
+    Ok(preferred
+        .into_iter()
+        .take_while(|locale| locale != &default)
+        .collect())
+}
+
+fn pick<'a, T>(
+    translations: &'a [T],
+    locales: &[String],
+    matches: impl Fn(&T, &str) -> bool,
+) -> Option<&'a T> {
+    locales.iter().find_map(|locale| {
+        translations
+            .iter()
+            .find(|translation| matches(translation, locale))
+    })
+}
+
+pub async fn product_translations(
+    product_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<Vec<PopulatedProductTranslation>, sqlx::Error> {
+    let translations = sqlx::query_as!(
+        ProductTranslation,
+        "SELECT * FROM product_translations WHERE product_id = $1 ORDER BY locale",
+        product_id
+    )
+    .fetch_all(&mut *db)
+    .await?;
+
+    let options = sqlx::query_as!(
+        OptionTranslation,
+        "SELECT * FROM option_translations WHERE product_id = $1 ORDER BY source",
+        product_id
+    )
+    .fetch_all(&mut *db)
+    .await?;
+
+    Ok(translations
+        .into_iter()
+        .map(|translation| PopulatedProductTranslation {
+            options: options
+                .iter()
+                .filter(|option| option.locale == translation.locale)
+                .cloned()
+                .collect(),
+            translation,
+        })
+        .collect())
+}
+
+pub async fn set_product_translation(
+    product_id: &Uuid,
+    input: &StoreProductTranslationSchema,
+    db: &mut PgConnection,
+) -> Result<PopulatedProductTranslation, sqlx::Error> {
+    let locale = input.locale.to_lowercase();
+
+    let translation = sqlx::query_as!(
+        ProductTranslation,
+        r#"
+          INSERT INTO product_translations(product_id, locale, name, description)
+          VALUES ($1, $2, $3, $4)
+          ON CONFLICT (product_id, locale) DO UPDATE
+          SET name = EXCLUDED.name, description = EXCLUDED.description
+          RETURNING *
+        "#,
+        product_id,
+        locale,
+        input.name,
+        input.description
+    )
+    .fetch_one(&mut *db)
+    .await?;
+
+    sqlx::query!(
+        "DELETE FROM option_translations WHERE product_id = $1 AND locale = $2",
+        product_id,
+        locale
+    )
+    .execute(&mut *db)
+    .await?;
+
+    let options = sqlx::query_as!(
+        OptionTranslation,
+        r#"
+          INSERT INTO option_translations(product_id, locale, source, name)
+          SELECT $1, $2, * FROM UNNEST($3::TEXT[], $4::TEXT[])
+          ON CONFLICT (product_id, locale, source) DO UPDATE SET name = EXCLUDED.name
+          RETURNING *
+        "#,
+        product_id,
+        locale,
+        &input
+            .options
+            .iter()
+            .map(|option| option.source.clone())
+            .collect::<Vec<String>>(),
+        &input
+            .options
+            .iter()
+            .map(|option| option.name.clone())
+            .collect::<Vec<String>>()
+    )
+    .fetch_all(&mut *db)
+    .await?;
+
+    Ok(PopulatedProductTranslation {
+        translation,
+        options,
+    })
+}
+
+pub async fn destroy_product_translation(
+    product_id: &Uuid,
+    locale: &str,
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM option_translations WHERE product_id = $1 AND locale = $2",
+        product_id,
+        locale.to_lowercase()
+    )
+    .execute(&mut *db)
+    .await?;
+
+    sqlx::query!(
+        "DELETE FROM product_translations WHERE product_id = $1 AND locale = $2",
+        product_id,
+        locale.to_lowercase()
+    )
+    .execute(&mut *db)
+    .await
+}
+
+pub async fn category_translations(
+    category_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<Vec<CategoryTranslation>, sqlx::Error> {
+    sqlx::query_as!(
+        CategoryTranslation,
+        "SELECT * FROM category_translations WHERE category_id = $1 ORDER BY locale",
+        category_id
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
+pub async fn set_category_translation(
+    category_id: &Uuid,
+    input: &StoreTranslationSchema,
+    db: &mut PgConnection,
+) -> Result<CategoryTranslation, sqlx::Error> {
+    sqlx::query_as!(
+        CategoryTranslation,
+        r#"
+          INSERT INTO category_translations(category_id, locale, name)
+          VALUES ($1, $2, $3)
+          ON CONFLICT (category_id, locale) DO UPDATE SET name = EXCLUDED.name
+          RETURNING *
+        "#,
+        category_id,
+        input.locale.to_lowercase(),
+        input.name
+    )
+    .fetch_one(&mut *db)
+    .await
+}
+
+pub async fn destroy_category_translation(
+    category_id: &Uuid,
+    locale: &str,
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM category_translations WHERE category_id = $1 AND locale = $2",
+        category_id,
+        locale.to_lowercase()
+    )
+    .execute(&mut *db)
+    .await
+}
+
+pub async fn unit_translations(
+    unit_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<Vec<UnitTranslation>, sqlx::Error> {
+    sqlx::query_as!(
+        UnitTranslation,
+        "SELECT * FROM unit_translations WHERE unit_id = $1 ORDER BY locale",
+        unit_id
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
+pub async fn set_unit_translation(
+    unit_id: &Uuid,
+    input: &StoreTranslationSchema,
+    db: &mut PgConnection,
+) -> Result<UnitTranslation, sqlx::Error> {
+    sqlx::query_as!(
+        UnitTranslation,
+        r#"
+          INSERT INTO unit_translations(unit_id, locale, name)
+          VALUES ($1, $2, $3)
+          ON CONFLICT (unit_id, locale) DO UPDATE SET name = EXCLUDED.name
+          RETURNING *
+        "#,
+        unit_id,
+        input.locale.to_lowercase(),
+        input.name
+    )
+    .fetch_one(&mut *db)
+    .await
+}
+
+pub async fn destroy_unit_translation(
+    unit_id: &Uuid,
+    locale: &str,
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM unit_translations WHERE unit_id = $1 AND locale = $2",
+        unit_id,
+        locale.to_lowercase()
+    )
+    .execute(&mut *db)
+    .await
+}
+
+pub async fn localize_categories(
+    categories: &mut [Category],
+    locales: &[String],
+    db: &mut PgConnection,
+) -> Result<(), sqlx::Error> {
+    if locales.is_empty() || categories.is_empty() {
+        return Ok(());
+    }
+
+    let translations = sqlx::query_as!(
+        CategoryTranslation,
+        "SELECT * FROM category_translations WHERE category_id = ANY($1) AND locale = ANY($2)",
+        &categories
+            .iter()
+            .map(|category| category.id)
+            .collect::<Vec<Uuid>>(),
+        locales
+    )
+    .fetch_all(&mut *db)
+    .await?;
+
+    for category in categories.iter_mut() {
+        if let Some(translation) = pick(&translations, locales, |translation, locale| {
+            translation.category_id == category.id && translation.locale == locale
+        }) {
+            category.name = translation.name.clone();
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn localize_units(
+    units: &mut [Unit],
+    locales: &[String],
+    db: &mut PgConnection,
+) -> Result<(), sqlx::Error> {
+    if locales.is_empty() || units.is_empty() {
+        return Ok(());
+    }
+
+    let translations = sqlx::query_as!(
+        UnitTranslation,
+        "SELECT * FROM unit_translations WHERE unit_id = ANY($1) AND locale = ANY($2)",
+        &units.iter().map(|unit| unit.id).collect::<Vec<Uuid>>(),
+        locales
+    )
+    .fetch_all(&mut *db)
+    .await?;
+
+    for unit in units.iter_mut() {
+        if let Some(translation) = pick(&translations, locales, |translation, locale| {
+            translation.unit_id == unit.id && translation.locale == locale
+        }) {
+            unit.name = translation.name.clone();
+        }
+    }
+
+    Ok(())
+}
+
+// each field falls back on its own, a missing option label keeps the original text
+pub async fn localize_products(
+    products: &mut [SProduct],
+    locales: &[String],
+    db: &mut PgConnection,
+) -> Result<(), sqlx::Error> {
+    if locales.is_empty() || products.is_empty() {
+        return Ok(());
+    }
+
+    let product_ids = products
+        .iter()
+        .map(|product| product.product.id)
+        .collect::<Vec<Uuid>>();
+
+    let translations = sqlx::query_as!(
+        ProductTranslation,
+        "SELECT * FROM product_translations WHERE product_id = ANY($1) AND locale = ANY($2)",
+        &product_ids,
+        locales
+    )
+    .fetch_all(&mut *db)
+    .await?;
+
+    let options = sqlx::query_as!(
+        OptionTranslation,
+        "SELECT * FROM option_translations WHERE product_id = ANY($1) AND locale = ANY($2)",
+        &product_ids,
+        locales
+    )
+    .fetch_all(&mut *db)
+    .await?;
+
+    let mut categories: Vec<Category> = products
+        .iter()
+        .map(|product| product.category.clone())
+        .collect();
+    localize_categories(&mut categories, locales, db).await?;
+
+    let mut units: Vec<Unit> = products
+        .iter()
+        .map(|product| product.unit.clone())
+        .collect();
+    localize_units(&mut units, locales, db).await?;
+
+    let label = |product_id: Uuid, source: &str| {
+        pick(&options, locales, |option, locale| {
+            option.product_id == product_id && option.source == source && option.locale == locale
+        })
+        .map(|option| option.name.clone())
+    };
+
+    for (index, product) in products.iter_mut().enumerate() {
+        let product_id = product.product.id;
+
+        if let Some(translation) = pick(&translations, locales, |translation, locale| {
+            translation.product_id == product_id && translation.locale == locale
+        }) {
+            product.product.name = translation.name.clone();
+
+            if translation.description.is_some() {
+                product.product.description = translation.description.clone();
+            }
+        }
+
+        product.category.name = categories[index].name.clone();
+        product.unit.name = units[index].name.clone();
+
+        for collection in product
+            .variants
+            .iter_mut()
+            .flat_map(|variant| variant.collections.iter_mut())
+        {
+            if let Some(name) = label(product_id, &collection.key.name) {
+                collection.key.name = name;
+            }
+
+            if let Some(name) = label(product_id, &collection.value.name) {
+                collection.value.name = name;
+            }
+        }
+    }
+
+    Ok(())
+}
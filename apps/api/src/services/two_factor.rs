@@ -0,0 +1,195 @@
+use chrono::NaiveDateTime;
+use sqlx::{postgres::PgQueryResult, Acquire, PgConnection};
+use uuid::Uuid;
+
+use crate::{
+    models::two_factor::TotpFactor, services, utils::constants::SETTINGS,
+    validations::settings::StoreSettingsSchema,
+};
+
+pub async fn find(
+    user_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<Option<TotpFactor>, sqlx::Error> {
+    sqlx::query_as!(
+        TotpFactor,
+        "SELECT user_id, secret, sealed, last_step, confirmed_at, created_at FROM totp_factors WHERE user_id = $1",
+        user_id
+    )
+    .fetch_optional(db)
+    .await
+}
+
+// starts over while unconfirmed, a confirmed factor has to be disabled first,
+// the secret comes in already sealed
+pub async fn enroll(
+    user_id: &Uuid,
+    secret: &[u8],
+    now: &NaiveDateTime,
+    db: &mut PgConnection,
+) -> Result<Option<TotpFactor>, sqlx::Error> {
+    sqlx::query_as!(
+        TotpFactor,
+        r#"
+            INSERT INTO totp_factors(user_id, secret, sealed, created_at) VALUES ($1, $2, TRUE, $3)
+            ON CONFLICT (user_id) DO UPDATE SET secret = $2, sealed = TRUE, last_step = NULL, created_at = $3
+            WHERE totp_factors.confirmed_at IS NULL
+            RETURNING user_id, secret, sealed, last_step, confirmed_at, created_at
+        "#,
+        user_id,
+        secret,
+        now
+    )
+    .fetch_optional(db)
+    .await
+}
+
+pub async fn unsealed(db: &mut PgConnection) -> Result<Vec<TotpFactor>, sqlx::Error> {
+    sqlx::query_as!(
+        TotpFactor,
+        "SELECT user_id, secret, sealed, last_step, confirmed_at, created_at FROM totp_factors WHERE NOT sealed"
+    )
+    .fetch_all(db)
+    .await
+}
+
+// leaves the factor alone if it was enrolled again in the meantime
+pub async fn seal(
+    user_id: &Uuid,
+    secret: &[u8],
+    sealed: &[u8],
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        "UPDATE totp_factors SET secret = $3, sealed = TRUE WHERE user_id = $1 AND secret = $2 AND NOT sealed",
+        user_id,
+        secret,
+        sealed
+    )
+    .execute(db)
+    .await
+}
+
+// a code is only good once, a step at or before the last one used is refused
+pub async fn use_step(
+    user_id: &Uuid,
+    step: i64,
+    db: &mut PgConnection,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+            UPDATE totp_factors SET last_step = $2
+            WHERE user_id = $1 AND (last_step IS NULL OR last_step < $2)
+        "#,
+        user_id,
+        step
+    )
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+pub async fn confirm(
+    user_id: &Uuid,
+    now: &NaiveDateTime,
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        "UPDATE totp_factors SET confirmed_at = $2 WHERE user_id = $1",
+        user_id,
+        now
+    )
+    .execute(db)
+    .await
+}
+
+pub async fn destroy(user_id: &Uuid, db: &mut PgConnection) -> Result<(), sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    sqlx::query!("DELETE FROM recovery_codes WHERE user_id = $1", user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query!("DELETE FROM totp_factors WHERE user_id = $1", user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await
+}
+
+pub async fn recovery_codes_left(
+    user_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM recovery_codes WHERE user_id = $1"#,
+        user_id
+    )
+    .fetch_one(db)
+    .await
+}
+
+pub async fn replace_recovery_codes(
+    user_id: &Uuid,
+    code_hashes: &[String],
+    db: &mut PgConnection,
+) -> Result<(), sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    sqlx::query!("DELETE FROM recovery_codes WHERE user_id = $1", user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query!(
+        "INSERT INTO recovery_codes(user_id, code_hash) SELECT $1, * FROM UNNEST($2::TEXT[])",
+        user_id,
+        code_hashes
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await
+}
+
+pub async fn consume_recovery_code(
+    user_id: &Uuid,
+    code_hashes: &[String],
+    db: &mut PgConnection,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        "DELETE FROM recovery_codes WHERE user_id = $1 AND code_hash = ANY($2)",
+        user_id,
+        code_hashes
+    )
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+pub async fn required(db: &mut PgConnection) -> Result<bool, sqlx::Error> {
+    Ok(
+        services::settings::find_by_key(SETTINGS.require_admin_two_factor, db)
+            .await?
+            .is_some_and(|setting| setting.value == "true"),
+    )
+}
+
+pub async fn set_required(required: bool, db: &mut PgConnection) -> Result<(), sqlx::Error> {
+    let input = StoreSettingsSchema {
+        key: SETTINGS.require_admin_two_factor.to_string(),
+        value: required.to_string(),
+    };
+
+    match services::settings::find_by_key(SETTINGS.require_admin_two_factor, db).await? {
+        Some(setting) => {
+            services::settings::update(&setting.id, &input, db).await?;
+        }
+        None => {
+            services::settings::insert(&input, db).await?;
+        }
+    };
+
+    Ok(())
+}
@@ -6,6 +6,7 @@ use crate::{
         role::Role,
         user::{PopulatedUser, User},
     },
+    services,
     validations::user::StoreUserSchema,
 };
 
@@ -14,7 +15,7 @@ pub async fn all(db: &mut PgConnection) -> Result<Vec<PopulatedUser>, sqlx::Erro
         PopulatedUser,
         r#"
             SELECT 
-                (users.id, users.name, users.email, users.role_id, users.created_at) as "user!: User",
+                (users.id, users.name, users.email, users.role_id, users.created_at, users.customer_group_id) as "user!: User",
                 (roles.id, roles.name, roles.created_at) as "role!: Role"
             FROM users 
             JOIN roles ON roles.id = users.role_id
@@ -29,7 +30,7 @@ pub async fn find(id: &Uuid, db: &mut PgConnection) -> Result<Option<PopulatedUs
         PopulatedUser,
         r#"
             SELECT 
-                (users.id, users.name, users.email, users.role_id, users.created_at) as "user!: User",
+                (users.id, users.name, users.email, users.role_id, users.created_at, users.customer_group_id) as "user!: User",
                 (roles.id, roles.name, roles.created_at) as "role!: Role"
             FROM users 
             JOIN roles ON roles.id = users.role_id
@@ -49,7 +50,7 @@ pub async fn find_by_email(
         PopulatedUser,
         r#"
             SELECT 
-                (users.id, users.name, users.email, users.role_id, users.created_at) as "user!: User",
+                (users.id, users.name, users.email, users.role_id, users.created_at, users.customer_group_id) as "user!: User",
                 (roles.id, roles.name, roles.created_at) as "role!: Role"
             FROM users 
             JOIN roles ON roles.id = users.role_id
@@ -72,7 +73,7 @@ pub async fn insert(
                 INSERT INTO users(name, email, role_id) VALUES($1, $2, $3) RETURNING *
             )
             SELECT 
-                (new_user.id, new_user.name, new_user.email, new_user.role_id, new_user.created_at) as "user!: User",
+                (new_user.id, new_user.name, new_user.email, new_user.role_id, new_user.created_at, new_user.customer_group_id) as "user!: User",
                 (roles.id, roles.name, roles.created_at) as "role!: Role"
             FROM new_user
             JOIN roles ON roles.id = new_user.role_id
@@ -85,6 +86,31 @@ pub async fn insert(
     .await
 }
 
+// signing in with an unknown email registers it with the given role
+pub async fn find_or_insert(
+    email: &str,
+    role: &str,
+    db: &mut PgConnection,
+) -> Result<PopulatedUser, sqlx::Error> {
+    if let Some(user) = find_by_email(email, &mut *db).await? {
+        return Ok(user);
+    }
+
+    let role = services::role::find_by_name(role, &mut *db)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+
+    insert(
+        &StoreUserSchema {
+            name: None,
+            role_id: role.id,
+            email: email.to_string(),
+        },
+        db,
+    )
+    .await
+}
+
 pub async fn update(
     id: &Uuid,
     input: &StoreUserSchema,
@@ -0,0 +1,172 @@
+use sqlx::{postgres::PgQueryResult, Acquire, PgConnection};
+use uuid::Uuid;
+
+use crate::models::{
+    product::{Product, ProductVariant},
+    wishlist::{PopulatedWishlistItem, Wishlist, WishlistItem},
+};
+
+pub async fn find(id: &Uuid, db: &mut PgConnection) -> Result<Option<Wishlist>, sqlx::Error> {
+    sqlx::query_as!(Wishlist, "SELECT * FROM wishlists WHERE id = $1", id)
+        .fetch_optional(&mut *db)
+        .await
+}
+
+pub async fn find_by_user_id(
+    user_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<Option<Wishlist>, sqlx::Error> {
+    sqlx::query_as!(
+        Wishlist,
+        "SELECT * FROM wishlists WHERE user_id = $1",
+        user_id
+    )
+    .fetch_optional(&mut *db)
+    .await
+}
+
+// a guest wishlist becomes the user's on sign in, or joins the one they already have
+pub async fn claim(
+    guest_id: &Uuid,
+    user_id: &Uuid,
+    connection: &mut PgConnection,
+) -> Result<Option<Wishlist>, sqlx::Error> {
+    let mut tx = connection.begin().await?;
+
+    let guest = sqlx::query_as!(
+        Wishlist,
+        "SELECT * FROM wishlists WHERE id = $1 AND user_id IS NULL FOR UPDATE",
+        guest_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(guest) = guest else {
+        tx.rollback().await?;
+        return find_by_user_id(user_id, connection).await;
+    };
+
+    let wishlist = match find_by_user_id(user_id, &mut tx).await? {
+        Some(owned) => {
+            sqlx::query!(
+                r#"
+                  INSERT INTO wishlist_items(wishlist_id, product_id, variant_id, created_at)
+                  SELECT $2, product_id, variant_id, created_at FROM wishlist_items WHERE wishlist_id = $1
+                  ON CONFLICT (wishlist_id, product_id, variant_id) DO NOTHING
+                "#,
+                guest.id,
+                owned.id
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query!("DELETE FROM wishlists WHERE id = $1", guest.id)
+                .execute(&mut *tx)
+                .await?;
+
+            owned
+        }
+        None => {
+            sqlx::query_as!(
+                Wishlist,
+                "UPDATE wishlists SET user_id = $2 WHERE id = $1 RETURNING *",
+                guest.id,
+                user_id
+            )
+            .fetch_one(&mut *tx)
+            .await?
+        }
+    };
+
+    tx.commit().await?;
+
+    Ok(Some(wishlist))
+}
+
+pub async fn insert(user_id: Option<Uuid>, db: &mut PgConnection) -> Result<Wishlist, sqlx::Error> {
+    sqlx::query_as!(
+        Wishlist,
+        "INSERT INTO wishlists(user_id) VALUES ($1) RETURNING *",
+        user_id
+    )
+    .fetch_one(&mut *db)
+    .await
+}
+
+pub async fn items(
+    wishlist_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<Vec<PopulatedWishlistItem>, sqlx::Error> {
+    sqlx::query_as!(
+        PopulatedWishlistItem,
+        r#"
+          SELECT
+            (wishlist_items.id, wishlist_items.wishlist_id, wishlist_items.product_id, wishlist_items.variant_id, wishlist_items.created_at) AS "item!: WishlistItem",
+            (products.id, products.name, products.description, products.unit_id, products.category_id, products.created_at, products.slug) AS "product!: Product",
+            CASE
+              WHEN product_variants.id IS NULL THEN NULL
+              ELSE (product_variants.id, product_variants.price, product_variants.product_id, product_variants.sku)
+            END AS "variant: ProductVariant"
+          FROM wishlist_items
+          JOIN products ON products.id = wishlist_items.product_id
+          LEFT JOIN product_variants ON product_variants.id = wishlist_items.variant_id
+          WHERE wishlist_items.wishlist_id = $1
+          ORDER BY wishlist_items.created_at DESC
+        "#,
+        wishlist_id
+    )
+    .fetch_all(&mut *db)
+    .await
+}
+
+pub async fn find_item(
+    wishlist_id: &Uuid,
+    item_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<Option<WishlistItem>, sqlx::Error> {
+    sqlx::query_as!(
+        WishlistItem,
+        "SELECT * FROM wishlist_items WHERE wishlist_id = $1 AND id = $2",
+        wishlist_id,
+        item_id
+    )
+    .fetch_optional(&mut *db)
+    .await
+}
+
+// adding the same product or variant twice returns the existing item
+pub async fn add_item(
+    wishlist_id: &Uuid,
+    product_id: &Uuid,
+    variant_id: Option<Uuid>,
+    db: &mut PgConnection,
+) -> Result<WishlistItem, sqlx::Error> {
+    sqlx::query_as!(
+        WishlistItem,
+        r#"
+          INSERT INTO wishlist_items(wishlist_id, product_id, variant_id)
+          VALUES ($1, $2, $3)
+          ON CONFLICT (wishlist_id, product_id, variant_id) DO UPDATE SET created_at = wishlist_items.created_at
+          RETURNING *
+        "#,
+        wishlist_id,
+        product_id,
+        variant_id
+    )
+    .fetch_one(&mut *db)
+    .await
+}
+
+pub async fn delete_item(
+    wishlist_id: &Uuid,
+    item_id: &Uuid,
+    db: &mut PgConnection,
+) -> Result<PgQueryResult, sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM wishlist_items WHERE wishlist_id = $1 AND id = $2",
+        wishlist_id,
+        item_id
+    )
+    .execute(&mut *db)
+    .await
+}
@@ -0,0 +1,30 @@
+use rand::{rngs::OsRng, RngCore};
+
+use super::{constants::API_KEY_PREFIX, signing};
+
+// returns the public prefix and the full key, which is only ever shown once
+pub fn generate() -> (String, String) {
+    let mut prefix = [0u8; 4];
+    OsRng.fill_bytes(&mut prefix);
+    let prefix = hex::encode(prefix);
+
+    let mut secret = [0u8; 32];
+    OsRng.fill_bytes(&mut secret);
+
+    let key = format!("{}_{}_{}", API_KEY_PREFIX, prefix, hex::encode(secret));
+    (prefix, key)
+}
+
+pub fn hash(key: &str) -> String {
+    signing::digest(&format!("api_key:{}", key))
+}
+
+pub fn bearer(authorization: &str) -> Option<&str> {
+    let (scheme, token) = authorization.split_once(' ')?;
+
+    if !scheme.eq_ignore_ascii_case("bearer") {
+        return None;
+    }
+
+    Some(token.trim())
+}
@@ -0,0 +1,130 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::http::{header, HeaderMap};
+use cookie::Cookie;
+use uuid::Uuid;
+
+use crate::utils::{
+    constants::{
+        CART_COOKIE_NAME, CSRF_COOKIE_NAME, SESSION_COOKIE_NAME, SESSION_MAX_AGE,
+        WISHLIST_COOKIE_NAME,
+    },
+    env::Env,
+    signing,
+};
+
+// the connecting address, or the last hop a trusted proxy added in front of it
+pub fn trusted_ip(env: &Env, headers: &HeaderMap, peer: Option<SocketAddr>) -> Option<IpAddr> {
+    let peer = peer?.ip();
+
+    if !env.trusted_proxies.contains(&peer) {
+        return Some(peer);
+    }
+
+    let forwarded = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|forwarded| forwarded.to_str().ok())
+        .flat_map(|forwarded| forwarded.split(','))
+        .map(|ip| ip.trim().parse::<IpAddr>())
+        .collect::<Vec<_>>();
+
+    // walked from the right, an entry that isn't an address ends the walk at the proxy
+    for ip in forwarded.into_iter().rev() {
+        match ip {
+            Ok(ip) if env.trusted_proxies.contains(&ip) => continue,
+            Ok(ip) => return Some(ip),
+            Err(_) => break,
+        }
+    }
+
+    Some(peer)
+}
+
+pub fn user_agent(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::USER_AGENT)
+        .and_then(|user_agent| user_agent.to_str().ok())
+        .map(|user_agent| user_agent.to_string())
+}
+
+// every cookie the api sets shares these attributes
+fn set_cookie(name: &str, value: &str) -> String {
+    format!(
+        "{}={}; Path=/; HttpOnly; Secure; SameSite=Strict; Max-Age={}",
+        name, value, SESSION_MAX_AGE
+    )
+}
+
+// expires the cookie straight away, same attributes so the browser drops the right one
+pub fn clear_cookie(name: &str) -> String {
+    format!(
+        "{}=; Path=/; HttpOnly; Secure; SameSite=Strict; Max-Age=0",
+        name
+    )
+}
+
+// bound to the cookie's name so a cart id can't be passed off as a session
+fn cookie_payload(name: &str, value: &str) -> String {
+    format!("cookie:{}:{}", name, value)
+}
+
+// value.signature, signed with the current secret only
+pub fn signed_value(env: &Env, name: &str, value: &str) -> String {
+    let signature = signing::sign(&env.app_secret, &cookie_payload(name, value));
+    format!("{}.{}", value, signature)
+}
+
+pub fn session_cookie(env: &Env, session: &Uuid) -> String {
+    set_cookie(
+        SESSION_COOKIE_NAME,
+        &signed_value(env, SESSION_COOKIE_NAME, &session.to_string()),
+    )
+}
+
+// lives as long as a session so a dashboard tab keeps working
+pub fn csrf_cookie(token: &str) -> String {
+    set_cookie(CSRF_COOKIE_NAME, token)
+}
+
+pub fn cart_cookie(env: &Env, cart_id: &Uuid) -> String {
+    set_cookie(
+        CART_COOKIE_NAME,
+        &signed_value(env, CART_COOKIE_NAME, &cart_id.to_string()),
+    )
+}
+
+pub fn wishlist_cookie(env: &Env, wishlist_id: &Uuid) -> String {
+    set_cookie(
+        WISHLIST_COOKIE_NAME,
+        &signed_value(env, WISHLIST_COOKIE_NAME, &wishlist_id.to_string()),
+    )
+}
+
+pub fn cookie(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|cookies| cookies.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .filter_map(|cookie| Cookie::parse(cookie.trim()).ok())
+        .find(|cookie| cookie.name() == name)
+        .map(|cookie| cookie.value().to_string())
+}
+
+// previous secrets are still accepted, the cookie is signed again with the current one
+// the next time it is set
+pub fn signed_cookie(env: &Env, headers: &HeaderMap, name: &str) -> Option<String> {
+    let cookie = cookie(headers, name)?;
+    let (value, signature) = cookie.rsplit_once('.')?;
+    let payload = cookie_payload(name, value);
+
+    env.secrets()
+        .any(|secret| signing::verify(secret, &payload, signature))
+        .then(|| value.to_string())
+}
+
+// a tampered or unsigned cookie is treated as if it wasn't sent
+pub fn cookie_id(env: &Env, headers: &HeaderMap, name: &str) -> Option<Uuid> {
+    signed_cookie(env, headers, name).and_then(|value| Uuid::parse_str(&value).ok())
+}
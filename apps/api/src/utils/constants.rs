@@ -1,3 +1,5 @@
+use super::rate_limit::{RateLimitKey, RateLimitPolicy};
+
 pub struct Role {
     pub admin: &'static str,
     pub member: &'static str,
@@ -8,16 +10,246 @@ pub const ROLES: Role = Role {
     member: "member",
 };
 
+pub struct Permission {
+    pub roles_manage: &'static str,
+    pub users_manage: &'static str,
+    pub pricing_write: &'static str,
+    pub catalog_write: &'static str,
+    pub products_write: &'static str,
+    pub reviews_moderate: &'static str,
+    pub stock_manage: &'static str,
+    pub orders_manage: &'static str,
+    pub settings_manage: &'static str,
+    pub api_keys_manage: &'static str,
+}
+
+pub const PERMISSIONS: Permission = Permission {
+    roles_manage: "roles.manage",
+    users_manage: "users.manage",
+    pricing_write: "pricing.write",
+    catalog_write: "catalog.write",
+    products_write: "products.write",
+    reviews_moderate: "reviews.moderate",
+    stock_manage: "stock.manage",
+    orders_manage: "orders.manage",
+    settings_manage: "settings.manage",
+    api_keys_manage: "api_keys.manage",
+};
+
+// everything a role can be granted, the admin role always has all of it
+pub const PERMISSION_NAMES: [&str; 10] = [
+    PERMISSIONS.roles_manage,
+    PERMISSIONS.users_manage,
+    PERMISSIONS.pricing_write,
+    PERMISSIONS.catalog_write,
+    PERMISSIONS.products_write,
+    PERMISSIONS.reviews_moderate,
+    PERMISSIONS.stock_manage,
+    PERMISSIONS.orders_manage,
+    PERMISSIONS.settings_manage,
+    PERMISSIONS.api_keys_manage,
+];
+
+pub struct ReviewStatus {
+    pub pending: &'static str,
+    pub approved: &'static str,
+    pub rejected: &'static str,
+}
+
+pub const REVIEW_STATUSES: ReviewStatus = ReviewStatus {
+    pending: "pending",
+    approved: "approved",
+    rejected: "rejected",
+};
+
+pub struct LinkKind {
+    pub related: &'static str,
+    pub cross_sell: &'static str,
+    pub up_sell: &'static str,
+}
+
+pub const LINK_KINDS: LinkKind = LinkKind {
+    related: "related",
+    cross_sell: "cross_sell",
+    up_sell: "up_sell",
+};
+
+pub struct PriceRuleKind {
+    pub fixed: &'static str,
+    pub percentage: &'static str,
+}
+
+pub const PRICE_RULE_KINDS: PriceRuleKind = PriceRuleKind {
+    fixed: "fixed",
+    percentage: "percentage",
+};
+
+pub struct RoundingMode {
+    pub nearest: &'static str,
+    pub up: &'static str,
+    pub down: &'static str,
+}
+
+pub const ROUNDING_MODES: RoundingMode = RoundingMode {
+    nearest: "nearest",
+    up: "up",
+    down: "down",
+};
+
+// used until an admin picks a base currency
+pub const DEFAULT_CURRENCY: &str = "USD";
+pub const CURRENCY_HEADER: &str = "x-currency";
+
+// content is written in the default locale, translations cover the others
+pub const DEFAULT_LOCALE: &str = "en";
+
+// products suggested from the same category when none are curated
+pub const RELATED_PRODUCTS_LIMIT: i64 = 8;
+
 pub const SESSION_COOKIE_NAME: &str = "session";
+
+// seconds a session lives without activity, also the cookie max-age
+pub const SESSION_MAX_AGE: i64 = 60 * 60 * 24 * 30;
+
+// seconds between two renewals of a session in use
+pub const SESSION_RENEW_INTERVAL: i64 = 60 * 5;
+
+// seconds a sign-in link and its code stay valid
+pub const MAGIC_TOKEN_EXPIRY: i64 = 60 * 15;
+
+// digits in the sign-in code, and wrong guesses allowed before it is burned
+pub const OTP_LENGTH: u32 = 6;
+pub const OTP_ATTEMPTS: i32 = 5;
+
+// bytes an APP_SECRET needs before the app agrees to start with it
+pub const APP_SECRET_MIN_LENGTH: usize = 32;
+
+// keys look like rmk_<prefix>_<secret>, the prefix is stored in the clear to tell them apart
+pub const API_KEY_PREFIX: &str = "rmk";
+
+// seconds before the last use of a key is written again
+pub const API_KEY_TOUCH_INTERVAL: i64 = 60;
+
+// double-submit pair, the dashboard echoes the cookie value back in the header
+pub const CSRF_COOKIE_NAME: &str = "csrf";
+pub const CSRF_HEADER_NAME: &str = "x-csrf-token";
+
 pub const CART_COOKIE_NAME: &str = "cart";
+pub const WISHLIST_COOKIE_NAME: &str = "wishlist_id";
 
 pub const PUBLIC_FOLDER_NAME: &str = "public";
+pub const PRIVATE_FOLDER_NAME: &str = "private";
+
+pub const IMAGE_CONTENT_TYPES: [&str; 2] = ["image/jpeg", "image/png"];
+
+// seconds to wait for a remote image before giving up
+pub const IMAGE_IMPORT_TIMEOUT: u64 = 10;
+
+// seconds a signed storage url stays valid
+pub const SIGNED_URL_EXPIRY: u32 = 60 * 60;
+
+// seconds between two orphan image sweeps
+pub const IMAGE_SWEEP_INTERVAL: u64 = 60 * 60;
+
+// seconds an unused image is kept before the sweep removes it
+pub const IMAGE_ORPHAN_GRACE: i64 = 60 * 60 * 24;
+
+// seconds between two sweeps of expired checkout reservations
+pub const RESERVATION_SWEEP_INTERVAL: u64 = 60;
+
+// seconds a password reset link stays valid
+pub const PASSWORD_RESET_EXPIRY: i64 = 60 * 60;
+
+// seconds the user has to come back from the oauth provider
+pub const OAUTH_STATE_EXPIRY: i64 = 60 * 10;
+pub const OAUTH_STATE_COOKIE_NAME: &str = "oauth_state";
+
+// seconds before a call to the oauth provider is given up
+pub const OAUTH_REQUEST_TIMEOUT: u64 = 10;
+
+// totp as authenticator apps expect it, one step of clock drift is tolerated either way
+pub const TOTP_ISSUER: &str = "Rumerce";
+pub const TOTP_PERIOD: i64 = 30;
+pub const TOTP_DIGITS: u32 = 6;
+pub const TOTP_SKEW: i64 = 1;
+
+// single use codes handed out when two-factor is turned on
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+// seconds between two sweeps of idle rate limit buckets
+pub const RATE_LIMIT_SWEEP_INTERVAL: u64 = 60 * 10;
+
+// longer than any policy period, a bucket idle this long is full again
+pub const RATE_LIMIT_IDLE: i64 = 60 * 60 * 24;
+
+// bytes read from a body when looking for the email to limit on
+pub const RATE_LIMIT_BODY_LIMIT: usize = 64 * 1024;
+
+pub const PUBLIC_RATE_LIMITS: &[RateLimitPolicy] = &[RateLimitPolicy {
+    name: "public",
+    key: RateLimitKey::Ip,
+    capacity: 300,
+    period: 60,
+}];
+
+// every sign-in sends a mail, an inbox only gets a handful per hour
+pub const SIGN_IN_RATE_LIMITS: &[RateLimitPolicy] = &[
+    RateLimitPolicy {
+        name: "sign_in_ip",
+        key: RateLimitKey::Ip,
+        capacity: 20,
+        period: 60 * 60,
+    },
+    RateLimitPolicy {
+        name: "sign_in_email",
+        key: RateLimitKey::Email,
+        capacity: 5,
+        period: 60 * 60,
+    },
+];
+
+// a six digit code falls to guessing without a cap per account
+pub const TWO_FACTOR_RATE_LIMITS: &[RateLimitPolicy] = &[RateLimitPolicy {
+    name: "two_factor_user",
+    key: RateLimitKey::User,
+    capacity: 10,
+    period: 60 * 15,
+}];
+
+pub const AUTH_RATE_LIMITS: &[RateLimitPolicy] = &[
+    RateLimitPolicy {
+        name: "auth_ip",
+        key: RateLimitKey::Ip,
+        capacity: 30,
+        period: 60 * 15,
+    },
+    RateLimitPolicy {
+        name: "auth_email",
+        key: RateLimitKey::Email,
+        capacity: 10,
+        period: 60 * 15,
+    },
+];
+
+// seconds a download link handed to a buyer stays valid
+pub const DOWNLOAD_EXPIRY: i64 = 60 * 60 * 24 * 7;
+
+// times a buyer can fetch a digital file through the same link
+pub const DOWNLOAD_LIMIT: i32 = 5;
 
 pub struct SettingConstant<'a> {
     pub setup: &'a str,
+    pub base_currency: &'a str,
+    pub default_locale: &'a str,
+    pub stock_alert_recipients: &'a str,
+    pub require_admin_two_factor: &'a str,
 }
 
 pub const SETTINGS: SettingConstant = SettingConstant {
     setup: "setup",
+    base_currency: "base_currency",
+    default_locale: "default_locale",
+    stock_alert_recipients: "stock_alert_recipients",
+    require_admin_two_factor: "require_admin_two_factor",
     //
 };
@@ -1,14 +1,18 @@
 use dotenvy::dotenv;
-use std::env;
+use std::{env, net::IpAddr, str::FromStr};
+
+use crate::utils::constants::APP_SECRET_MIN_LENGTH;
 
 #[derive(Clone)]
 pub struct Env {
     // APP
     pub app_url: String,
     pub app_secret: String,
+    pub app_previous_secrets: Vec<String>,
 
     // CLIENT
     pub client_url: String,
+    pub trusted_proxies: Vec<IpAddr>,
 
     // RESEND
     pub resend_token: String,
@@ -16,22 +20,115 @@ pub struct Env {
 
     // DATABASE
     pub database_url: String,
+
+    // STORAGE
+    pub storage_driver: String,
+    pub s3_bucket: Option<String>,
+    pub s3_region: Option<String>,
+    pub s3_endpoint: Option<String>,
+    pub s3_access_key: Option<String>,
+    pub s3_secret_key: Option<String>,
+    pub s3_public_url: Option<String>,
+    pub s3_private_bucket: Option<String>,
+
+    // UPLOADS
+    pub upload_max_file_size: usize,
+    pub upload_max_request_size: usize,
+
+    // CHECKOUT
+    pub reservation_ttl: i64,
+
+    // RATE LIMIT
+    pub rate_limit_driver: String,
+
+    // OAUTH
+    pub oauth_provider: String,
+    pub oauth_client_id: Option<String>,
+    pub oauth_client_secret: Option<String>,
+    pub oauth_authorize_url: Option<String>,
+    pub oauth_token_url: Option<String>,
+    pub oauth_userinfo_url: Option<String>,
+    pub oauth_redirect_url: Option<String>,
+    pub oauth_scopes: String,
 }
 
 fn dot_env(name: &str) -> String {
     env::var(name).expect(&format!("{} is missing", name))
 }
 
+fn dot_env_optional(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+fn dot_env_parse<T: FromStr>(name: &str, default: T) -> T {
+    match dot_env_optional(name) {
+        Some(value) => value
+            .parse()
+            .unwrap_or_else(|_| panic!("{} is invalid", name)),
+        None => default,
+    }
+}
+
+fn dot_env_list(name: &str) -> Vec<String> {
+    match dot_env_optional(name) {
+        Some(value) => value
+            .split(',')
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+// every signature and token hash is keyed with it, a short one can be guessed
+fn dot_env_secret(name: &str) -> String {
+    let secret = dot_env(name);
+
+    if secret.len() < APP_SECRET_MIN_LENGTH {
+        panic!("{} must be at least {} bytes", name, APP_SECRET_MIN_LENGTH);
+    }
+
+    secret
+}
+
+impl Env {
+    // the current secret first, then the retired ones still accepted
+    pub fn secrets(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.app_secret).chain(self.app_previous_secrets.iter())
+    }
+}
+
 pub fn init() -> Env {
     dotenv().expect(".env file not found");
 
+    let app_previous_secrets = dot_env_list("APP_PREVIOUS_SECRETS");
+
+    if app_previous_secrets
+        .iter()
+        .any(|secret| secret.len() < APP_SECRET_MIN_LENGTH)
+    {
+        panic!(
+            "APP_PREVIOUS_SECRETS must each be at least {} bytes",
+            APP_SECRET_MIN_LENGTH
+        );
+    }
+
     Env {
         // APP
         app_url: dot_env("APP_URL"),
-        app_secret: dot_env("APP_SECRET"),
+        app_secret: dot_env_secret("APP_SECRET"),
+        app_previous_secrets,
 
         // CLIENT
         client_url: dot_env("CLIENT_URL"),
+        trusted_proxies: dot_env_list("TRUSTED_PROXIES")
+            .iter()
+            .map(|proxy| {
+                proxy
+                    .parse()
+                    .unwrap_or_else(|_| panic!("TRUSTED_PROXIES is invalid"))
+            })
+            .collect(),
 
         // RESEND
         resend_token: dot_env("RESEND_TOKEN"),
@@ -39,5 +136,36 @@ pub fn init() -> Env {
 
         // DATABASE
         database_url: dot_env("DATABASE_URL"),
+
+        // STORAGE
+        storage_driver: dot_env_optional("STORAGE_DRIVER").unwrap_or("local".to_string()),
+        s3_bucket: dot_env_optional("S3_BUCKET"),
+        s3_region: dot_env_optional("S3_REGION"),
+        s3_endpoint: dot_env_optional("S3_ENDPOINT"),
+        s3_access_key: dot_env_optional("S3_ACCESS_KEY"),
+        s3_secret_key: dot_env_optional("S3_SECRET_KEY"),
+        s3_public_url: dot_env_optional("S3_PUBLIC_URL"),
+        s3_private_bucket: dot_env_optional("S3_PRIVATE_BUCKET"),
+
+        // UPLOADS
+        upload_max_file_size: dot_env_parse("UPLOAD_MAX_FILE_SIZE", 2 * 1024 * 1024),
+        upload_max_request_size: dot_env_parse("UPLOAD_MAX_REQUEST_SIZE", 20 * 1024 * 1024),
+
+        // CHECKOUT
+        reservation_ttl: dot_env_parse("RESERVATION_TTL", 15 * 60),
+
+        // RATE LIMIT
+        rate_limit_driver: dot_env_optional("RATE_LIMIT_DRIVER").unwrap_or("memory".to_string()),
+
+        // OAUTH
+        oauth_provider: dot_env_optional("OAUTH_PROVIDER").unwrap_or("oidc".to_string()),
+        oauth_client_id: dot_env_optional("OAUTH_CLIENT_ID"),
+        oauth_client_secret: dot_env_optional("OAUTH_CLIENT_SECRET"),
+        oauth_authorize_url: dot_env_optional("OAUTH_AUTHORIZE_URL"),
+        oauth_token_url: dot_env_optional("OAUTH_TOKEN_URL"),
+        oauth_userinfo_url: dot_env_optional("OAUTH_USERINFO_URL"),
+        oauth_redirect_url: dot_env_optional("OAUTH_REDIRECT_URL"),
+        oauth_scopes: dot_env_optional("OAUTH_SCOPES")
+            .unwrap_or("openid email profile".to_string()),
     }
 }
@@ -1,4 +1,12 @@
+pub mod api_key;
+pub mod client;
 pub mod constants;
 pub mod db;
 pub mod env;
 pub mod mailer;
+pub mod oauth;
+pub mod password;
+pub mod rate_limit;
+pub mod signing;
+pub mod storage;
+pub mod totp;
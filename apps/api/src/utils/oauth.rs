@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::{rngs::OsRng, RngCore};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use url::Url;
+
+use super::{constants::OAUTH_REQUEST_TIMEOUT, env::Env};
+use crate::models::identity::OAuthUser;
+
+#[derive(Debug, Error)]
+pub enum OAuthError {
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+
+    #[error("provider answered {0}")]
+    Status(reqwest::StatusCode),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Url(#[from] url::ParseError),
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+#[derive(Clone)]
+pub struct OAuthProvider {
+    pub name: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub authorize_url: String,
+    pub token_url: String,
+    pub userinfo_url: String,
+    pub redirect_url: String,
+    pub scopes: String,
+}
+
+impl OAuthProvider {
+    // none until a client id and every provider url are configured
+    pub fn from_env(env: &Env) -> Option<Self> {
+        Some(OAuthProvider {
+            name: env.oauth_provider.clone(),
+            client_id: env.oauth_client_id.clone()?,
+            client_secret: env.oauth_client_secret.clone(),
+            authorize_url: env.oauth_authorize_url.clone()?,
+            token_url: env.oauth_token_url.clone()?,
+            userinfo_url: env.oauth_userinfo_url.clone()?,
+            redirect_url: env.oauth_redirect_url.clone()?,
+            scopes: env.oauth_scopes.clone(),
+        })
+    }
+
+    pub fn authorize(&self, state: &str, challenge: &str) -> Result<String, OAuthError> {
+        let mut url = Url::parse(&self.authorize_url)?;
+
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.client_id)
+            .append_pair("redirect_uri", &self.redirect_url)
+            .append_pair("scope", &self.scopes)
+            .append_pair("state", state)
+            .append_pair("code_challenge", challenge)
+            .append_pair("code_challenge_method", "S256");
+
+        Ok(url.to_string())
+    }
+
+    // trades the authorization code for an access token, the verifier proves we started the flow
+    pub async fn exchange(&self, code: &str, verifier: &str) -> Result<String, OAuthError> {
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &self.redirect_url),
+            ("client_id", &self.client_id),
+            ("code_verifier", verifier),
+        ];
+
+        if let Some(secret) = &self.client_secret {
+            form.push(("client_secret", secret));
+        }
+
+        let response = client()?
+            .post(&self.token_url)
+            .header(reqwest::header::ACCEPT, "application/json")
+            .form(&form)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(OAuthError::Status(response.status()));
+        }
+
+        let token: TokenResponse = serde_json::from_slice(&response.bytes().await?)?;
+
+        Ok(token.access_token)
+    }
+
+    pub async fn user(&self, access_token: &str) -> Result<OAuthUser, OAuthError> {
+        let response = client()?
+            .get(&self.userinfo_url)
+            .bearer_auth(access_token)
+            .header(reqwest::header::ACCEPT, "application/json")
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(OAuthError::Status(response.status()));
+        }
+
+        Ok(serde_json::from_slice(&response.bytes().await?)?)
+    }
+}
+
+fn client() -> Result<reqwest::Client, reqwest::Error> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(OAUTH_REQUEST_TIMEOUT))
+        .build()
+}
+
+// 32 random bytes as the verifier, its sha256 as the s256 challenge
+pub fn pkce() -> (String, String) {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+
+    let verifier = URL_SAFE_NO_PAD.encode(bytes);
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+
+    (verifier, challenge)
+}
@@ -0,0 +1,57 @@
+use std::sync::OnceLock;
+
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PasswordError {
+    #[error("{0}")]
+    Hash(argon2::password_hash::Error),
+
+    #[error(transparent)]
+    Join(#[from] tokio::task::JoinError),
+}
+
+impl From<argon2::password_hash::Error> for PasswordError {
+    fn from(err: argon2::password_hash::Error) -> Self {
+        PasswordError::Hash(err)
+    }
+}
+
+fn hash_blocking(password: &str) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+
+    Ok(Argon2::default()
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string())
+}
+
+// hashing takes tens of milliseconds on purpose, so it stays off the async workers
+pub async fn hash(password: String) -> Result<String, PasswordError> {
+    Ok(tokio::task::spawn_blocking(move || hash_blocking(&password)).await??)
+}
+
+pub async fn verify(password: String, hash: String) -> Result<bool, PasswordError> {
+    tokio::task::spawn_blocking(move || {
+        let hash = PasswordHash::new(&hash)?;
+
+        match Argon2::default().verify_password(password.as_bytes(), &hash) {
+            Ok(()) => Ok(true),
+            Err(argon2::password_hash::Error::Password) => Ok(false),
+            Err(err) => Err(PasswordError::Hash(err)),
+        }
+    })
+    .await?
+}
+
+// checked against when the user has no password, so a miss takes as long as a wrong password
+pub fn dummy_hash() -> String {
+    static DUMMY: OnceLock<String> = OnceLock::new();
+
+    DUMMY
+        .get_or_init(|| hash_blocking("dummy password").expect("argon2 hashes a constant"))
+        .clone()
+}
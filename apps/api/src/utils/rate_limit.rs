@@ -0,0 +1,142 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use axum::async_trait;
+use chrono::NaiveDateTime;
+use thiserror::Error;
+
+use super::{db::DB, env::Env};
+use crate::{models::rate_limit::RateLimitBucket, services};
+
+#[derive(Debug, Error)]
+pub enum RateLimitError {
+    #[error(transparent)]
+    Sqlx(#[from] sqlx::Error),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum RateLimitKey {
+    Ip,
+    Email,
+    // the signed in user, only on routes behind the auth middleware
+    User,
+}
+
+// a bucket of capacity tokens per key, refilled evenly over period seconds
+#[derive(Clone, Copy)]
+pub struct RateLimitPolicy {
+    pub name: &'static str,
+    pub key: RateLimitKey,
+    pub capacity: u32,
+    pub period: i64,
+}
+
+#[async_trait]
+pub trait RateLimit: RateLimitClone + Send + Sync {
+    // none when the request may go through, otherwise the seconds to wait
+    async fn take(
+        &self,
+        key: &str,
+        policy: &RateLimitPolicy,
+        now: NaiveDateTime,
+    ) -> Result<Option<i64>, RateLimitError>;
+
+    async fn sweep(&self, before: NaiveDateTime) -> Result<u64, RateLimitError>;
+}
+
+pub trait RateLimitClone {
+    fn clone_box(&self) -> Box<dyn RateLimit>;
+}
+
+impl<T> RateLimitClone for T
+where
+    T: 'static + RateLimit + Clone,
+{
+    fn clone_box(&self) -> Box<dyn RateLimit> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn RateLimit> {
+    fn clone(&self) -> Box<dyn RateLimit> {
+        self.clone_box()
+    }
+}
+
+// only sees the requests of this process
+#[derive(Clone, Default)]
+pub struct MemoryRateLimit {
+    buckets: Arc<Mutex<HashMap<String, RateLimitBucket>>>,
+}
+
+#[async_trait]
+impl RateLimit for MemoryRateLimit {
+    async fn take(
+        &self,
+        key: &str,
+        policy: &RateLimitPolicy,
+        now: NaiveDateTime,
+    ) -> Result<Option<i64>, RateLimitError> {
+        let mut buckets = self.buckets.lock().unwrap();
+
+        let bucket = buckets
+            .entry(key.to_string())
+            .or_insert_with(|| RateLimitBucket::full(key, policy.capacity, now));
+
+        Ok(bucket.take(policy.capacity, policy.period, now))
+    }
+
+    async fn sweep(&self, before: NaiveDateTime) -> Result<u64, RateLimitError> {
+        let mut buckets = self.buckets.lock().unwrap();
+        let count = buckets.len();
+
+        buckets.retain(|_, bucket| bucket.updated_at >= before);
+
+        Ok((count - buckets.len()) as u64)
+    }
+}
+
+// shared by every instance pointing at the same database
+#[derive(Clone)]
+pub struct PostgresRateLimit {
+    db: DB,
+}
+
+impl PostgresRateLimit {
+    pub fn new(db: DB) -> Self {
+        PostgresRateLimit { db }
+    }
+}
+
+#[async_trait]
+impl RateLimit for PostgresRateLimit {
+    async fn take(
+        &self,
+        key: &str,
+        policy: &RateLimitPolicy,
+        now: NaiveDateTime,
+    ) -> Result<Option<i64>, RateLimitError> {
+        let mut connection = self.db.acquire().await?;
+
+        Ok(
+            services::rate_limit::take(key, policy.capacity, policy.period, &now, &mut connection)
+                .await?,
+        )
+    }
+
+    async fn sweep(&self, before: NaiveDateTime) -> Result<u64, RateLimitError> {
+        let mut connection = self.db.acquire().await?;
+
+        Ok(services::rate_limit::sweep(&before, &mut connection).await?)
+    }
+}
+
+pub fn init(env: &Env, db: &DB) -> Box<dyn RateLimit> {
+    match env.rate_limit_driver.as_str() {
+        "postgres" => Box::new(PostgresRateLimit::new(db.clone())),
+        "memory" => Box::new(MemoryRateLimit::default()),
+        driver => panic!("Unknown rate limit driver {}", driver),
+    }
+}
@@ -0,0 +1,28 @@
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn mac(secret: &str, payload: &str) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("hmac accepts keys of any size");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+// unkeyed, for random tokens too long to brute force, so rotating the secret doesn't void them
+pub fn digest(payload: &str) -> String {
+    hex::encode(Sha256::digest(payload.as_bytes()))
+}
+
+pub fn sign(secret: &str, payload: &str) -> String {
+    hex::encode(mac(secret, payload).finalize().into_bytes())
+}
+
+// compares in constant time so a signature can't be guessed byte by byte
+pub fn verify(secret: &str, payload: &str, signature: &str) -> bool {
+    match hex::decode(signature) {
+        Ok(signature) => mac(secret, payload).verify_slice(&signature).is_ok(),
+        Err(_) => false,
+    }
+}
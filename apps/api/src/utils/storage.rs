@@ -0,0 +1,291 @@
+use std::path::{Component, Path, PathBuf};
+
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use s3::{creds::Credentials, error::S3Error, Bucket, Region};
+use thiserror::Error;
+use tokio::fs;
+
+use super::{
+    constants::{PRIVATE_FOLDER_NAME, PUBLIC_FOLDER_NAME, SIGNED_URL_EXPIRY},
+    env::Env,
+};
+
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("invalid storage key: {0}")]
+    InvalidKey(String),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    S3(#[from] S3Error),
+}
+
+pub struct StorageObject {
+    pub key: String,
+    pub modified_at: DateTime<Utc>,
+}
+
+#[async_trait]
+pub trait Storage: StorageClone + Send + Sync {
+    async fn put(&self, key: &str, bytes: &[u8], content_type: &str) -> Result<(), StorageError>;
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, StorageError>;
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError>;
+
+    async fn list(&self) -> Result<Vec<StorageObject>, StorageError>;
+
+    // public or signed url the client can fetch the object from
+    async fn url(&self, key: &str) -> Result<String, StorageError>;
+
+    // directory the objects live in when they can be served straight from disk
+    fn local_root(&self) -> Option<&Path>;
+}
+
+pub trait StorageClone {
+    fn clone_box(&self) -> Box<dyn Storage>;
+}
+
+impl<T> StorageClone for T
+where
+    T: 'static + Storage + Clone,
+{
+    fn clone_box(&self) -> Box<dyn Storage> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn Storage> {
+    fn clone(&self) -> Box<dyn Storage> {
+        self.clone_box()
+    }
+}
+
+fn validate_key(key: &str) -> Result<(), StorageError> {
+    let valid = !key.is_empty()
+        && Path::new(key)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+
+    if !valid {
+        return Err(StorageError::InvalidKey(key.to_string()));
+    }
+
+    Ok(())
+}
+
+#[derive(Clone)]
+pub struct LocalStorage {
+    pub root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        LocalStorage { root: root.into() }
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, bytes: &[u8], _: &str) -> Result<(), StorageError> {
+        validate_key(key)?;
+        fs::create_dir_all(&self.root).await?;
+        fs::write(self.root.join(key), bytes).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, StorageError> {
+        validate_key(key)?;
+        Ok(fs::read(self.root.join(key)).await?)
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        validate_key(key)?;
+        fs::remove_file(self.root.join(key)).await?;
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<StorageObject>, StorageError> {
+        let mut objects = Vec::new();
+
+        let mut entries = match fs::read_dir(&self.root).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(objects),
+            Err(err) => return Err(err.into()),
+        };
+
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = entry.metadata().await?;
+
+            if !metadata.is_file() {
+                continue;
+            }
+
+            objects.push(StorageObject {
+                key: entry.file_name().to_string_lossy().to_string(),
+                modified_at: DateTime::from(metadata.modified()?),
+            });
+        }
+
+        Ok(objects)
+    }
+
+    async fn url(&self, key: &str) -> Result<String, StorageError> {
+        validate_key(key)?;
+        Ok(format!("/{}/{}", PUBLIC_FOLDER_NAME, key))
+    }
+
+    fn local_root(&self) -> Option<&Path> {
+        Some(&self.root)
+    }
+}
+
+#[derive(Clone)]
+pub struct S3Storage {
+    pub bucket: Box<Bucket>,
+    pub public_url: Option<String>,
+}
+
+impl S3Storage {
+    pub fn new(
+        bucket: &str,
+        region: &str,
+        endpoint: &str,
+        access_key: &str,
+        secret_key: &str,
+        public_url: Option<String>,
+    ) -> Result<Self, StorageError> {
+        let region = Region::Custom {
+            region: region.to_string(),
+            endpoint: endpoint.to_string(),
+        };
+
+        let credentials = Credentials {
+            access_key: Some(access_key.to_string()),
+            secret_key: Some(secret_key.to_string()),
+            security_token: None,
+            session_token: None,
+            expiration: None,
+        };
+
+        // path style keeps minio and other s3 compatible servers working
+        let bucket = Bucket::new(bucket, region, credentials)?.with_path_style();
+
+        Ok(S3Storage { bucket, public_url })
+    }
+}
+
+#[async_trait]
+impl Storage for S3Storage {
+    async fn put(&self, key: &str, bytes: &[u8], content_type: &str) -> Result<(), StorageError> {
+        validate_key(key)?;
+        self.bucket
+            .put_object_with_content_type(key, bytes, content_type)
+            .await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, StorageError> {
+        validate_key(key)?;
+        let response = self.bucket.get_object(key).await?;
+        Ok(response.to_vec())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        validate_key(key)?;
+        self.bucket.delete_object(key).await?;
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<StorageObject>, StorageError> {
+        let pages = self.bucket.list(String::new(), None).await?;
+
+        Ok(pages
+            .into_iter()
+            .flat_map(|page| page.contents)
+            .map(|object| StorageObject {
+                modified_at: DateTime::parse_from_rfc3339(&object.last_modified)
+                    .map(|date| date.with_timezone(&Utc))
+                    .unwrap_or_else(|_| Utc::now()),
+                key: object.key,
+            })
+            .collect())
+    }
+
+    async fn url(&self, key: &str) -> Result<String, StorageError> {
+        validate_key(key)?;
+
+        if let Some(public_url) = &self.public_url {
+            return Ok(format!("{}/{}", public_url.trim_end_matches('/'), key));
+        }
+
+        Ok(self
+            .bucket
+            .presign_get(key, SIGNED_URL_EXPIRY, None)
+            .await?)
+    }
+
+    fn local_root(&self) -> Option<&Path> {
+        None
+    }
+}
+
+pub fn init(env: &Env) -> Box<dyn Storage> {
+    match env.storage_driver.as_str() {
+        "s3" => {
+            let storage = S3Storage::new(
+                env.s3_bucket.as_deref().expect("S3_BUCKET is missing"),
+                env.s3_region.as_deref().expect("S3_REGION is missing"),
+                env.s3_endpoint.as_deref().expect("S3_ENDPOINT is missing"),
+                env.s3_access_key
+                    .as_deref()
+                    .expect("S3_ACCESS_KEY is missing"),
+                env.s3_secret_key
+                    .as_deref()
+                    .expect("S3_SECRET_KEY is missing"),
+                env.s3_public_url.clone(),
+            );
+
+            Box::new(storage.expect("Invalid S3 configuration"))
+        }
+        "local" => Box::new(LocalStorage::new(PUBLIC_FOLDER_NAME)),
+        driver => panic!("Unknown storage driver {}", driver),
+    }
+}
+
+// files that are only handed out through signed download links, never served publicly
+pub fn init_private(env: &Env) -> Box<dyn Storage> {
+    match env.storage_driver.as_str() {
+        "s3" => {
+            let bucket = env
+                .s3_private_bucket
+                .as_deref()
+                .expect("S3_PRIVATE_BUCKET is missing");
+
+            // the public bucket is served as is and swept for orphan images
+            if env.s3_bucket.as_deref() == Some(bucket) {
+                panic!("S3_PRIVATE_BUCKET must differ from S3_BUCKET");
+            }
+
+            let storage = S3Storage::new(
+                bucket,
+                env.s3_region.as_deref().expect("S3_REGION is missing"),
+                env.s3_endpoint.as_deref().expect("S3_ENDPOINT is missing"),
+                env.s3_access_key
+                    .as_deref()
+                    .expect("S3_ACCESS_KEY is missing"),
+                env.s3_secret_key
+                    .as_deref()
+                    .expect("S3_SECRET_KEY is missing"),
+                None,
+            );
+
+            Box::new(storage.expect("Invalid S3 configuration"))
+        }
+        "local" => Box::new(LocalStorage::new(PRIVATE_FOLDER_NAME)),
+        driver => panic!("Unknown storage driver {}", driver),
+    }
+}
@@ -0,0 +1,134 @@
+use aes_gcm::{
+    aead::{generic_array::GenericArray, Aead, AeadCore},
+    Aes256Gcm,
+};
+use hmac::{Hmac, Mac};
+use rand::{rngs::OsRng, RngCore};
+use sha1::Sha1;
+use sha2::Sha256;
+use url::Url;
+
+use super::constants::{TOTP_DIGITS, TOTP_ISSUER, TOTP_PERIOD, TOTP_SKEW};
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const NONCE_SIZE: usize = 12;
+
+// 160 bits, the key size rfc 4226 recommends for hmac-sha1
+pub fn secret() -> Vec<u8> {
+    let mut secret = vec![0u8; 20];
+    OsRng.fill_bytes(&mut secret);
+    secret
+}
+
+// a key of its own derived from the app secret, which never encrypts anything itself
+fn cipher(app_secret: &str) -> Aes256Gcm {
+    let mut mac = Hmac::<Sha256>::new_from_slice(app_secret.as_bytes())
+        .expect("hmac accepts keys of any size");
+    mac.update(b"totp_factors.secret");
+
+    // qualified, with KeyInit in scope hmac's new_from_slice would be ambiguous
+    <Aes256Gcm as aes_gcm::KeyInit>::new(&mac.finalize().into_bytes())
+}
+
+// what gets stored, a random nonce followed by the encrypted secret
+pub fn seal(app_secret: &str, secret: &[u8]) -> Vec<u8> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let sealed = cipher(app_secret)
+        .encrypt(&nonce, secret)
+        .expect("aes-gcm encrypts secrets of any size");
+
+    [nonce.as_slice(), &sealed].concat()
+}
+
+// secrets sealed before the app secret was rotated still open with a retired one
+pub fn open<'a>(app_secrets: impl Iterator<Item = &'a String>, sealed: &[u8]) -> Option<Vec<u8>> {
+    if sealed.len() < NONCE_SIZE {
+        return None;
+    }
+
+    let (nonce, sealed) = sealed.split_at(NONCE_SIZE);
+
+    app_secrets.into_iter().find_map(|app_secret| {
+        cipher(app_secret)
+            .decrypt(GenericArray::from_slice(nonce), sealed)
+            .ok()
+    })
+}
+
+// unpadded rfc 4648 base32, the form authenticator apps take secrets in
+pub fn base32(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for byte in bytes {
+        buffer = (buffer << 8) | *byte as u32;
+        bits += 8;
+
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(BASE32_ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+
+    if bits > 0 {
+        encoded.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+
+    encoded
+}
+
+pub fn step(timestamp: i64) -> i64 {
+    timestamp.div_euclid(TOTP_PERIOD)
+}
+
+pub fn code(secret: &[u8], step: i64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("hmac accepts keys of any size");
+    mac.update(&step.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    // dynamic truncation from rfc 4226
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+
+    format!(
+        "{:0width$}",
+        binary % 10u32.pow(TOTP_DIGITS),
+        width = TOTP_DIGITS as usize
+    )
+}
+
+// the step the code belongs to, so the caller can refuse it a second time
+pub fn verify(secret: &[u8], code: &str, timestamp: i64) -> Option<i64> {
+    let current = step(timestamp);
+
+    (current - TOTP_SKEW..=current + TOTP_SKEW).find(|step| self::code(secret, *step) == code)
+}
+
+pub fn uri(secret: &[u8], account: &str) -> String {
+    let mut url = Url::parse("otpauth://totp/").expect("otpauth is a valid url");
+
+    url.set_path(&format!("/{}:{}", TOTP_ISSUER, account));
+    url.query_pairs_mut()
+        .append_pair("secret", &base32(secret))
+        .append_pair("issuer", TOTP_ISSUER)
+        .append_pair("algorithm", "SHA1")
+        .append_pair("digits", &TOTP_DIGITS.to_string())
+        .append_pair("period", &TOTP_PERIOD.to_string());
+
+    url.to_string()
+}
+
+// ten hex characters in two groups, easy to copy down on paper
+pub fn recovery_code() -> String {
+    let mut bytes = [0u8; 5];
+    OsRng.fill_bytes(&mut bytes);
+
+    let code = hex::encode(bytes);
+    format!("{}-{}", &code[..5], &code[5..])
+}
@@ -0,0 +1,26 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use super::role::validate_permissions;
+
+#[derive(Deserialize, Validate)]
+pub struct CreateApiKeySchema {
+    #[validate(length(min = 1))]
+    pub name: String,
+    #[serde(default)]
+    #[validate(custom(function = "validate_permissions"))]
+    pub scopes: Vec<String>,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize)]
+pub struct StoreApiKeySchema {
+    pub name: String,
+    pub prefix: String,
+    pub key_hash: String,
+    pub scopes: Vec<String>,
+    pub user_id: Uuid,
+    pub expires_at: Option<NaiveDateTime>,
+}
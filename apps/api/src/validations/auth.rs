@@ -1,3 +1,4 @@
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use validator::Validate;
@@ -12,6 +13,9 @@ pub struct SignInSchema {
 pub struct StoreSessionSchema {
     pub session: Uuid,
     pub user_id: Uuid,
+    pub expires_at: NaiveDateTime,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Deserialize, Serialize, Validate)]
+pub struct BundleItemSchema {
+    pub variant_id: Uuid,
+    #[validate(range(min = 1))]
+    pub quantity: i32,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct StoreBundleSchema {
+    #[validate(length(min = 1))]
+    pub name: String,
+    pub description: Option<String>,
+    #[validate(range(min = 1.0))]
+    pub price: f32,
+    #[validate(length(min = 1), nested)]
+    pub items: Vec<BundleItemSchema>,
+}
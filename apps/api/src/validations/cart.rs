@@ -9,7 +9,9 @@ pub struct StoreCartSchema {
 
 #[derive(Deserialize, Validate)]
 pub struct StoreCartItemSchema {
-    pub variant_id: Uuid,
+    // a line holds either a variant or a bundle
+    pub variant_id: Option<Uuid>,
+    pub bundle_id: Option<Uuid>,
     #[validate(range(min = 1))]
     pub quantity: i32,
 }
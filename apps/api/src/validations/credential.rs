@@ -0,0 +1,33 @@
+use serde::Deserialize;
+use validator::Validate;
+
+// argon2 runs over the whole input, the upper bound keeps hashing cheap to refuse
+#[derive(Deserialize, Validate)]
+pub struct PasswordSignInSchema {
+    #[validate(email)]
+    pub email: String,
+    #[validate(length(min = 1, max = 128))]
+    pub password: String,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct SetPasswordSchema {
+    #[validate(length(min = 1, max = 128))]
+    pub current_password: Option<String>,
+    #[validate(length(min = 8, max = 128))]
+    pub password: String,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct ForgotPasswordSchema {
+    #[validate(email)]
+    pub email: String,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct ResetPasswordSchema {
+    #[validate(length(min = 1, max = 64))]
+    pub token: String,
+    #[validate(length(min = 8, max = 128))]
+    pub password: String,
+}
@@ -0,0 +1,76 @@
+use axum::http::HeaderMap;
+use serde::Deserialize;
+use validator::{Validate, ValidationError};
+
+use crate::utils::constants::{CURRENCY_HEADER, ROUNDING_MODES};
+
+fn default_decimals() -> i16 {
+    2
+}
+
+fn default_rounding() -> String {
+    ROUNDING_MODES.nearest.to_string()
+}
+
+#[derive(Deserialize, Validate)]
+pub struct StoreExchangeRateSchema {
+    #[validate(custom(function = "validate_currency"))]
+    pub currency: String,
+    #[validate(range(exclusive_min = 0.0))]
+    pub rate: f64,
+    #[serde(default = "default_decimals")]
+    #[validate(range(min = 0, max = 4))]
+    pub decimals: i16,
+    #[serde(default = "default_rounding")]
+    #[validate(custom(function = "validate_rounding"))]
+    pub rounding: String,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct SetBaseCurrencySchema {
+    #[validate(custom(function = "validate_currency"))]
+    pub currency: String,
+}
+
+#[derive(Deserialize)]
+pub struct CurrencyParams {
+    pub currency: Option<String>,
+}
+
+impl CurrencyParams {
+    // the query wins over the header
+    pub fn preferred(&self, headers: &HeaderMap) -> Option<String> {
+        self.currency
+            .clone()
+            .or_else(|| {
+                headers
+                    .get(CURRENCY_HEADER)
+                    .and_then(|currency| currency.to_str().ok())
+                    .map(|currency| currency.to_string())
+            })
+            .map(|currency| currency.trim().to_uppercase())
+    }
+}
+
+// iso 4217 codes, three upper case letters
+fn validate_currency(currency: &str) -> Result<(), ValidationError> {
+    if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err(ValidationError::new("currency"));
+    }
+
+    Ok(())
+}
+
+fn validate_rounding(rounding: &str) -> Result<(), ValidationError> {
+    let modes = [
+        ROUNDING_MODES.nearest,
+        ROUNDING_MODES.up,
+        ROUNDING_MODES.down,
+    ];
+
+    if !modes.contains(&rounding) {
+        return Err(ValidationError::new("rounding"));
+    }
+
+    Ok(())
+}
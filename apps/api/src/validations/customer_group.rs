@@ -0,0 +1,14 @@
+use serde::Deserialize;
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Deserialize, Validate)]
+pub struct StoreCustomerGroupSchema {
+    #[validate(length(min = 1))]
+    pub name: String,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct AssignCustomerGroupSchema {
+    pub customer_group_id: Option<Uuid>,
+}
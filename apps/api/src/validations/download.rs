@@ -0,0 +1,16 @@
+use serde::Deserialize;
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Deserialize, Validate)]
+pub struct StoreDownloadsSchema {
+    pub user_id: Uuid,
+    #[validate(length(min = 1))]
+    pub variant_ids: Vec<Uuid>,
+}
+
+#[derive(Deserialize)]
+pub struct DownloadParams {
+    pub expires: i64,
+    pub signature: String,
+}
@@ -1,4 +1,20 @@
+use serde::Deserialize;
+use url::Url;
+use validator::Validate;
+
 pub struct StoreImageSchema {
     pub name: String,
     pub src: String,
 }
+
+#[derive(Deserialize, Validate)]
+pub struct UpdateImageSchema {
+    #[validate(length(min = 1))]
+    pub name: String,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct ImportImagesSchema {
+    #[validate(length(min = 1, max = 20))]
+    pub urls: Vec<Url>,
+}
@@ -1,10 +1,20 @@
 use chrono::NaiveDateTime;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use validator::Validate;
 
 #[derive(Serialize)]
 pub struct StoreMagicTokenSchema {
-    pub token: Uuid,
+    pub token_hash: String,
+    pub otp_hash: String,
     pub user_id: Uuid,
     pub expires_at: NaiveDateTime,
 }
+
+#[derive(Deserialize, Validate)]
+pub struct VerifyOtpSchema {
+    #[validate(email)]
+    pub email: String,
+    #[validate(length(min = 1, max = 16))]
+    pub code: String,
+}
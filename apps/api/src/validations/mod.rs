@@ -8,17 +8,32 @@ use serde::de::DeserializeOwned;
 use thiserror::Error;
 use validator::Validate;
 
+pub mod api_key;
 pub mod auth;
+pub mod bundle;
 pub mod cart;
 pub mod category;
+pub mod credential;
+pub mod currency;
+pub mod customer_group;
+pub mod download;
+pub mod identity;
 pub mod image;
 pub mod magic_tokens;
+pub mod price_list;
 pub mod product;
+pub mod product_link;
+pub mod review;
 pub mod role;
+pub mod sale;
 pub mod settings;
+pub mod stock;
+pub mod translation;
+pub mod two_factor;
 pub mod unit;
 pub mod user;
 pub mod warehouse;
+pub mod wishlist;
 
 #[derive(Default, Clone, Debug, Copy)]
 pub struct ValidatedForm<T>(pub T);
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::utils::constants::PRICE_RULE_KINDS;
+
+fn default_min_quantity() -> i32 {
+    1
+}
+
+#[derive(Deserialize, Serialize, Validate)]
+#[validate(schema(function = "validate_rule"))]
+pub struct StorePriceRuleSchema {
+    pub kind: String,
+    #[validate(range(min = 0.0))]
+    pub value: f32,
+    #[serde(default = "default_min_quantity")]
+    #[validate(range(min = 1))]
+    pub min_quantity: i32,
+    pub variant_id: Option<Uuid>,
+    pub category_id: Option<Uuid>,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct StorePriceListSchema {
+    #[validate(length(min = 1))]
+    pub name: String,
+    // lists without a group apply to every customer, guests included
+    pub customer_group_id: Option<Uuid>,
+    #[validate(nested)]
+    pub rules: Vec<StorePriceRuleSchema>,
+}
+
+fn validate_rule(rule: &StorePriceRuleSchema) -> Result<(), ValidationError> {
+    if rule.variant_id.is_some() == rule.category_id.is_some() {
+        return Err(ValidationError::new("target"));
+    }
+
+    if rule.kind == PRICE_RULE_KINDS.percentage {
+        if rule.value > 100.0 {
+            return Err(ValidationError::new("value"));
+        }
+    } else if rule.kind != PRICE_RULE_KINDS.fixed {
+        return Err(ValidationError::new("kind"));
+    }
+
+    Ok(())
+}
@@ -16,12 +16,16 @@ pub struct Variant {
     pub options: Vec<VariantOption>,
     #[validate(range(min = 1.0))]
     pub price: f32,
+    #[validate(length(min = 1))]
+    pub sku: Option<String>,
 }
 
 #[derive(Deserialize, Validate)]
 pub struct StoreProductSchema {
     #[validate(length(min = 1))]
     pub name: String,
+    #[validate(length(min = 1))]
+    pub slug: Option<String>,
     pub description: Option<String>,
     pub unit_id: Uuid,
     pub category_id: Uuid,
@@ -30,3 +34,9 @@ pub struct StoreProductSchema {
     #[validate(length(min = 1))]
     pub images: Vec<Uuid>,
 }
+
+#[derive(Deserialize)]
+pub struct ImportParams {
+    #[serde(default)]
+    pub dry_run: bool,
+}
@@ -0,0 +1,14 @@
+use serde::Deserialize;
+use uuid::Uuid;
+use validator::Validate;
+
+// every list replaces the curated links of its kind, order is kept as the display order
+#[derive(Deserialize, Validate)]
+pub struct StoreProductLinksSchema {
+    #[serde(default)]
+    pub related: Vec<Uuid>,
+    #[serde(default)]
+    pub cross_sell: Vec<Uuid>,
+    #[serde(default)]
+    pub up_sell: Vec<Uuid>,
+}
@@ -0,0 +1,39 @@
+use serde::Deserialize;
+use validator::{Validate, ValidationError};
+
+use crate::utils::constants::REVIEW_STATUSES;
+
+#[derive(Deserialize, Validate)]
+pub struct StoreReviewSchema {
+    #[validate(range(min = 1, max = 5))]
+    pub rating: i16,
+    #[validate(length(min = 1))]
+    pub title: String,
+    #[validate(length(min = 1))]
+    pub body: String,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct UpdateReviewSchema {
+    #[validate(custom(function = "validate_status"))]
+    pub status: String,
+}
+
+#[derive(Deserialize)]
+pub struct ReviewFilter {
+    pub status: Option<String>,
+}
+
+fn validate_status(status: &str) -> Result<(), ValidationError> {
+    let statuses = [
+        REVIEW_STATUSES.pending,
+        REVIEW_STATUSES.approved,
+        REVIEW_STATUSES.rejected,
+    ];
+
+    if !statuses.contains(&status) {
+        return Err(ValidationError::new("status"));
+    }
+
+    Ok(())
+}
@@ -1,8 +1,31 @@
 use serde::{Deserialize, Serialize};
-use validator::Validate;
+use validator::{Validate, ValidationError};
+
+use crate::utils::constants::PERMISSION_NAMES;
 
 #[derive(Deserialize, Serialize, Validate)]
-pub struct StoreRoleSchema<'a> {
+pub struct StoreRoleSchema {
+    #[validate(length(min = 1))]
+    pub name: String,
+}
+
+// used for both creating and editing, permissions replace whatever the role had
+#[derive(Deserialize, Validate)]
+pub struct SaveRoleSchema {
     #[validate(length(min = 1))]
-    pub name: &'a str,
+    pub name: String,
+    #[serde(default)]
+    #[validate(custom(function = "validate_permissions"))]
+    pub permissions: Vec<String>,
+}
+
+pub fn validate_permissions(permissions: &[String]) -> Result<(), ValidationError> {
+    if permissions
+        .iter()
+        .any(|permission| !PERMISSION_NAMES.contains(&permission.as_str()))
+    {
+        return Err(ValidationError::new("permission"));
+    }
+
+    Ok(())
 }
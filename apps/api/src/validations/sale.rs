@@ -0,0 +1,40 @@
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::utils::constants::PRICE_RULE_KINDS;
+
+#[derive(Deserialize, Validate)]
+#[validate(schema(function = "validate_sale"))]
+pub struct StoreSaleSchema {
+    #[validate(length(min = 1))]
+    pub name: String,
+    pub kind: String,
+    #[validate(range(min = 0.0))]
+    pub value: f32,
+    pub variant_id: Option<Uuid>,
+    pub category_id: Option<Uuid>,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: NaiveDateTime,
+}
+
+fn validate_sale(sale: &StoreSaleSchema) -> Result<(), ValidationError> {
+    if sale.variant_id.is_some() == sale.category_id.is_some() {
+        return Err(ValidationError::new("target"));
+    }
+
+    if sale.ends_at <= sale.starts_at {
+        return Err(ValidationError::new("ends_at"));
+    }
+
+    if sale.kind == PRICE_RULE_KINDS.percentage {
+        if sale.value > 100.0 {
+            return Err(ValidationError::new("value"));
+        }
+    } else if sale.kind != PRICE_RULE_KINDS.fixed {
+        return Err(ValidationError::new("kind"));
+    }
+
+    Ok(())
+}
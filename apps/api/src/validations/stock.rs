@@ -0,0 +1,34 @@
+use serde::Deserialize;
+use uuid::Uuid;
+use validator::{Validate, ValidateEmail, ValidationError};
+
+#[derive(Deserialize, Validate)]
+pub struct StoreStockSchema {
+    pub variant_id: Uuid,
+    pub warehouse_id: Uuid,
+    #[validate(range(min = 0))]
+    pub quantity: i32,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct StoreThresholdSchema {
+    pub variant_id: Uuid,
+    pub warehouse_id: Uuid,
+    // none stops watching the stock
+    #[validate(range(min = 0))]
+    pub reorder_threshold: Option<i32>,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct StoreAlertRecipientsSchema {
+    #[validate(custom(function = "validate_emails"))]
+    pub emails: Vec<String>,
+}
+
+fn validate_emails(emails: &[String]) -> Result<(), ValidationError> {
+    if !emails.iter().all(|email| email.validate_email()) {
+        return Err(ValidationError::new("email"));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use axum::http::{header, HeaderMap};
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+#[derive(Deserialize, Serialize, Validate)]
+pub struct OptionTranslationSchema {
+    #[validate(length(min = 1))]
+    pub source: String,
+    #[validate(length(min = 1))]
+    pub name: String,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct StoreProductTranslationSchema {
+    #[validate(custom(function = "validate_locale"))]
+    pub locale: String,
+    #[validate(length(min = 1))]
+    pub name: String,
+    pub description: Option<String>,
+    // option keys and values, by their text in the default locale
+    #[serde(default)]
+    #[validate(nested)]
+    pub options: Vec<OptionTranslationSchema>,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct StoreTranslationSchema {
+    #[validate(custom(function = "validate_locale"))]
+    pub locale: String,
+    #[validate(length(min = 1))]
+    pub name: String,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct SetDefaultLocaleSchema {
+    #[validate(custom(function = "validate_locale"))]
+    pub locale: String,
+}
+
+#[derive(Deserialize)]
+pub struct LocaleParams {
+    pub locale: Option<String>,
+}
+
+impl LocaleParams {
+    // the query wins over accept-language, most preferred first
+    pub fn preferred(&self, headers: &HeaderMap) -> Vec<String> {
+        let mut tags: Vec<(String, f32)> = match &self.locale {
+            Some(locale) => vec![(locale.clone(), 1.0)],
+            None => headers
+                .get(header::ACCEPT_LANGUAGE)
+                .and_then(|languages| languages.to_str().ok())
+                .map(|languages| {
+                    languages
+                        .split(',')
+                        .filter_map(|language| {
+                            let mut parts = language.split(';');
+                            let tag = parts.next()?.trim();
+                            let quality = parts
+                                .find_map(|part| part.trim().strip_prefix("q="))
+                                .map(|quality| quality.parse::<f32>().unwrap_or(0.0))
+                                .unwrap_or(1.0);
+
+                            if tag.is_empty() || tag == "*" || quality <= 0.0 {
+                                return None;
+                            }
+
+                            Some((tag.to_string(), quality))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+
+        tags.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        // fr-ch falls back to fr before the next language
+        let mut locales: Vec<String> = Vec::new();
+        for (tag, _) in tags {
+            let tag = tag.to_lowercase();
+            let primary = tag.split('-').next().unwrap_or_default().to_string();
+
+            for locale in [tag, primary] {
+                if !locales.contains(&locale) {
+                    locales.push(locale);
+                }
+            }
+        }
+
+        locales
+    }
+}
+
+// bcp 47 language tags like en, fr or pt-BR
+fn validate_locale(locale: &str) -> Result<(), ValidationError> {
+    let mut parts = locale.split('-');
+
+    let language = parts.next().unwrap_or_default();
+    if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(ValidationError::new("locale"));
+    }
+
+    for part in parts {
+        if !(2..=8).contains(&part.len()) || !part.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(ValidationError::new("locale"));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,14 @@
+use serde::Deserialize;
+use validator::Validate;
+
+// a totp code or one of the recovery codes
+#[derive(Deserialize, Validate)]
+pub struct TwoFactorCodeSchema {
+    #[validate(length(min = 1, max = 32))]
+    pub code: String,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct SetTwoFactorRequiredSchema {
+    pub required: bool,
+}
@@ -2,7 +2,7 @@ use serde::Deserialize;
 use validator::Validate;
 
 #[derive(Deserialize, Validate)]
-pub struct StoreUnitSchema<'a> {
+pub struct StoreUnitSchema {
     #[validate(length(min = 1))]
-    pub name: &'a str,
+    pub name: String,
 }
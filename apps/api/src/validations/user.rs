@@ -3,10 +3,10 @@ use uuid::Uuid;
 use validator::Validate;
 
 #[derive(Deserialize, Validate)]
-pub struct StoreUserSchema<'a> {
+pub struct StoreUserSchema {
     #[validate(length(min = 1))]
-    pub name: Option<&'a str>,
+    pub name: Option<String>,
     #[validate(email)]
-    pub email: &'a str,
+    pub email: String,
     pub role_id: Uuid,
 }
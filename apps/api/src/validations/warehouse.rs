@@ -2,7 +2,7 @@ use serde::Deserialize;
 use validator::Validate;
 
 #[derive(Deserialize, Validate)]
-pub struct StoreWarehouseSchema<'a> {
+pub struct StoreWarehouseSchema {
     #[validate(length(min = 1))]
-    pub name: &'a str,
+    pub name: String,
 }
@@ -0,0 +1,18 @@
+use serde::Deserialize;
+use uuid::Uuid;
+use validator::Validate;
+
+// either a whole product or one of its variants
+#[derive(Deserialize, Validate)]
+pub struct StoreWishlistItemSchema {
+    pub product_id: Option<Uuid>,
+    pub variant_id: Option<Uuid>,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct MoveWishlistItemSchema {
+    // required when the wishlisted item is a product rather than a variant
+    pub variant_id: Option<Uuid>,
+    #[validate(range(min = 1))]
+    pub quantity: i32,
+}
@@ -0,0 +1,221 @@
+use axum::{body::Body, http::Request, Router};
+use chrono::Utc;
+use common::{auth, init, send};
+use rumerce::{services, utils::totp};
+use serde_json::json;
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use tower::ServiceExt;
+
+mod common;
+
+async fn send_with_key(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    key: &str,
+    body: Option<serde_json::Value>,
+) -> u16 {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Authorization", format!("Bearer {}", key))
+        .header("Content-Type", "application/json")
+        .body(match body {
+            Some(body) => Body::from(body.to_string()),
+            None => Body::empty(),
+        })
+        .unwrap();
+
+    app.clone()
+        .oneshot(request)
+        .await
+        .unwrap()
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+pub async fn api_keys_act_within_their_scopes() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let (_, session) = auth(&config.admin, &mut config.connection).await;
+    let session = format!("session={}", session.session);
+
+    let (status, _, created) = send(
+        &config.app,
+        "POST",
+        "/api-keys",
+        &session,
+        Some(json!({ "name": "ERP", "scopes": ["stock.manage"] })),
+    )
+    .await;
+    assert_eq!(status, 201);
+
+    let key = created["key"].as_str().unwrap().to_string();
+    let prefix = created["prefix"].as_str().unwrap();
+    assert!(key.starts_with(&format!("rmk_{}_", prefix)));
+    assert!(created.get("key_hash").is_none());
+    assert!(created["last_used_at"].is_null());
+
+    // only a hash is kept
+    let key_hash = sqlx::query_scalar!("SELECT key_hash FROM api_keys")
+        .fetch_one(&mut *config.connection)
+        .await
+        .unwrap();
+    assert_ne!(key_hash, key);
+
+    let status = send_with_key(&config.app, "GET", "/warehouses", &key, None).await;
+    assert_eq!(status, 200);
+
+    let status = send_with_key(
+        &config.app,
+        "POST",
+        "/warehouses",
+        &key,
+        Some(json!({ "name": "North" })),
+    )
+    .await;
+    assert_eq!(status, 201);
+
+    // outside its scopes, and off the personal routes
+    let status = send_with_key(&config.app, "GET", "/users", &key, None).await;
+    assert_eq!(status, 403);
+
+    let status = send_with_key(&config.app, "GET", "/profile", &key, None).await;
+    assert_eq!(status, 403);
+
+    let status = send_with_key(&config.app, "GET", "/warehouses", "rmk_nope_nope", None).await;
+    assert_eq!(status, 401);
+
+    let (status, _, keys) = send(&config.app, "GET", "/api-keys", &session, None).await;
+    assert_eq!(status, 200);
+    assert_eq!(keys.as_array().unwrap().len(), 1);
+    assert!(keys[0]["last_used_at"].is_string());
+    assert_eq!(keys[0]["scopes"], json!(["stock.manage"]));
+
+    let id = created["id"].as_str().unwrap();
+    let (status, _, _) = send(
+        &config.app,
+        "DELETE",
+        &format!("/api-keys/{id}"),
+        &session,
+        None,
+    )
+    .await;
+    assert_eq!(status, 204);
+
+    let status = send_with_key(&config.app, "GET", "/warehouses", &key, None).await;
+    assert_eq!(status, 401);
+}
+
+#[tokio::test]
+pub async fn api_keys_expire_and_follow_their_owner() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let (user, session) = auth(&config.admin, &mut config.connection).await;
+    let session = format!("session={}", session.session);
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/api-keys",
+        &session,
+        Some(json!({ "name": "ERP", "expires_at": "2000-01-01T00:00:00" })),
+    )
+    .await;
+    assert_eq!(status, 400);
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/api-keys",
+        &session,
+        Some(json!({ "name": "ERP", "scopes": ["nothing.at_all"] })),
+    )
+    .await;
+    assert_eq!(status, 400);
+
+    let (status, _, created) = send(
+        &config.app,
+        "POST",
+        "/api-keys",
+        &session,
+        Some(json!({
+            "name": "ERP",
+            "scopes": ["stock.manage"],
+            "expires_at": "2999-01-01T00:00:00",
+        })),
+    )
+    .await;
+    assert_eq!(status, 201);
+    let key = created["key"].as_str().unwrap().to_string();
+
+    let status = send_with_key(&config.app, "GET", "/warehouses", &key, None).await;
+    assert_eq!(status, 200);
+
+    sqlx::query!("UPDATE api_keys SET expires_at = NOW() - INTERVAL '1 minute'")
+        .execute(&mut *config.connection)
+        .await
+        .unwrap();
+
+    let status = send_with_key(&config.app, "GET", "/warehouses", &key, None).await;
+    assert_eq!(status, 401);
+
+    sqlx::query!("UPDATE api_keys SET expires_at = NULL")
+        .execute(&mut *config.connection)
+        .await
+        .unwrap();
+
+    // an authenticator confirmed after the key was made isn't vouched for by the key
+    let now = Utc::now().naive_utc();
+    let secret = totp::seal(&config.env.app_secret, &totp::secret());
+    services::two_factor::enroll(&user.user.id, &secret, &now, &mut config.connection)
+        .await
+        .unwrap();
+    services::two_factor::confirm(&user.user.id, &now, &mut config.connection)
+        .await
+        .unwrap();
+
+    let status = send_with_key(&config.app, "GET", "/warehouses", &key, None).await;
+    assert_eq!(status, 403);
+
+    services::two_factor::destroy(&user.user.id, &mut config.connection)
+        .await
+        .unwrap();
+
+    let status = send_with_key(&config.app, "GET", "/warehouses", &key, None).await;
+    assert_eq!(status, 200);
+
+    // the owner's role no longer grants the scope, so the key can't use it either
+    sqlx::query!(
+        "DELETE FROM role_permissions WHERE role_id = $1 AND permission = 'stock.manage'",
+        config.admin.id
+    )
+    .execute(&mut *config.connection)
+    .await
+    .unwrap();
+
+    let status = send_with_key(&config.app, "GET", "/warehouses", &key, None).await;
+    assert_eq!(status, 403);
+
+    // keys go away with the user who made them
+    services::user::destroy(&user.user.id, &mut config.connection)
+        .await
+        .unwrap();
+
+    let status = send_with_key(&config.app, "GET", "/warehouses", &key, None).await;
+    assert_eq!(status, 401);
+}
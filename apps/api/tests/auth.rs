@@ -1,9 +1,12 @@
 use std::collections::HashMap;
 
 use axum::{body::Body, http::Request};
-use common::init;
+use common::{init, Config};
 use http_body_util::BodyExt;
-use rumerce::{services, utils::constants::ROLES};
+use rumerce::{
+    services,
+    utils::constants::{OTP_ATTEMPTS, ROLES},
+};
 use testcontainers::{runners::AsyncRunner, ImageExt};
 use testcontainers_modules::postgres::Postgres;
 use tower::ServiceExt;
@@ -11,6 +14,24 @@ use uuid::Uuid;
 
 mod common;
 
+// the link and the code only exist in plain text inside the last mail
+fn mailed_html(config: &Config) -> String {
+    let mails = config.mails.lock().unwrap();
+    mails.last().unwrap()["html"].as_str().unwrap().to_string()
+}
+
+fn mailed_token(config: &Config) -> String {
+    let html = mailed_html(config);
+    let token = html.split("token=").nth(1).unwrap();
+    token.split('"').next().unwrap().to_string()
+}
+
+fn mailed_code(config: &Config) -> String {
+    let html = mailed_html(config);
+    let code = html.split("<strong>").nth(1).unwrap();
+    code.split("</strong>").next().unwrap().to_string()
+}
+
 #[tokio::test]
 pub async fn sign_in_success() {
     let container = Postgres::default()
@@ -202,10 +223,7 @@ pub async fn auth_success() {
     assert_eq!(user.user.email, email);
     assert_eq!(user.role.name, ROLES.member);
 
-    let token = services::magic_tokens::find_by_user(&user.user.id, &mut config.connection)
-        .await
-        .unwrap()
-        .unwrap();
+    let token = mailed_token(&config);
 
     let response = config
         .app
@@ -213,7 +231,7 @@ pub async fn auth_success() {
         .oneshot(
             Request::builder()
                 .method("GET")
-                .uri(format!("/auth?token={}", token.token.token))
+                .uri(format!("/auth?token={}", token))
                 .body(Body::empty())
                 .unwrap(),
         )
@@ -377,10 +395,7 @@ pub async fn sign_out_success() {
     assert_eq!(user.user.email, email);
     assert_eq!(user.role.name, ROLES.member);
 
-    let token = services::magic_tokens::find_by_user(&user.user.id, &mut config.connection)
-        .await
-        .unwrap()
-        .unwrap();
+    let token = mailed_token(&config);
 
     let response = config
         .app
@@ -388,7 +403,7 @@ pub async fn sign_out_success() {
         .oneshot(
             Request::builder()
                 .method("GET")
-                .uri(format!("/auth?token={}", token.token.token))
+                .uri(format!("/auth?token={}", token))
                 .body(Body::empty())
                 .unwrap(),
         )
@@ -507,10 +522,7 @@ pub async fn profile_success() {
     assert_eq!(user.user.email, email);
     assert_eq!(user.role.name, ROLES.member);
 
-    let token = services::magic_tokens::find_by_user(&user.user.id, &mut config.connection)
-        .await
-        .unwrap()
-        .unwrap();
+    let token = mailed_token(&config);
 
     let response = config
         .app
@@ -518,7 +530,7 @@ pub async fn profile_success() {
         .oneshot(
             Request::builder()
                 .method("GET")
-                .uri(format!("/auth?token={}", token.token.token))
+                .uri(format!("/auth?token={}", token))
                 .body(Body::empty())
                 .unwrap(),
         )
@@ -568,3 +580,282 @@ pub async fn profile_success() {
 
     assert_eq!(value["user"]["email"], email);
 }
+
+async fn request_sign_in(config: &Config, email: &str) {
+    let mut body = HashMap::new();
+    body.insert("email", email);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/sign-in")
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_string(&body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+}
+
+async fn use_token(config: &Config, token: &str) -> u16 {
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/auth?token={}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    response.status().as_u16()
+}
+
+async fn use_code(config: &Config, email: &str, code: &str) -> u16 {
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/auth/otp")
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    serde_json::json!({ "email": email, "code": code }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    response.status().as_u16()
+}
+
+#[tokio::test]
+pub async fn magic_token_stored_hashed() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let email = "example@example.com";
+    request_sign_in(&config, email).await;
+
+    let token = mailed_token(&config);
+    let code = mailed_code(&config);
+
+    let user = services::user::find_by_email(email, &mut config.connection)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let stored = services::magic_tokens::find_by_user(&user.user.id, &mut config.connection)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(code.len(), 6);
+    assert_ne!(stored.token.token_hash, token);
+    assert!(!stored.token.token_hash.contains(&token));
+    assert_ne!(stored.token.otp_hash, code);
+}
+
+#[tokio::test]
+pub async fn magic_token_single_use() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let config = init(&container).await;
+
+    request_sign_in(&config, "example@example.com").await;
+    let token = mailed_token(&config);
+
+    let (first, second) = tokio::join!(use_token(&config, &token), use_token(&config, &token));
+
+    let mut statuses = vec![first, second];
+    statuses.sort();
+    assert_eq!(statuses, vec![200, 400]);
+
+    assert_eq!(use_token(&config, &token).await, 400);
+}
+
+#[tokio::test]
+pub async fn magic_token_expired() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    request_sign_in(&config, "example@example.com").await;
+    let token = mailed_token(&config);
+    let code = mailed_code(&config);
+
+    sqlx::query!("UPDATE magic_tokens SET expires_at = NOW() - INTERVAL '1 minute'")
+        .execute(&mut *config.connection)
+        .await
+        .unwrap();
+
+    assert_eq!(use_token(&config, &token).await, 400);
+    assert_eq!(use_code(&config, "example@example.com", &code).await, 400);
+
+    let sessions = services::sessions::all(&mut config.connection)
+        .await
+        .unwrap();
+    assert_eq!(sessions.len(), 0);
+}
+
+#[tokio::test]
+pub async fn magic_token_replaced_by_newer() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let email = "example@example.com";
+
+    request_sign_in(&config, email).await;
+    let old_token = mailed_token(&config);
+    let old_code = mailed_code(&config);
+
+    request_sign_in(&config, email).await;
+    let token = mailed_token(&config);
+
+    let tokens = services::magic_tokens::all(&mut config.connection)
+        .await
+        .unwrap();
+    assert_eq!(tokens.len(), 1);
+
+    assert_eq!(use_token(&config, &old_token).await, 400);
+    if old_code != mailed_code(&config) {
+        assert_eq!(use_code(&config, email, &old_code).await, 400);
+    }
+    assert_eq!(use_token(&config, &token).await, 200);
+}
+
+#[tokio::test]
+pub async fn otp_success() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let email = "example@example.com";
+    request_sign_in(&config, email).await;
+    let code = mailed_code(&config);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/auth/otp")
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    serde_json::json!({ "email": email, "code": code }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let value = response
+        .headers()
+        .get("set-cookie")
+        .unwrap()
+        .to_str()
+        .unwrap();
+    assert!(value.contains("session"));
+
+    let tokens = services::magic_tokens::all(&mut config.connection)
+        .await
+        .unwrap();
+    assert_eq!(tokens.len(), 0);
+
+    // the link went with the code
+    assert_eq!(use_token(&config, &mailed_token(&config)).await, 400);
+}
+
+#[tokio::test]
+pub async fn otp_attempts_exhausted() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let email = "example@example.com";
+    request_sign_in(&config, email).await;
+    let code = mailed_code(&config);
+
+    for _ in 0..OTP_ATTEMPTS {
+        assert_eq!(use_code(&config, email, "not-a-code").await, 400);
+    }
+
+    assert_eq!(use_code(&config, email, &code).await, 400);
+
+    let sessions = services::sessions::all(&mut config.connection)
+        .await
+        .unwrap();
+    assert_eq!(sessions.len(), 0);
+}
+
+#[tokio::test]
+pub async fn otp_attempts_hold_under_concurrency() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let email = "example@example.com";
+    request_sign_in(&config, email).await;
+
+    // a few more than allowed, the email rate limit would answer 429 past its own capacity
+    let guesses = (0..OTP_ATTEMPTS + 3).map(|_| use_code(&config, email, "not-a-code"));
+    let statuses = futures::future::join_all(guesses).await;
+    assert!(statuses.iter().all(|status| *status == 400));
+
+    let attempts = sqlx::query_scalar!("SELECT attempts FROM magic_tokens")
+        .fetch_one(&mut *config.connection)
+        .await
+        .unwrap();
+    assert_eq!(attempts, OTP_ATTEMPTS);
+
+    // asking for another code doesn't hand out fresh guesses
+    request_sign_in(&config, email).await;
+    let code = mailed_code(&config);
+    assert_eq!(use_code(&config, email, &code).await, 400);
+
+    // the link still works
+    assert_eq!(use_token(&config, &mailed_token(&config)).await, 200);
+}
@@ -0,0 +1,247 @@
+use axum::{body::Body, http::Request};
+use common::{auth, init};
+use http_body_util::BodyExt;
+use rumerce::{
+    services,
+    validations::{
+        category::StoreCategorySchema,
+        product::{StoreProductSchema, Variant, VariantOption},
+        stock::StoreStockSchema,
+        unit::StoreUnitSchema,
+        warehouse::StoreWarehouseSchema,
+    },
+};
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use tower::ServiceExt;
+
+mod common;
+
+#[tokio::test]
+pub async fn bundle_in_cart_reserves_components() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let (_, session) = auth(&config.admin, &mut config.connection).await;
+
+    let unit = services::unit::insert(
+        &StoreUnitSchema {
+            name: "Unit 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let category = services::category::insert(
+        &StoreCategorySchema {
+            name: "Category 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    for name in ["Product 1", "Product 2"] {
+        let input = StoreProductSchema {
+            name: name.to_string(),
+            slug: None,
+            description: None,
+            unit_id: unit.id,
+            category_id: category.id,
+            variants: vec![Variant {
+                options: vec![VariantOption {
+                    key: "Size".to_string(),
+                    value: "M".to_string(),
+                }],
+                price: 10.0,
+                sku: None,
+            }],
+            images: vec![],
+        };
+
+        let product = services::product::insert(&input, &mut config.connection)
+            .await
+            .unwrap();
+
+        product
+            .attach_variants(&input.variants, &mut config.connection)
+            .await
+            .unwrap();
+    }
+
+    let variants = sqlx::query_scalar!(
+        "SELECT product_variants.id FROM product_variants JOIN products ON products.id = product_variants.product_id ORDER BY products.name"
+    )
+    .fetch_all(&mut *config.connection)
+    .await
+    .unwrap();
+
+    let warehouse = services::warehouse::insert(
+        &StoreWarehouseSchema {
+            name: "Warehouse 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    for variant_id in &variants {
+        services::stock::set(
+            &StoreStockSchema {
+                variant_id: *variant_id,
+                warehouse_id: warehouse.id,
+                quantity: 5,
+            },
+            &mut config.connection,
+        )
+        .await
+        .unwrap();
+    }
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/bundles")
+                .header("Cookie", format!("session={}", session.session))
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    serde_json::json!({
+                        "name": "Bundle 1",
+                        "price": 15.0,
+                        "items": [
+                            { "variant_id": variants[0], "quantity": 2 },
+                            { "variant_id": variants[1], "quantity": 1 },
+                        ],
+                    })
+                    .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 201);
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let bundle: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    let add_to_cart = |quantity: i32, cookie: Option<&str>| {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/carts")
+            .header("Content-Type", "application/json");
+
+        let request = match cookie {
+            Some(cookie) => request.header("Cookie", cookie),
+            None => request,
+        };
+
+        request
+            .body(Body::from(
+                serde_json::json!({ "bundle_id": bundle["id"], "quantity": quantity }).to_string(),
+            ))
+            .unwrap()
+    };
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(add_to_cart(2, None))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let cookie = response.headers()["set-cookie"]
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/carts")
+                .header("Cookie", &cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body.as_array().unwrap().len(), 1);
+    assert_eq!(body[0]["bundle_id"], bundle["id"]);
+    assert_eq!(body[0]["components"].as_array().unwrap().len(), 2);
+
+    let reserved = sqlx::query!(
+        r#"
+          SELECT stocks.variant_id, SUM(stock_reservations.quantity)::INTEGER AS "reserved!"
+          FROM stock_reservations
+          JOIN stocks ON stocks.id = stock_reservations.stock_id
+          GROUP BY stocks.variant_id
+        "#
+    )
+    .fetch_all(&mut *config.connection)
+    .await
+    .unwrap();
+
+    for row in reserved {
+        let expected = if row.variant_id == variants[0] { 4 } else { 2 };
+        assert_eq!(row.reserved, expected);
+    }
+
+    // one more bundle would need 6 of the first component
+    let response = config
+        .app
+        .clone()
+        .oneshot(add_to_cart(1, Some(&cookie)))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 409);
+
+    let count = sqlx::query_scalar!(r#"SELECT COUNT(*) AS "count!" FROM cart_items"#)
+        .fetch_one(&mut *config.connection)
+        .await
+        .unwrap();
+
+    assert_eq!(count, 1);
+
+    // the hold runs out like a checkout's instead of locking the stock for good
+    let open = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM stock_reservations WHERE expires_at IS NULL"#
+    )
+    .fetch_one(&mut *config.connection)
+    .await
+    .unwrap();
+
+    assert_eq!(open, 0);
+
+    sqlx::query!("UPDATE stock_reservations SET expires_at = NOW() - INTERVAL '1 minute'")
+        .execute(&mut *config.connection)
+        .await
+        .unwrap();
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(add_to_cart(1, None))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+}
@@ -0,0 +1,179 @@
+use common::{auth, init, send};
+use rumerce::{
+    services,
+    validations::{
+        category::StoreCategorySchema,
+        image::StoreImageSchema,
+        product::{StoreProductSchema, Variant, VariantOption},
+        stock::StoreStockSchema,
+        unit::StoreUnitSchema,
+        warehouse::StoreWarehouseSchema,
+    },
+};
+use serde_json::json;
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use uuid::Uuid;
+
+mod common;
+
+async fn variant(connection: &mut sqlx::PgConnection) -> Uuid {
+    let unit = services::unit::insert(
+        &StoreUnitSchema {
+            name: "Unit 1".to_string(),
+        },
+        connection,
+    )
+    .await
+    .unwrap();
+
+    let category = services::category::insert(
+        &StoreCategorySchema {
+            name: "Category 1".to_string(),
+        },
+        connection,
+    )
+    .await
+    .unwrap();
+
+    let image = services::image::insert(
+        &StoreImageSchema {
+            name: "image.png".to_string(),
+            src: "image.png".to_string(),
+        },
+        connection,
+    )
+    .await
+    .unwrap();
+
+    let input = StoreProductSchema {
+        name: "Product 1".to_string(),
+        slug: None,
+        description: None,
+        unit_id: unit.id,
+        category_id: category.id,
+        variants: vec![Variant {
+            options: vec![VariantOption {
+                key: "Size".to_string(),
+                value: "M".to_string(),
+            }],
+            price: 100.0,
+            sku: None,
+        }],
+        images: vec![image.id],
+    };
+
+    let product = services::product::insert(&input, connection).await.unwrap();
+
+    product
+        .attach_variants(&input.variants, connection)
+        .await
+        .unwrap();
+
+    product
+        .attach_images(&input.images, connection)
+        .await
+        .unwrap();
+
+    let variant_id = sqlx::query_scalar!("SELECT id FROM product_variants")
+        .fetch_one(&mut *connection)
+        .await
+        .unwrap();
+
+    let warehouse = services::warehouse::insert(
+        &StoreWarehouseSchema {
+            name: "Warehouse 1".to_string(),
+        },
+        connection,
+    )
+    .await
+    .unwrap();
+
+    services::stock::set(
+        &StoreStockSchema {
+            variant_id,
+            warehouse_id: warehouse.id,
+            quantity: 10,
+        },
+        connection,
+    )
+    .await
+    .unwrap();
+
+    variant_id
+}
+
+#[tokio::test]
+pub async fn carts_follow_the_cookie_or_the_user() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let variant_id = variant(&mut config.connection).await;
+
+    // guests get a cart cookie with the same attributes as the session cookie
+    let (status, set_cookie, _) = send(
+        &config.app,
+        "POST",
+        "/carts",
+        "",
+        Some(json!({ "variant_id": variant_id, "quantity": 1 })),
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    let set_cookie = set_cookie.unwrap();
+    assert!(set_cookie.starts_with("cart="));
+    assert!(set_cookie.contains("HttpOnly"));
+    assert!(set_cookie.contains("Secure"));
+    assert!(set_cookie.contains("SameSite=Strict"));
+
+    let guest = set_cookie.split(';').next().unwrap().to_string();
+
+    let (status, _, lines) = send(&config.app, "GET", "/carts", &guest, None).await;
+    assert_eq!(status, 200);
+    assert_eq!(lines.as_array().unwrap().len(), 1);
+
+    // signed in, the user's own cart is found without any cart cookie
+    let (user, session) = auth(&config.member, &mut config.connection).await;
+    let session = format!("session={}", session.session);
+
+    let (status, set_cookie, _) = send(
+        &config.app,
+        "POST",
+        "/carts",
+        &session,
+        Some(json!({ "variant_id": variant_id, "quantity": 2 })),
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    let owned = set_cookie.unwrap().split(';').next().unwrap().to_string();
+    assert_ne!(owned, guest);
+
+    let (status, _, lines) = send(&config.app, "GET", "/carts", &session, None).await;
+    assert_eq!(status, 200);
+    assert_eq!(lines.as_array().unwrap().len(), 1);
+    assert_eq!(lines[0]["quantity"], 2);
+
+    let user_id = sqlx::query_scalar!(
+        "SELECT user_id FROM carts WHERE id = $1",
+        Uuid::parse_str(owned["cart=".len()..].split('.').next().unwrap()).unwrap()
+    )
+    .fetch_one(&mut *config.connection)
+    .await
+    .unwrap();
+    assert_eq!(user_id, Some(user.user.id));
+
+    // a cookie alone can't reach a cart that belongs to someone
+    let (status, _, lines) = send(&config.app, "GET", "/carts", &owned, None).await;
+    assert_eq!(status, 200);
+    assert!(lines.as_array().unwrap().is_empty());
+
+    let (status, _, _) = send(&config.app, "GET", "/profile", &guest, None).await;
+    assert_eq!(status, 401);
+}
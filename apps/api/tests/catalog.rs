@@ -0,0 +1,178 @@
+use axum::{body::Body, http::Request};
+use common::{auth, init};
+use http_body_util::BodyExt;
+use rumerce::{services, validations::image::StoreImageSchema};
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use tower::ServiceExt;
+
+mod common;
+
+const CSV: &str = "slug,name,description,category,unit,sku,price,options,images
+t-shirt,T-shirt,Cotton,Clothes,Piece,TS-S,10,Size=S,shirt.png
+t-shirt,T-shirt,Cotton,Clothes,Piece,TS-M,12,Size=M,shirt.png
+";
+
+#[tokio::test]
+pub async fn import_products_dry_run() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+    let (_, session) = auth(&config.admin, &mut config.connection).await;
+
+    services::image::insert(
+        &StoreImageSchema {
+            name: "shirt.png".to_string(),
+            src: "shirt.png".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let response = config
+        .app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/products/import?dry_run=true")
+                .header("Cookie", format!("session={}", session.session))
+                .header("Content-Type", "text/csv")
+                .body(Body::from(CSV))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body["dry_run"], true);
+    assert_eq!(body["created"], 1);
+    assert_eq!(body["categories"][0], "Clothes");
+    assert_eq!(body["units"][0], "Piece");
+
+    let product = services::product::find_by_slug("t-shirt", &mut config.connection)
+        .await
+        .unwrap();
+    assert!(product.is_none());
+}
+
+#[tokio::test]
+pub async fn import_products_reports_row_errors() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+    let (_, session) = auth(&config.admin, &mut config.connection).await;
+
+    let csv = "slug,name,description,category,unit,sku,price,options,images
+t-shirt,T-shirt,,Clothes,Piece,TS-S,10,Size,
+t-shirt,T-shirt,,Clothes,Piece,TS-M,12,Size=M,missing.png
+t-shirt,T-shirt,,Clothes,Piece,TS-M,12,Size=L,
+";
+
+    let response = config
+        .app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/products/import")
+                .header("Cookie", format!("session={}", session.session))
+                .header("Content-Type", "text/csv")
+                .body(Body::from(csv))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 422);
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    let lines = body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| error["line"].as_u64().unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(lines, vec![2, 3, 4]);
+}
+
+#[tokio::test]
+pub async fn import_and_export_products() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    services::image::insert(
+        &StoreImageSchema {
+            name: "shirt.png".to_string(),
+            src: "shirt.png".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let report = services::catalog::import(CSV, &mut config.connection)
+        .await
+        .unwrap();
+
+    assert!(report.errors.is_empty());
+    assert_eq!(report.created, 1);
+
+    let variant_id = sqlx::query_scalar!("SELECT id FROM product_variants WHERE sku = 'TS-S'")
+        .fetch_one(&mut *config.connection)
+        .await
+        .unwrap();
+
+    // upserting by sku keeps the product and the listed variants, the rest are dropped
+    let csv = "slug,name,description,category,unit,sku,price,options,images
+,Cotton T-shirt,Cotton,Clothes,Piece,TS-S,15,Size=S,shirt.png
+";
+
+    let report = services::catalog::import(csv, &mut config.connection)
+        .await
+        .unwrap();
+
+    assert!(report.errors.is_empty());
+    assert_eq!(report.created, 0);
+    assert_eq!(report.updated, 1);
+
+    let rows = services::catalog::export(&mut config.connection)
+        .await
+        .unwrap();
+
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].name, "Cotton T-shirt");
+    assert_eq!(rows[0].sku.as_deref(), Some("TS-S"));
+    assert_eq!(rows[0].price, Some(15.0));
+    assert_eq!(rows[0].options.as_deref(), Some("Size=S"));
+    assert_eq!(rows[0].images.as_deref(), Some("shirt.png"));
+
+    let variant_ids = sqlx::query_scalar!("SELECT id FROM product_variants")
+        .fetch_all(&mut *config.connection)
+        .await
+        .unwrap();
+
+    assert_eq!(variant_ids, vec![variant_id]);
+
+    let csv = String::from_utf8(services::catalog::to_csv(&rows).unwrap()).unwrap();
+    assert!(csv.starts_with("slug,name,description,category,unit,sku,price,options,images\n"));
+}
@@ -0,0 +1,167 @@
+use axum::{body::Body, http::Request};
+use common::{auth, init, send};
+use http_body_util::BodyExt;
+use rumerce::utils::client;
+use serde_json::json;
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use tower::ServiceExt;
+
+mod common;
+
+#[tokio::test]
+pub async fn csrf_token_is_issued_and_kept() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let config = init(&container).await;
+
+    let (status, set_cookie, token) = send(&config.bare_app, "GET", "/csrf", "", None).await;
+    assert_eq!(status, 200);
+
+    let token = token.as_str().unwrap().to_string();
+    let set_cookie = set_cookie.unwrap();
+    assert!(set_cookie.starts_with(&format!("csrf={};", token)));
+    assert!(set_cookie.contains("HttpOnly"));
+    assert!(set_cookie.contains("SameSite=Strict"));
+
+    // other tabs keep working, the same token comes back while the cookie lives
+    let (status, _, again) = send(
+        &config.bare_app,
+        "GET",
+        "/csrf",
+        &format!("csrf={}", token),
+        None,
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(again, token);
+}
+
+#[tokio::test]
+pub async fn cookie_mutations_need_a_matching_token() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let (_, session) = auth(&config.admin, &mut config.connection).await;
+    let session = format!(
+        "session={}",
+        client::signed_value(&config.env, "session", &session.session.to_string())
+    );
+
+    // reads are left alone
+    let (status, _, _) = send(&config.bare_app, "GET", "/users", &session, None).await;
+    assert_eq!(status, 200);
+
+    let (status, _, message) = send(
+        &config.bare_app,
+        "POST",
+        "/categories",
+        &session,
+        Some(json!({ "name": "Shoes" })),
+    )
+    .await;
+    assert_eq!(status, 403);
+    assert_eq!(message, "Missing CSRF token");
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/categories")
+        .header("Cookie", format!("{}; csrf=right", session))
+        .header("x-csrf-token", "wrong")
+        .header("Content-Type", "application/json")
+        .body(Body::from(json!({ "name": "Shoes" }).to_string()))
+        .unwrap();
+
+    let response = config.bare_app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), 403);
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let message: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+    assert_eq!(message, "Invalid CSRF token");
+
+    // the app echoes the token from its cookie the way the dashboard does
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/categories",
+        &session,
+        Some(json!({ "name": "Shoes" })),
+    )
+    .await;
+    assert_eq!(status, 201);
+
+    // personal routes and carts are covered the same way
+    let (status, _, _) = send(&config.bare_app, "POST", "/sign-out", &session, None).await;
+    assert_eq!(status, 403);
+
+    let (status, _, _) = send(
+        &config.bare_app,
+        "POST",
+        "/carts",
+        "cart=00000000-0000-0000-0000-000000000000",
+        Some(json!({})),
+    )
+    .await;
+    assert_eq!(status, 403);
+
+    for uri in ["/wishlists", "/sign-in", "/auth/otp", "/setup"] {
+        let (status, _, message) = send(
+            &config.bare_app,
+            "POST",
+            uri,
+            "wishlist=00000000-0000-0000-0000-000000000000",
+            Some(json!({})),
+        )
+        .await;
+        assert_eq!(status, 403, "{uri}");
+        assert_eq!(message, "Missing CSRF token");
+    }
+
+    // an authorization header alone isn't a key, the cookies still need the token
+    let request = Request::builder()
+        .method("POST")
+        .uri("/carts")
+        .header("Cookie", "cart=00000000-0000-0000-0000-000000000000")
+        .header("Authorization", "Bearer forged")
+        .header("Content-Type", "application/json")
+        .body(Body::from(json!({}).to_string()))
+        .unwrap();
+
+    let response = config.bare_app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), 403);
+
+    // api keys don't ride on cookies, so they need no token
+    let (status, _, created) = send(
+        &config.app,
+        "POST",
+        "/api-keys",
+        &session,
+        Some(json!({ "name": "ERP", "scopes": ["catalog.write"] })),
+    )
+    .await;
+    assert_eq!(status, 201);
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/categories")
+        .header("Cookie", &session)
+        .header(
+            "Authorization",
+            format!("Bearer {}", created["key"].as_str().unwrap()),
+        )
+        .header("Content-Type", "application/json")
+        .body(Body::from(json!({ "name": "Boots" }).to_string()))
+        .unwrap();
+
+    let response = config.bare_app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), 201);
+}
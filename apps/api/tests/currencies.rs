@@ -0,0 +1,395 @@
+use axum::{body::Body, http::Request};
+use common::{auth, init};
+use http_body_util::BodyExt;
+use rumerce::{
+    services,
+    validations::{
+        category::StoreCategorySchema,
+        image::StoreImageSchema,
+        product::{StoreProductSchema, Variant, VariantOption},
+        stock::StoreStockSchema,
+        unit::StoreUnitSchema,
+        warehouse::StoreWarehouseSchema,
+    },
+};
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use tower::ServiceExt;
+
+mod common;
+
+#[tokio::test]
+pub async fn prices_convert_to_the_requested_currency() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let (_, session) = auth(&config.admin, &mut config.connection).await;
+
+    let unit = services::unit::insert(
+        &StoreUnitSchema {
+            name: "Unit 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let category = services::category::insert(
+        &StoreCategorySchema {
+            name: "Category 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let image = services::image::insert(
+        &StoreImageSchema {
+            name: "image.png".to_string(),
+            src: "image.png".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let input = StoreProductSchema {
+        name: "Product 1".to_string(),
+        slug: None,
+        description: None,
+        unit_id: unit.id,
+        category_id: category.id,
+        variants: vec![Variant {
+            options: vec![VariantOption {
+                key: "Size".to_string(),
+                value: "M".to_string(),
+            }],
+            price: 100.0,
+            sku: None,
+        }],
+        images: vec![image.id],
+    };
+
+    let product = services::product::insert(&input, &mut config.connection)
+        .await
+        .unwrap();
+
+    product
+        .attach_variants(&input.variants, &mut config.connection)
+        .await
+        .unwrap();
+
+    product
+        .attach_images(&input.images, &mut config.connection)
+        .await
+        .unwrap();
+
+    let variant_id = sqlx::query_scalar!("SELECT id FROM product_variants")
+        .fetch_one(&mut *config.connection)
+        .await
+        .unwrap();
+
+    let warehouse = services::warehouse::insert(
+        &StoreWarehouseSchema {
+            name: "Warehouse 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    services::stock::set(
+        &StoreStockSchema {
+            variant_id,
+            warehouse_id: warehouse.id,
+            quantity: 10,
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let request = |method: &str, uri: &str, cookie: Option<&str>, body: serde_json::Value| {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Content-Type", "application/json");
+
+        let request = match cookie {
+            Some(cookie) => request.header("Cookie", cookie),
+            None => request,
+        };
+
+        request.body(Body::from(body.to_string())).unwrap()
+    };
+
+    let admin = format!("session={}", session.session);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            "/currencies/base",
+            Some(&admin),
+            serde_json::json!({ "currency": "EUR" }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            "/exchange-rates",
+            Some(&admin),
+            serde_json::json!({ "currency": "usd", "rate": 1.1 }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 400);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            "/exchange-rates",
+            Some(&admin),
+            serde_json::json!({ "currency": "USD", "rate": 1.08333, "rounding": "up" }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            "/exchange-rates",
+            Some(&admin),
+            serde_json::json!({ "currency": "JPY", "rate": 162.4, "decimals": 0 }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let yen: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request("GET", "/currencies", None, serde_json::Value::Null))
+        .await
+        .unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body["base"], "EUR");
+    assert_eq!(body["rates"].as_array().unwrap().len(), 2);
+
+    // base currency by default
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "GET",
+            &format!("/products/{}", product.id),
+            None,
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body["variants"][0]["currency"], "EUR");
+    assert_eq!(body["variants"][0]["price"], 100.0);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "GET",
+            &format!("/products/{}?currency=usd", product.id),
+            None,
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body["variants"][0]["currency"], "USD");
+    assert_eq!(body["variants"][0]["price"], 108.34);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "GET",
+            "/products?currency=GBP",
+            None,
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 400);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/carts",
+            None,
+            serde_json::json!({ "variant_id": variant_id, "quantity": 3 }),
+        ))
+        .await
+        .unwrap();
+
+    let cookie = response.headers()["set-cookie"]
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/carts")
+                .header("Cookie", &cookie)
+                .header("X-Currency", "JPY")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body[0]["currency"], "JPY");
+    assert_eq!(body[0]["unit_price"], 16240.0);
+    assert_eq!(body[0]["total"], 48720.0);
+
+    // rounded up once per unit, the total is three of those
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/carts?currency=USD")
+                .header("Cookie", &cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body[0]["unit_price"], 108.34);
+    assert_eq!(body[0]["total"], 325.02);
+
+    // the order charges dollars and keeps the euro amount
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/carts/checkout?currency=USD",
+            Some(&cookie),
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let checkout: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(checkout["currency"], "USD");
+    assert_eq!(checkout["total"], 325.02);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "POST",
+            &format!("/checkouts/{}/paid", checkout["cart_id"].as_str().unwrap()),
+            Some(&admin),
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "GET",
+            "/orders",
+            Some(&admin),
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let orders: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(orders.as_array().unwrap().len(), 1);
+    assert_eq!(orders[0]["id"], checkout["order_id"]);
+    assert_eq!(orders[0]["currency"], "USD");
+    assert_eq!(orders[0]["total"], 325.02);
+    assert_eq!(orders[0]["base_currency"], "EUR");
+    assert_eq!(orders[0]["base_total"], 300.0);
+    assert!(!orders[0]["paid_at"].is_null());
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "DELETE",
+            &format!("/exchange-rates/{}", yen["id"].as_str().unwrap()),
+            Some(&admin),
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/carts")
+                .header("Cookie", &cookie)
+                .header("X-Currency", "JPY")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 400);
+}
@@ -0,0 +1,305 @@
+use axum::{body::Body, http::Request};
+use common::{auth, init};
+use http_body_util::BodyExt;
+use rumerce::{
+    services,
+    utils::constants::DOWNLOAD_LIMIT,
+    validations::{
+        category::StoreCategorySchema,
+        product::{StoreProductSchema, Variant, VariantOption},
+        stock::StoreStockSchema,
+        unit::StoreUnitSchema,
+        warehouse::StoreWarehouseSchema,
+    },
+};
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use tower::ServiceExt;
+
+mod common;
+
+#[tokio::test]
+pub async fn digital_variant_download_links() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let (user, session) = auth(&config.admin, &mut config.connection).await;
+
+    let unit = services::unit::insert(
+        &StoreUnitSchema {
+            name: "Unit 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let category = services::category::insert(
+        &StoreCategorySchema {
+            name: "Category 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let input = StoreProductSchema {
+        name: "Product 1".to_string(),
+        slug: None,
+        description: None,
+        unit_id: unit.id,
+        category_id: category.id,
+        variants: ["Ebook", "Paperback"]
+            .iter()
+            .map(|format| Variant {
+                options: vec![VariantOption {
+                    key: "Format".to_string(),
+                    value: format.to_string(),
+                }],
+                price: 10.0,
+                sku: Some(format.to_string()),
+            })
+            .collect(),
+        images: vec![],
+    };
+
+    let product = services::product::insert(&input, &mut config.connection)
+        .await
+        .unwrap();
+
+    product
+        .attach_variants(&input.variants, &mut config.connection)
+        .await
+        .unwrap();
+
+    let ebook = sqlx::query_scalar!("SELECT id FROM product_variants WHERE sku = 'Ebook'")
+        .fetch_one(&mut *config.connection)
+        .await
+        .unwrap();
+
+    let paperback = sqlx::query_scalar!("SELECT id FROM product_variants WHERE sku = 'Paperback'")
+        .fetch_one(&mut *config.connection)
+        .await
+        .unwrap();
+
+    let boundary = "rumerce-boundary";
+    let body = format!(
+        "--{boundary}\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"book.pdf\"\r\n\
+        Content-Type: application/pdf\r\n\r\n\
+        content\r\n\
+        --{boundary}--\r\n"
+    );
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/variants/{}/file", ebook))
+                .header("Cookie", format!("session={}", session.session))
+                .header(
+                    "Content-Type",
+                    format!("multipart/form-data; boundary={boundary}"),
+                )
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 201);
+
+    let add_to_cart = |variant_id, cookie: Option<&str>| {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/carts")
+            .header("Content-Type", "application/json");
+
+        let request = match cookie {
+            Some(cookie) => request.header("Cookie", cookie),
+            None => request,
+        };
+
+        request
+            .body(Body::from(
+                serde_json::json!({ "variant_id": variant_id, "quantity": 1 }).to_string(),
+            ))
+            .unwrap()
+    };
+
+    let shipping = |cookie: &str| {
+        Request::builder()
+            .method("GET")
+            .uri("/carts/shipping")
+            .header("Cookie", cookie)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(add_to_cart(ebook, None))
+        .await
+        .unwrap();
+
+    let cookie = response.headers()["set-cookie"]
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let response = config.app.clone().oneshot(shipping(&cookie)).await.unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body["requires_shipping"], false);
+
+    config
+        .app
+        .clone()
+        .oneshot(add_to_cart(paperback, Some(&cookie)))
+        .await
+        .unwrap();
+
+    let response = config.app.clone().oneshot(shipping(&cookie)).await.unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body["requires_shipping"], true);
+
+    // the buyer's own cart goes through checkout, paying for it hands out the downloads
+    let buyer = format!("session={}", session.session);
+
+    let warehouse = services::warehouse::insert(
+        &StoreWarehouseSchema {
+            name: "Warehouse 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    services::stock::set(
+        &StoreStockSchema {
+            variant_id: paperback,
+            warehouse_id: warehouse.id,
+            quantity: 5,
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    for variant_id in [ebook, paperback] {
+        let response = config
+            .app
+            .clone()
+            .oneshot(add_to_cart(variant_id, Some(&buyer)))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 200);
+    }
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/carts/checkout")
+                .header("Cookie", &buyer)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let checkout: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!(
+                    "/checkouts/{}/paid",
+                    checkout["cart_id"].as_str().unwrap()
+                ))
+                .header("Cookie", &buyer)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let downloads = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM downloads WHERE user_id = $1",
+        user.user.id
+    )
+    .fetch_one(&mut *config.connection)
+    .await
+    .unwrap();
+
+    // only the digital variant gets a download
+    assert_eq!(downloads, Some(1));
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/downloads")
+                .header("Cookie", format!("session={}", session.session))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body[0]["name"], "book.pdf");
+
+    let url = body[0]["url"].as_str().unwrap().to_string();
+
+    let download = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(download(&format!("{}0", url)))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 403);
+
+    for _ in 0..DOWNLOAD_LIMIT {
+        let response = config.app.clone().oneshot(download(&url)).await.unwrap();
+
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["content-type"], "application/pdf");
+
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&bytes[..], b"content");
+    }
+
+    let response = config.app.clone().oneshot(download(&url)).await.unwrap();
+
+    assert_eq!(response.status(), 410);
+}
@@ -0,0 +1,470 @@
+use axum::{async_trait, body::Body, http::Request};
+use chrono::{Duration, Utc};
+use common::{auth, init};
+use http_body_util::BodyExt;
+use rumerce::{
+    models::product::Product,
+    services,
+    utils::{
+        constants::PUBLIC_FOLDER_NAME,
+        storage::{LocalStorage, Storage, StorageError, StorageObject},
+    },
+    validations::{
+        category::StoreCategorySchema, image::StoreImageSchema, product::StoreProductSchema,
+        unit::StoreUnitSchema,
+    },
+};
+use sqlx::PgConnection;
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+mod common;
+
+async fn product(connection: &mut PgConnection) -> Product {
+    let unit = services::unit::insert(
+        &StoreUnitSchema {
+            name: "Unit 1".to_string(),
+        },
+        connection,
+    )
+    .await
+    .unwrap();
+
+    let category = services::category::insert(
+        &StoreCategorySchema {
+            name: "Category 1".to_string(),
+        },
+        connection,
+    )
+    .await
+    .unwrap();
+
+    services::product::insert(
+        &StoreProductSchema {
+            name: "Product 1".to_string(),
+            slug: None,
+            description: None,
+            unit_id: unit.id,
+            category_id: category.id,
+            variants: vec![],
+            images: vec![],
+        },
+        connection,
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+pub async fn list_images_with_references() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+    let (_, session) = auth(&config.admin, &mut config.connection).await;
+
+    let product = product(&mut config.connection).await;
+
+    let used = services::image::insert(
+        &StoreImageSchema {
+            name: "used.png".to_string(),
+            src: "used.png".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    services::image::insert(
+        &StoreImageSchema {
+            name: "unused.png".to_string(),
+            src: "unused.png".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    product
+        .attach_images(&vec![used.id], &mut config.connection)
+        .await
+        .unwrap();
+
+    let response = config
+        .app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/images")
+                .header("Cookie", format!("session={}", session.session))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+    let body = match body {
+        serde_json::Value::Array(body) => body,
+        _ => panic!("array expected"),
+    };
+
+    assert_eq!(body.len(), 2);
+
+    let used = body
+        .iter()
+        .find(|image| image["name"] == "used.png")
+        .unwrap();
+    assert_eq!(used["references"], 1);
+    assert_eq!(used["products"][0]["id"], product.id.to_string());
+    assert_eq!(used["url"], "/public/used.png");
+
+    let unused = body
+        .iter()
+        .find(|image| image["name"] == "unused.png")
+        .unwrap();
+    assert_eq!(unused["references"], 0);
+}
+
+#[tokio::test]
+pub async fn destroy_image_in_use() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+    let (_, session) = auth(&config.admin, &mut config.connection).await;
+
+    let product = product(&mut config.connection).await;
+
+    let image = services::image::insert(
+        &StoreImageSchema {
+            name: "used.png".to_string(),
+            src: "used.png".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    product
+        .attach_images(&vec![image.id], &mut config.connection)
+        .await
+        .unwrap();
+
+    let response = config
+        .app
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(format!("/images/{}", image.id))
+                .header("Cookie", format!("session={}", session.session))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 409);
+
+    let image = services::image::find(&image.id, &mut config.connection)
+        .await
+        .unwrap();
+    assert!(image.is_some());
+}
+
+#[tokio::test]
+pub async fn sweep_removes_orphans() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let storage = LocalStorage::new(std::env::temp_dir().join(Uuid::new_v4().to_string()));
+
+    let product = product(&mut config.connection).await;
+
+    for src in ["used.png", "unused.png", "untracked.png"] {
+        storage.put(src, b"content", "image/png").await.unwrap();
+    }
+
+    let used = services::image::insert(
+        &StoreImageSchema {
+            name: "used.png".to_string(),
+            src: "used.png".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let unused = services::image::insert(
+        &StoreImageSchema {
+            name: "unused.png".to_string(),
+            src: "unused.png".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    product
+        .attach_images(&vec![used.id], &mut config.connection)
+        .await
+        .unwrap();
+
+    // nothing is old enough yet
+    let removed = services::image::sweep(
+        &(Utc::now().naive_utc() - Duration::hours(1)),
+        &storage,
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(removed, 0);
+
+    let removed = services::image::sweep(
+        &(Utc::now().naive_utc() + Duration::hours(1)),
+        &storage,
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(removed, 2);
+
+    let keys = storage
+        .list()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|object| object.key)
+        .collect::<Vec<_>>();
+
+    assert_eq!(keys, vec!["used.png".to_string()]);
+
+    let unused = services::image::find(&unused.id, &mut config.connection)
+        .await
+        .unwrap();
+    assert!(unused.is_none());
+}
+
+// local storage that refuses to delete one key
+#[derive(Clone)]
+struct StubbornStorage {
+    inner: LocalStorage,
+    stuck: &'static str,
+}
+
+#[async_trait]
+impl Storage for StubbornStorage {
+    async fn put(&self, key: &str, bytes: &[u8], content_type: &str) -> Result<(), StorageError> {
+        self.inner.put(key, bytes, content_type).await
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, StorageError> {
+        self.inner.get(key).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        if key == self.stuck {
+            return Err(StorageError::InvalidKey(key.to_string()));
+        }
+
+        self.inner.delete(key).await
+    }
+
+    async fn list(&self) -> Result<Vec<StorageObject>, StorageError> {
+        self.inner.list().await
+    }
+
+    async fn url(&self, key: &str) -> Result<String, StorageError> {
+        self.inner.url(key).await
+    }
+
+    fn local_root(&self) -> Option<&std::path::Path> {
+        self.inner.local_root()
+    }
+}
+
+#[tokio::test]
+pub async fn sweep_skips_files_it_cant_delete() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let storage = StubbornStorage {
+        inner: LocalStorage::new(std::env::temp_dir().join(Uuid::new_v4().to_string())),
+        stuck: "a.png",
+    };
+
+    for src in ["a.png", "b.png"] {
+        storage.put(src, b"content", "image/png").await.unwrap();
+    }
+
+    let removed = services::image::sweep(
+        &(Utc::now().naive_utc() + Duration::hours(1)),
+        &storage,
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(removed, 1);
+
+    let keys = storage
+        .list()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|object| object.key)
+        .collect::<Vec<_>>();
+
+    assert_eq!(keys, vec!["a.png".to_string()]);
+}
+
+#[tokio::test]
+pub async fn upload_multiple_images() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+    let (_, session) = auth(&config.admin, &mut config.connection).await;
+
+    let boundary = "rumerce-boundary";
+    let body = format!(
+        "--{boundary}\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"first image.png\"\r\n\
+        Content-Type: image/png\r\n\r\n\
+        content\r\n\
+        --{boundary}\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"notes.txt\"\r\n\
+        Content-Type: text/plain\r\n\r\n\
+        content\r\n\
+        --{boundary}--\r\n"
+    );
+
+    let response = config
+        .app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/images")
+                .header("Cookie", format!("session={}", session.session))
+                .header(
+                    "Content-Type",
+                    format!("multipart/form-data; boundary={boundary}"),
+                )
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 207);
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body[0]["name"], "first image.png");
+    assert!(body[0]["image"]["src"]
+        .as_str()
+        .unwrap()
+        .ends_with("-first-image.png"));
+    assert!(body[0]["error"].is_null());
+
+    assert_eq!(body[1]["name"], "notes.txt");
+    assert!(body[1]["image"].is_null());
+    assert_eq!(body[1]["error"], "Only image file type is allowed");
+
+    let src = body[0]["image"]["src"].as_str().unwrap();
+    LocalStorage::new(PUBLIC_FOLDER_NAME)
+        .delete(src)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+pub async fn import_refuses_internal_addresses() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+    let (_, session) = auth(&config.admin, &mut config.connection).await;
+
+    // a local server that would hand out an image to anyone who reaches it
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut buffer = [0; 1024];
+            let _ = stream.read(&mut buffer).await;
+            let _ = stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nContent-Length: 7\r\n\r\ncontent")
+                .await;
+        }
+    });
+
+    let response = config
+        .app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/images/import")
+                .header("Cookie", format!("session={}", session.session))
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    serde_json::json!({
+                        "urls": [
+                            "http://127.0.0.1/image.png",
+                            "http://169.254.169.254/latest/meta-data",
+                            "http://[::ffff:10.0.0.1]/image.png",
+                            format!("http://localhost:{port}/image.png")
+                        ]
+                    })
+                    .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 400);
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    for result in body.as_array().unwrap().iter().take(3) {
+        assert_eq!(result["error"], "Only public addresses are allowed");
+    }
+
+    // names are only refused once resolved, inside the client
+    assert!(body[3]["image"].is_null());
+    assert_eq!(body[3]["error"], "Couldn't fetch file");
+}
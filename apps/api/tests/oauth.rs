@@ -0,0 +1,284 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{HeaderMap, Request, StatusCode},
+    response::Response,
+    routing::{get, post},
+    Form, Json, Router,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use common::{init, init_with};
+use rumerce::{services, validations::user::StoreUserSchema};
+use sha2::{Digest, Sha256};
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use tower::ServiceExt;
+use url::Url;
+
+mod common;
+
+#[derive(Clone, Default)]
+struct Provider {
+    // what the token endpoint received, to check the pkce verifier
+    forms: Arc<Mutex<Vec<HashMap<String, String>>>>,
+    claims: Arc<Mutex<serde_json::Value>>,
+}
+
+async fn token(
+    State(provider): State<Provider>,
+    Form(form): Form<HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let code = form.get("code").cloned();
+    provider.forms.lock().unwrap().push(form);
+
+    if code.as_deref() != Some("code") {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    Ok(Json(
+        serde_json::json!({ "access_token": "access", "token_type": "Bearer" }),
+    ))
+}
+
+async fn userinfo(
+    State(provider): State<Provider>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    if headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        != Some("Bearer access")
+    {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(Json(provider.claims.lock().unwrap().clone()))
+}
+
+async fn serve(provider: Provider) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let app = Router::new()
+        .route("/token", post(token))
+        .route("/userinfo", get(userinfo))
+        .with_state(provider);
+
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    format!("http://{}", address)
+}
+
+async fn authorize(app: &Router) -> (String, String) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/oauth/authorize")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 303);
+
+    let location = Url::parse(response.headers()["location"].to_str().unwrap()).unwrap();
+    let query: HashMap<_, _> = location.query_pairs().into_owned().collect();
+
+    assert_eq!(query["response_type"], "code");
+    assert_eq!(query["client_id"], "client");
+    assert_eq!(query["code_challenge_method"], "S256");
+
+    let cookie = response.headers()["set-cookie"].to_str().unwrap();
+    assert!(cookie.starts_with(&format!("oauth_state={};", query["state"])));
+
+    (query["state"].clone(), query["code_challenge"].clone())
+}
+
+async fn callback(app: &Router, state: &str, cookie: &str) -> Response {
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/oauth/callback?code=code&state={}", state))
+                .header("COOKIE", format!("oauth_state={}", cookie))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+pub async fn oauth_not_configured() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let config = init(&container).await;
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/oauth/authorize")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+pub async fn oauth_links_verified_email() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let provider = Provider::default();
+    let url = serve(provider.clone()).await;
+
+    let mut config = init_with(&container, |env| {
+        env.oauth_client_id = Some("client".to_string());
+        env.oauth_client_secret = Some("secret".to_string());
+        env.oauth_authorize_url = Some(format!("{}/authorize", url));
+        env.oauth_token_url = Some(format!("{}/token", url));
+        env.oauth_userinfo_url = Some(format!("{}/userinfo", url));
+        env.oauth_redirect_url = Some("http://localhost:5173/oauth/callback".to_string());
+    })
+    .await;
+
+    let existing = services::user::insert(
+        &StoreUserSchema {
+            name: None,
+            email: "example@example.com".to_string(),
+            role_id: config.member.id,
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    *provider.claims.lock().unwrap() = serde_json::json!({
+        "sub": "42",
+        "email": "example@example.com",
+        "email_verified": true,
+    });
+
+    let (state, challenge) = authorize(&config.app).await;
+
+    // a state from another browser is refused
+    let response = callback(&config.app, &state, "other").await;
+    assert_eq!(response.status(), 400);
+
+    // the browser is sent back to the storefront with its session
+    let response = callback(&config.app, &state, &state).await;
+    assert_eq!(response.status(), 303);
+    assert_eq!(
+        response.headers()["location"],
+        config.env.client_url.as_str()
+    );
+
+    let cookies: Vec<_> = response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .map(|cookie| cookie.to_str().unwrap().to_string())
+        .collect();
+    assert!(cookies.iter().any(|cookie| cookie.starts_with("session=")));
+    assert!(cookies
+        .iter()
+        .any(|cookie| cookie.starts_with("oauth_state=;") && cookie.contains("Max-Age=0")));
+
+    {
+        let forms = provider.forms.lock().unwrap();
+        let form = forms.last().unwrap();
+        let verifier = &form["code_verifier"];
+
+        assert_eq!(form["grant_type"], "authorization_code");
+        assert_eq!(form["client_secret"], "secret");
+        assert_eq!(
+            URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())),
+            challenge
+        );
+    }
+
+    let identity = services::identity::find("oidc", "42", &mut config.connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(identity.user_id, existing.user.id);
+
+    // the state only works once
+    let response = callback(&config.app, &state, &state).await;
+    assert_eq!(response.status(), 400);
+
+    // once linked the identity is enough, whatever the provider says about the email now
+    *provider.claims.lock().unwrap() = serde_json::json!({
+        "sub": "42",
+        "email": "changed@example.com",
+        "email_verified": false,
+    });
+
+    let (state, _) = authorize(&config.app).await;
+    let response = callback(&config.app, &state, &state).await;
+    assert_eq!(response.status(), 303);
+
+    let users = services::user::all(&mut config.connection).await.unwrap();
+    assert_eq!(users.len(), 1);
+}
+
+#[tokio::test]
+pub async fn oauth_rejects_unverified_email() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let provider = Provider::default();
+    let url = serve(provider.clone()).await;
+
+    let mut config = init_with(&container, |env| {
+        env.oauth_client_id = Some("client".to_string());
+        env.oauth_authorize_url = Some(format!("{}/authorize", url));
+        env.oauth_token_url = Some(format!("{}/token", url));
+        env.oauth_userinfo_url = Some(format!("{}/userinfo", url));
+        env.oauth_redirect_url = Some("http://localhost:5173/oauth/callback".to_string());
+    })
+    .await;
+
+    *provider.claims.lock().unwrap() = serde_json::json!({
+        "sub": "7",
+        "email": "victim@example.com",
+        "email_verified": false,
+    });
+
+    let (state, _) = authorize(&config.app).await;
+    let response = callback(&config.app, &state, &state).await;
+    assert_eq!(response.status(), 403);
+
+    let user = services::user::find_by_email("victim@example.com", &mut config.connection)
+        .await
+        .unwrap();
+    assert!(user.is_none());
+
+    // a public client sends no secret
+    let forms = provider.forms.lock().unwrap();
+    assert!(!forms.last().unwrap().contains_key("client_secret"));
+}
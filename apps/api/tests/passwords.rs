@@ -0,0 +1,214 @@
+use common::{auth, init, send};
+use rumerce::services;
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+
+mod common;
+
+#[tokio::test]
+pub async fn password_set_sign_in_and_change() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let (user, session) = auth(&config.member, &mut config.connection).await;
+    let session = format!("session={}", session.session);
+
+    // no password yet, magic links only
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/sign-in/password",
+        "",
+        Some(serde_json::json!({ "email": user.user.email, "password": "anything" })),
+    )
+    .await;
+    assert_eq!(status, 401);
+
+    let (status, _, _) = send(
+        &config.app,
+        "PUT",
+        "/password",
+        &session,
+        Some(serde_json::json!({ "password": "short" })),
+    )
+    .await;
+    assert_eq!(status, 400);
+
+    let (status, _, _) = send(
+        &config.app,
+        "PUT",
+        "/password",
+        &session,
+        Some(serde_json::json!({ "password": "correct horse" })),
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    let credential = services::credential::find(&user.user.id, &mut config.connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(credential.password_hash.starts_with("$argon2"));
+    assert!(!credential.password_hash.contains("correct horse"));
+
+    let (status, set_cookie, _) = send(
+        &config.app,
+        "POST",
+        "/sign-in/password",
+        "",
+        Some(serde_json::json!({ "email": user.user.email, "password": "correct horse" })),
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert!(set_cookie.unwrap().contains("session"));
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/sign-in/password",
+        "",
+        Some(serde_json::json!({ "email": user.user.email, "password": "wrong horse" })),
+    )
+    .await;
+    assert_eq!(status, 401);
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/sign-in/password",
+        "",
+        Some(serde_json::json!({ "email": "nobody@example.com", "password": "correct horse" })),
+    )
+    .await;
+    assert_eq!(status, 401);
+
+    // changing it needs the current one
+    let (status, _, _) = send(
+        &config.app,
+        "PUT",
+        "/password",
+        &session,
+        Some(serde_json::json!({ "password": "battery staple" })),
+    )
+    .await;
+    assert_eq!(status, 400);
+
+    let (status, _, _) = send(
+        &config.app,
+        "PUT",
+        "/password",
+        &session,
+        Some(
+            serde_json::json!({ "current_password": "wrong horse", "password": "battery staple" }),
+        ),
+    )
+    .await;
+    assert_eq!(status, 400);
+
+    let (status, _, _) = send(
+        &config.app,
+        "PUT",
+        "/password",
+        &session,
+        Some(serde_json::json!({
+            "current_password": "correct horse",
+            "password": "battery staple"
+        })),
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/sign-in/password",
+        "",
+        Some(serde_json::json!({ "email": user.user.email, "password": "battery staple" })),
+    )
+    .await;
+    assert_eq!(status, 200);
+}
+
+#[tokio::test]
+pub async fn password_reset() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let (user, session) = auth(&config.member, &mut config.connection).await;
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/password/forgot",
+        "",
+        Some(serde_json::json!({ "email": "nobody@example.com" })),
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(config.mails.lock().unwrap().len(), 0);
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/password/forgot",
+        "",
+        Some(serde_json::json!({ "email": user.user.email })),
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    let token = {
+        let mails = config.mails.lock().unwrap();
+        assert_eq!(mails.len(), 1);
+
+        let html = mails[0]["html"].as_str().unwrap().to_string();
+        let token = html.split("token=").nth(1).unwrap();
+        token.split('"').next().unwrap().to_string()
+    };
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/password/reset",
+        "",
+        Some(serde_json::json!({ "token": token, "password": "correct horse" })),
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    // every session is gone, including the one that was open
+    let found = services::sessions::find_by_session(&session.session, &mut config.connection)
+        .await
+        .unwrap();
+    assert!(found.is_none());
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/password/reset",
+        "",
+        Some(serde_json::json!({ "token": token, "password": "battery staple" })),
+    )
+    .await;
+    assert_eq!(status, 400);
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/sign-in/password",
+        "",
+        Some(serde_json::json!({ "email": user.user.email, "password": "correct horse" })),
+    )
+    .await;
+    assert_eq!(status, 200);
+}
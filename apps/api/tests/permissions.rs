@@ -0,0 +1,390 @@
+use chrono::{Duration, Utc};
+use common::{auth, init, send};
+use rumerce::{
+    services,
+    utils::constants::{PERMISSION_NAMES, ROLES, SESSION_MAX_AGE},
+    validations::auth::StoreSessionSchema,
+};
+use serde_json::json;
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use uuid::Uuid;
+
+mod common;
+
+async fn staff_session(email: &str, role: &str, connection: &mut sqlx::PgConnection) -> String {
+    let user = services::user::find_or_insert(email, role, connection)
+        .await
+        .unwrap();
+
+    let session = services::sessions::insert(
+        StoreSessionSchema {
+            session: Uuid::new_v4(),
+            user_id: user.user.id,
+            expires_at: Utc::now().naive_utc() + Duration::seconds(SESSION_MAX_AGE),
+            user_agent: None,
+            ip: None,
+        },
+        connection,
+    )
+    .await
+    .unwrap()
+    .session;
+
+    format!("session={}", session)
+}
+
+#[tokio::test]
+pub async fn roles_are_managed_by_admins() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let (_, session) = auth(&config.admin, &mut config.connection).await;
+    let session = format!("session={}", session.session);
+
+    let (status, _, permissions) = send(&config.app, "GET", "/permissions", &session, None).await;
+    assert_eq!(status, 200);
+    assert_eq!(permissions, json!(PERMISSION_NAMES));
+
+    let (status, _, roles) = send(&config.app, "GET", "/roles", &session, None).await;
+    assert_eq!(status, 200);
+    let admin = roles
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|role| role["name"] == ROLES.admin)
+        .unwrap();
+    assert_eq!(
+        admin["permissions"].as_array().unwrap().len(),
+        PERMISSION_NAMES.len()
+    );
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/roles",
+        &session,
+        Some(json!({ "name": "catalog", "permissions": ["catalog.nope"] })),
+    )
+    .await;
+    assert_eq!(status, 400);
+
+    let (status, _, role) = send(
+        &config.app,
+        "POST",
+        "/roles",
+        &session,
+        Some(json!({ "name": "catalog", "permissions": ["catalog.write"] })),
+    )
+    .await;
+    assert_eq!(status, 201);
+    assert_eq!(role["name"], "catalog");
+    assert_eq!(role["permissions"], json!(["catalog.write"]));
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/roles",
+        &session,
+        Some(json!({ "name": "catalog" })),
+    )
+    .await;
+    assert_eq!(status, 409);
+
+    let id = role["id"].as_str().unwrap();
+
+    let (status, _, _) = send(
+        &config.app,
+        "PATCH",
+        &format!("/roles/{id}"),
+        &session,
+        Some(
+            json!({ "name": "merchandising", "permissions": ["catalog.write", "products.write"] }),
+        ),
+    )
+    .await;
+    assert_eq!(status, 204);
+
+    let (status, _, role) = send(&config.app, "GET", &format!("/roles/{id}"), &session, None).await;
+    assert_eq!(status, 200);
+    assert_eq!(role["name"], "merchandising");
+    assert_eq!(
+        role["permissions"],
+        json!(["catalog.write", "products.write"])
+    );
+
+    // built-in roles stay as they are
+    let (status, _, _) = send(
+        &config.app,
+        "PATCH",
+        &format!("/roles/{}", config.admin.id),
+        &session,
+        Some(json!({ "name": ROLES.admin, "permissions": [] })),
+    )
+    .await;
+    assert_eq!(status, 403);
+
+    let (status, _, _) = send(
+        &config.app,
+        "DELETE",
+        &format!("/roles/{}", config.member.id),
+        &session,
+        None,
+    )
+    .await;
+    assert_eq!(status, 403);
+
+    let (status, _, _) = send(
+        &config.app,
+        "DELETE",
+        &format!("/roles/{id}"),
+        &session,
+        None,
+    )
+    .await;
+    assert_eq!(status, 204);
+
+    let (status, _, _) = send(&config.app, "GET", &format!("/roles/{id}"), &session, None).await;
+    assert_eq!(status, 404);
+}
+
+#[tokio::test]
+pub async fn routes_check_their_own_permission() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let (_, session) = auth(&config.admin, &mut config.connection).await;
+    let admin = format!("session={}", session.session);
+
+    let (status, _, role) = send(
+        &config.app,
+        "POST",
+        "/roles",
+        &admin,
+        Some(json!({ "name": "catalog", "permissions": ["catalog.write"] })),
+    )
+    .await;
+    assert_eq!(status, 201);
+    let id = role["id"].as_str().unwrap();
+
+    let staff = staff_session("staff@example.com", "catalog", &mut config.connection).await;
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/categories",
+        &staff,
+        Some(json!({ "name": "Shoes" })),
+    )
+    .await;
+    assert_eq!(status, 201);
+
+    let (status, _, message) = send(
+        &config.app,
+        "POST",
+        "/warehouses",
+        &staff,
+        Some(json!({ "name": "North" })),
+    )
+    .await;
+    assert_eq!(status, 403);
+    assert_eq!(message, "Missing permission stock.manage");
+
+    let (status, _, _) = send(&config.app, "GET", "/roles", &staff, None).await;
+    assert_eq!(status, 403);
+
+    // permissions are read on every request, so changes apply to open sessions
+    let (status, _, _) = send(
+        &config.app,
+        "PATCH",
+        &format!("/roles/{id}"),
+        &admin,
+        Some(json!({ "name": "catalog", "permissions": ["stock.manage"] })),
+    )
+    .await;
+    assert_eq!(status, 204);
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/warehouses",
+        &staff,
+        Some(json!({ "name": "North" })),
+    )
+    .await;
+    assert_eq!(status, 201);
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/categories",
+        &staff,
+        Some(json!({ "name": "Boots" })),
+    )
+    .await;
+    assert_eq!(status, 403);
+
+    let (status, _, _) = send(&config.app, "DELETE", &format!("/roles/{id}"), &admin, None).await;
+    assert_eq!(status, 409);
+
+    // members hold no permissions and never reach the admin routes
+    let member = staff_session("member@example.com", ROLES.member, &mut config.connection).await;
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/categories",
+        &member,
+        Some(json!({ "name": "Hats" })),
+    )
+    .await;
+    assert_eq!(status, 403);
+}
+
+#[tokio::test]
+pub async fn permissions_cant_be_escalated() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let (admin_user, session) = auth(&config.admin, &mut config.connection).await;
+    let admin = format!("session={}", session.session);
+
+    let (status, _, role) = send(
+        &config.app,
+        "POST",
+        "/roles",
+        &admin,
+        Some(json!({
+            "name": "staff",
+            "permissions": ["roles.manage", "users.manage", "catalog.write"],
+        })),
+    )
+    .await;
+    assert_eq!(status, 201);
+    let staff_role = role["id"].as_str().unwrap().to_string();
+
+    let (status, _, role) = send(
+        &config.app,
+        "POST",
+        "/roles",
+        &admin,
+        Some(json!({ "name": "stock", "permissions": ["stock.manage"] })),
+    )
+    .await;
+    assert_eq!(status, 201);
+    let stock_role = role["id"].as_str().unwrap().to_string();
+
+    let staff = staff_session("staff@example.com", "staff", &mut config.connection).await;
+    let staff_id = sqlx::query_scalar!("SELECT id FROM users WHERE email = 'staff@example.com'")
+        .fetch_one(&mut *config.connection)
+        .await
+        .unwrap();
+
+    // roles only grant what the caller holds, and their own stays as it is
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/roles",
+        &staff,
+        Some(json!({ "name": "stockist", "permissions": ["stock.manage"] })),
+    )
+    .await;
+    assert_eq!(status, 403);
+
+    let (status, _, role) = send(
+        &config.app,
+        "POST",
+        "/roles",
+        &staff,
+        Some(json!({ "name": "catalog", "permissions": ["catalog.write"] })),
+    )
+    .await;
+    assert_eq!(status, 201);
+    let catalog_role = role["id"].as_str().unwrap().to_string();
+
+    let (status, _, _) = send(
+        &config.app,
+        "PATCH",
+        &format!("/roles/{staff_role}"),
+        &staff,
+        Some(json!({ "name": "staff", "permissions": PERMISSION_NAMES })),
+    )
+    .await;
+    assert_eq!(status, 403);
+
+    let (status, _, _) = send(
+        &config.app,
+        "PATCH",
+        &format!("/roles/{stock_role}"),
+        &staff,
+        Some(json!({ "name": "stock", "permissions": [] })),
+    )
+    .await;
+    assert_eq!(status, 403);
+
+    // users can't be moved into or out of a role beyond the caller's own
+    let (status, _, _) = send(
+        &config.app,
+        "PATCH",
+        &format!("/users/{staff_id}"),
+        &staff,
+        Some(json!({ "email": "staff@example.com", "role_id": config.admin.id })),
+    )
+    .await;
+    assert_eq!(status, 403);
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/users",
+        &staff,
+        Some(json!({ "email": "new@example.com", "role_id": config.admin.id })),
+    )
+    .await;
+    assert_eq!(status, 403);
+
+    let (status, _, _) = send(
+        &config.app,
+        "PATCH",
+        &format!("/users/{}", admin_user.user.id),
+        &staff,
+        Some(json!({ "email": "staff@example.com", "role_id": config.admin.id })),
+    )
+    .await;
+    assert_eq!(status, 403);
+
+    let (status, _, _) = send(
+        &config.app,
+        "DELETE",
+        &format!("/users/{}", admin_user.user.id),
+        &staff,
+        None,
+    )
+    .await;
+    assert_eq!(status, 403);
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/users",
+        &staff,
+        Some(json!({ "email": "new@example.com", "role_id": catalog_role })),
+    )
+    .await;
+    assert_eq!(status, 201);
+}
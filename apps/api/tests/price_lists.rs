@@ -0,0 +1,245 @@
+use axum::{body::Body, http::Request};
+use common::{auth, init};
+use http_body_util::BodyExt;
+use rumerce::{
+    services,
+    validations::{
+        category::StoreCategorySchema,
+        image::StoreImageSchema,
+        product::{StoreProductSchema, Variant, VariantOption},
+        unit::StoreUnitSchema,
+    },
+};
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use tower::ServiceExt;
+
+mod common;
+
+#[tokio::test]
+pub async fn prices_resolve_per_customer_group() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let (user, session) = auth(&config.admin, &mut config.connection).await;
+
+    let unit = services::unit::insert(
+        &StoreUnitSchema {
+            name: "Unit 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let category = services::category::insert(
+        &StoreCategorySchema {
+            name: "Category 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let image = services::image::insert(
+        &StoreImageSchema {
+            name: "image.png".to_string(),
+            src: "image.png".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let input = StoreProductSchema {
+        name: "Product 1".to_string(),
+        slug: None,
+        description: None,
+        unit_id: unit.id,
+        category_id: category.id,
+        variants: vec![Variant {
+            options: vec![VariantOption {
+                key: "Size".to_string(),
+                value: "M".to_string(),
+            }],
+            price: 100.0,
+            sku: None,
+        }],
+        images: vec![image.id],
+    };
+
+    let product = services::product::insert(&input, &mut config.connection)
+        .await
+        .unwrap();
+
+    product
+        .attach_variants(&input.variants, &mut config.connection)
+        .await
+        .unwrap();
+
+    product
+        .attach_images(&input.images, &mut config.connection)
+        .await
+        .unwrap();
+
+    let variant_id = sqlx::query_scalar!("SELECT id FROM product_variants")
+        .fetch_one(&mut *config.connection)
+        .await
+        .unwrap();
+
+    let admin_request = |method: &str, uri: &str, body: serde_json::Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Cookie", format!("session={}", session.session))
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(admin_request(
+            "POST",
+            "/customer-groups",
+            serde_json::json!({ "name": "Wholesale" }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 201);
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let group: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(admin_request(
+            "POST",
+            "/price-lists",
+            serde_json::json!({
+                "name": "Wholesale",
+                "customer_group_id": group["id"],
+                "rules": [{ "kind": "percentage", "value": 10.0, "category_id": category.id }],
+            }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 201);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(admin_request(
+            "POST",
+            "/price-lists",
+            serde_json::json!({
+                "name": "Volume",
+                "rules": [{ "kind": "fixed", "value": 80.0, "min_quantity": 10, "variant_id": variant_id }],
+            }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 201);
+
+    // a rule needs exactly one target
+    let response = config
+        .app
+        .clone()
+        .oneshot(admin_request(
+            "POST",
+            "/price-lists",
+            serde_json::json!({
+                "name": "Broken",
+                "rules": [{ "kind": "fixed", "value": 80.0 }],
+            }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 400);
+
+    let show = |cookie: Option<String>| {
+        let request = Request::builder().uri(format!("/products/{}", product.id));
+
+        let request = match cookie {
+            Some(cookie) => request.header("Cookie", cookie),
+            None => request,
+        };
+
+        request.body(Body::empty()).unwrap()
+    };
+
+    let response = config.app.clone().oneshot(show(None)).await.unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body["variants"][0]["price"], 100.0);
+    assert_eq!(body["variants"][0]["tiers"][0]["min_quantity"], 10);
+    assert_eq!(body["variants"][0]["tiers"][0]["price"], 80.0);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(admin_request(
+            "PUT",
+            &format!("/users/{}/customer-group", user.user.id),
+            serde_json::json!({ "customer_group_id": group["id"] }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 204);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(show(Some(format!("session={}", session.session))))
+        .await
+        .unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body["variants"][0]["variant"]["price"], 100.0);
+    assert_eq!(body["variants"][0]["price"], 90.0);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(admin_request(
+            "POST",
+            "/carts",
+            serde_json::json!({ "variant_id": variant_id, "quantity": 10 }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/carts")
+                .header("Cookie", format!("session={}", session.session))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body[0]["unit_price"], 80.0);
+    assert_eq!(body[0]["total"], 800.0);
+}
@@ -0,0 +1,231 @@
+use axum::{body::Body, http::Request};
+use common::{auth, init};
+use http_body_util::BodyExt;
+use rumerce::{
+    models::product::Product,
+    services,
+    validations::{
+        category::StoreCategorySchema,
+        product::{StoreProductSchema, Variant, VariantOption},
+        unit::StoreUnitSchema,
+    },
+};
+use sqlx::PgConnection;
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use tower::ServiceExt;
+
+mod common;
+
+async fn products(connection: &mut PgConnection) -> Vec<Product> {
+    let unit = services::unit::insert(
+        &StoreUnitSchema {
+            name: "Unit 1".to_string(),
+        },
+        connection,
+    )
+    .await
+    .unwrap();
+
+    let category = services::category::insert(
+        &StoreCategorySchema {
+            name: "Category 1".to_string(),
+        },
+        connection,
+    )
+    .await
+    .unwrap();
+
+    let mut products = Vec::new();
+
+    for name in ["Product 1", "Product 2", "Product 3"] {
+        let input = StoreProductSchema {
+            name: name.to_string(),
+            slug: None,
+            description: None,
+            unit_id: unit.id,
+            category_id: category.id,
+            variants: vec![Variant {
+                options: vec![VariantOption {
+                    key: "Size".to_string(),
+                    value: name.to_string(),
+                }],
+                price: 10.0,
+                sku: None,
+            }],
+            images: vec![],
+        };
+
+        let product = services::product::insert(&input, connection).await.unwrap();
+
+        product
+            .attach_variants(&input.variants, connection)
+            .await
+            .unwrap();
+
+        products.push(product);
+    }
+
+    products
+}
+
+async fn json(response: axum::response::Response) -> serde_json::Value {
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&bytes[..]).unwrap()
+}
+
+#[tokio::test]
+pub async fn curated_and_fallback_links() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+    let (_, session) = auth(&config.admin, &mut config.connection).await;
+
+    let products = products(&mut config.connection).await;
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("PUT")
+                .uri(format!("/products/{}/links", products[0].id))
+                .header("Cookie", format!("session={}", session.session))
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    serde_json::json!({ "up_sell": [products[2].id], "related": [products[0].id] })
+                        .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 400);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("PUT")
+                .uri(format!("/products/{}/links", products[0].id))
+                .header("Cookie", format!("session={}", session.session))
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    serde_json::json!({ "up_sell": [products[2].id], "cross_sell": [products[1].id] })
+                        .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 204);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/products/{}/links", products[0].id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let body = json(response).await;
+
+    assert_eq!(body["up_sell"][0]["id"], products[2].id.to_string());
+    assert_eq!(body["cross_sell"][0]["id"], products[1].id.to_string());
+
+    // nothing curated, so the rest of the category is suggested
+    assert_eq!(body["related"].as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+pub async fn cart_cross_sells() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let products = products(&mut config.connection).await;
+
+    sqlx::query!(
+        "INSERT INTO product_links(product_id, linked_product_id, kind) VALUES ($1, $2, 'cross_sell'), ($1, $3, 'cross_sell')",
+        products[0].id,
+        products[1].id,
+        products[2].id
+    )
+    .execute(&mut *config.connection)
+    .await
+    .unwrap();
+
+    let variants = sqlx::query_scalar!(
+        "SELECT id FROM product_variants WHERE product_id = ANY($1)",
+        &vec![products[0].id, products[1].id]
+    )
+    .fetch_all(&mut *config.connection)
+    .await
+    .unwrap();
+
+    let mut cookie = String::new();
+
+    for variant in variants {
+        let mut request = Request::builder()
+            .method("POST")
+            .uri("/carts")
+            .header("Content-Type", "application/json");
+
+        if !cookie.is_empty() {
+            request = request.header("Cookie", &cookie);
+        }
+
+        let response = config
+            .app
+            .clone()
+            .oneshot(
+                request
+                    .body(Body::from(
+                        serde_json::json!({ "variant_id": variant, "quantity": 1 }).to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        cookie = response.headers()["set-cookie"]
+            .to_str()
+            .unwrap()
+            .to_string();
+    }
+
+    let response = config
+        .app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/carts/cross-sells")
+                .header("Cookie", &cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let body = json(response).await;
+
+    // product 2 is already in the cart
+    assert_eq!(body.as_array().unwrap().len(), 1);
+    assert_eq!(body[0]["id"], products[2].id.to_string());
+}
@@ -0,0 +1,246 @@
+use std::net::SocketAddr;
+
+use axum::{body::Body, extract::ConnectInfo, http::Request, Router};
+use chrono::{Duration, Utc};
+use common::init;
+use rumerce::{
+    services,
+    utils::{
+        constants::{PUBLIC_RATE_LIMITS, SIGN_IN_RATE_LIMITS},
+        rate_limit::{MemoryRateLimit, RateLimit},
+    },
+};
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use tower::ServiceExt;
+
+mod common;
+
+// what the server sees of the connection, oneshot requests have none otherwise
+fn peer(ip: &str) -> ConnectInfo<SocketAddr> {
+    ConnectInfo(SocketAddr::new(ip.parse().unwrap(), 443))
+}
+
+async fn sign_in(app: &Router, email: &str, ip: &str) -> axum::response::Response {
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/sign-in")
+                .header("Content-Type", "application/json")
+                .extension(peer(ip))
+                .body(Body::from(
+                    serde_json::json!({ "email": email }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+pub async fn sign_in_limited_by_email() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let config = init(&container).await;
+
+    let capacity = SIGN_IN_RATE_LIMITS[1].capacity;
+
+    // a different address each time, only the inbox is shared
+    for i in 0..capacity {
+        let response = sign_in(&config.app, "victim@example.com", &format!("10.0.0.{i}")).await;
+        assert_eq!(response.status(), 200);
+    }
+
+    let response = sign_in(&config.app, "Victim@Example.com ", "10.0.1.1").await;
+    assert_eq!(response.status(), 429);
+
+    let retry_after: i64 = response
+        .headers()
+        .get("retry-after")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(retry_after > 0);
+
+    assert_eq!(config.mails.lock().unwrap().len(), capacity as usize);
+
+    // other inboxes are unaffected
+    let response = sign_in(&config.app, "other@example.com", "10.0.1.1").await;
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+pub async fn sign_in_limited_by_ip() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let config = init(&container).await;
+
+    let capacity = SIGN_IN_RATE_LIMITS[0].capacity;
+
+    for i in 0..capacity {
+        let response = sign_in(&config.app, &format!("user{i}@example.com"), "10.0.0.1").await;
+        assert_eq!(response.status(), 200);
+    }
+
+    let response = sign_in(&config.app, "late@example.com", "10.0.0.1").await;
+    assert_eq!(response.status(), 429);
+    assert!(response.headers().get("retry-after").is_some());
+
+    // a forged header from the same connection doesn't get a fresh bucket
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/sign-in")
+                .header("Content-Type", "application/json")
+                .header("x-forwarded-for", "10.9.9.9")
+                .extension(peer("10.0.0.1"))
+                .body(Body::from(
+                    serde_json::json!({ "email": "late@example.com" }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 429);
+
+    let response = sign_in(&config.app, "late@example.com", "10.0.0.2").await;
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+pub async fn public_limited_by_ip() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let config = init(&container).await;
+
+    let request = |ip: &str| {
+        Request::builder()
+            .method("GET")
+            .uri("/currencies")
+            .extension(peer(ip))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let capacity = PUBLIC_RATE_LIMITS[0].capacity;
+
+    // the bucket keeps refilling while the loop runs, so a few more than capacity get through
+    let mut allowed = 0;
+    loop {
+        let response = config
+            .app
+            .clone()
+            .oneshot(request("10.0.0.1"))
+            .await
+            .unwrap();
+
+        if response.status() == 429 {
+            break;
+        }
+
+        assert_eq!(response.status(), 200);
+        allowed += 1;
+        assert!(allowed <= capacity * 2);
+    }
+
+    assert!(allowed >= capacity);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request("10.0.0.2"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+pub async fn buckets_refill() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let policy = SIGN_IN_RATE_LIMITS[1];
+    let now = Utc::now().naive_utc();
+    let later = now + Duration::seconds(policy.period / policy.capacity as i64 + 1);
+
+    // postgres backend
+    for _ in 0..policy.capacity {
+        let taken = services::rate_limit::take(
+            "test",
+            policy.capacity,
+            policy.period,
+            &now,
+            &mut config.connection,
+        )
+        .await
+        .unwrap();
+        assert!(taken.is_none());
+    }
+
+    let taken = services::rate_limit::take(
+        "test",
+        policy.capacity,
+        policy.period,
+        &now,
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+    assert_eq!(taken, Some(policy.period / policy.capacity as i64));
+
+    let taken = services::rate_limit::take(
+        "test",
+        policy.capacity,
+        policy.period,
+        &later,
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+    assert!(taken.is_none());
+
+    let swept = services::rate_limit::sweep(&later, &mut config.connection)
+        .await
+        .unwrap();
+    assert_eq!(swept, 0);
+
+    let swept =
+        services::rate_limit::sweep(&(later + Duration::seconds(1)), &mut config.connection)
+            .await
+            .unwrap();
+    assert_eq!(swept, 1);
+
+    // memory backend
+    let memory = MemoryRateLimit::default();
+
+    for _ in 0..policy.capacity {
+        assert!(memory.take("test", &policy, now).await.unwrap().is_none());
+    }
+
+    assert!(memory.take("test", &policy, now).await.unwrap().is_some());
+    assert!(memory.take("test", &policy, later).await.unwrap().is_none());
+    assert_eq!(memory.sweep(later + Duration::seconds(1)).await.unwrap(), 1);
+}
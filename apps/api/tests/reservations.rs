@@ -0,0 +1,363 @@
+use axum::{body::Body, http::Request};
+use common::{auth, init};
+use http_body_util::BodyExt;
+use rumerce::{
+    services,
+    validations::{
+        category::StoreCategorySchema,
+        image::StoreImageSchema,
+        product::{StoreProductSchema, Variant, VariantOption},
+        stock::StoreStockSchema,
+        unit::StoreUnitSchema,
+        warehouse::StoreWarehouseSchema,
+    },
+};
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use tower::ServiceExt;
+
+mod common;
+
+#[tokio::test]
+pub async fn checkout_holds_stock_until_paid_or_released() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let (_, session) = auth(&config.admin, &mut config.connection).await;
+
+    let unit = services::unit::insert(
+        &StoreUnitSchema {
+            name: "Unit 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let category = services::category::insert(
+        &StoreCategorySchema {
+            name: "Category 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let image = services::image::insert(
+        &StoreImageSchema {
+            name: "image.png".to_string(),
+            src: "image.png".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let input = StoreProductSchema {
+        name: "Product 1".to_string(),
+        slug: None,
+        description: None,
+        unit_id: unit.id,
+        category_id: category.id,
+        variants: vec![Variant {
+            options: vec![VariantOption {
+                key: "Size".to_string(),
+                value: "M".to_string(),
+            }],
+            price: 100.0,
+            sku: None,
+        }],
+        images: vec![image.id],
+    };
+
+    let product = services::product::insert(&input, &mut config.connection)
+        .await
+        .unwrap();
+
+    product
+        .attach_variants(&input.variants, &mut config.connection)
+        .await
+        .unwrap();
+
+    product
+        .attach_images(&input.images, &mut config.connection)
+        .await
+        .unwrap();
+
+    let variant_id = sqlx::query_scalar!("SELECT id FROM product_variants")
+        .fetch_one(&mut *config.connection)
+        .await
+        .unwrap();
+
+    let request = |method: &str, uri: &str, cookie: Option<&str>, body: serde_json::Value| {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Content-Type", "application/json");
+
+        let request = match cookie {
+            Some(cookie) => request.header("Cookie", cookie),
+            None => request,
+        };
+
+        request.body(Body::from(body.to_string())).unwrap()
+    };
+
+    let warehouse = services::warehouse::insert(
+        &StoreWarehouseSchema {
+            name: "Warehouse 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    services::stock::set(
+        &StoreStockSchema {
+            variant_id,
+            warehouse_id: warehouse.id,
+            quantity: 5,
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let admin = format!("session={}", session.session);
+
+    let app = config.app.clone();
+    let available = || {
+        let app = app.clone();
+        let uri = format!("/products/{}", product.id);
+
+        async move {
+            let response = app
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let bytes = response.into_body().collect().await.unwrap().to_bytes();
+            let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+            body["variants"][0]["available"].as_i64().unwrap()
+        }
+    };
+
+    assert_eq!(available().await, 5);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/carts",
+            None,
+            serde_json::json!({ "variant_id": variant_id, "quantity": 3 }),
+        ))
+        .await
+        .unwrap();
+
+    let cookie = response.headers()["set-cookie"]
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    // putting an item in the cart doesn't hold anything yet
+    assert_eq!(available().await, 5);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/carts/checkout",
+            Some(&cookie),
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let checkout: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+    let cart_id = checkout["cart_id"].as_str().unwrap().to_string();
+
+    assert_eq!(available().await, 2);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/carts",
+            None,
+            serde_json::json!({ "variant_id": variant_id, "quantity": 3 }),
+        ))
+        .await
+        .unwrap();
+
+    let other = response.headers()["set-cookie"]
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/carts/checkout",
+            Some(&other),
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 409);
+
+    // a failed payment gives the stock back
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "POST",
+            &format!("/checkouts/{}/failed", cart_id),
+            Some(&admin),
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(available().await, 5);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/carts/checkout",
+            Some(&cookie),
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(available().await, 2);
+
+    sqlx::query!(
+        "UPDATE stock_reservations SET expires_at = NOW() - INTERVAL '1 minute' WHERE expires_at IS NOT NULL"
+    )
+    .execute(&mut *config.connection)
+    .await
+    .unwrap();
+
+    assert_eq!(available().await, 5);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "POST",
+            &format!("/checkouts/{}/paid", cart_id),
+            Some(&admin),
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 409);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/carts/checkout",
+            Some(&cookie),
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    // changing the cart drops the hold
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/carts",
+            Some(&cookie),
+            serde_json::json!({ "variant_id": variant_id, "quantity": 1 }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(available().await, 5);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/carts/checkout",
+            Some(&cookie),
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(available().await, 1);
+
+    // a repeated callback can't deduct the same cart twice
+    let callbacks = (0..2).map(|_| {
+        config.app.clone().oneshot(request(
+            "POST",
+            &format!("/checkouts/{}/paid", cart_id),
+            Some(&admin),
+            serde_json::Value::Null,
+        ))
+    });
+    let mut statuses = futures::future::join_all(callbacks)
+        .await
+        .into_iter()
+        .map(|response| response.unwrap().status().as_u16())
+        .collect::<Vec<_>>();
+    statuses.sort();
+
+    assert_eq!(statuses, vec![200, 409]);
+    assert_eq!(available().await, 1);
+
+    let quantity = sqlx::query_scalar!("SELECT quantity FROM stocks")
+        .fetch_one(&mut *config.connection)
+        .await
+        .unwrap();
+
+    assert_eq!(quantity, 1);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "GET",
+            "/carts",
+            Some(&cookie),
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body, serde_json::json!([]));
+}
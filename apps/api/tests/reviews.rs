@@ -0,0 +1,225 @@
+use axum::{body::Body, http::Request};
+use chrono::Utc;
+use common::{auth, init};
+use http_body_util::BodyExt;
+use rumerce::{
+    services,
+    utils::constants::REVIEW_STATUSES,
+    validations::{
+        category::StoreCategorySchema, product::StoreProductSchema, stock::StoreStockSchema,
+        unit::StoreUnitSchema, warehouse::StoreWarehouseSchema,
+    },
+};
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+mod common;
+
+#[tokio::test]
+pub async fn review_product() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+    let (_, session) = auth(&config.member, &mut config.connection).await;
+
+    let unit = services::unit::insert(
+        &StoreUnitSchema {
+            name: "Unit 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let category = services::category::insert(
+        &StoreCategorySchema {
+            name: "Category 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let product = services::product::insert(
+        &StoreProductSchema {
+            name: "Product 1".to_string(),
+            slug: None,
+            description: None,
+            unit_id: unit.id,
+            category_id: category.id,
+            variants: vec![],
+            images: vec![],
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let review = |rating: i16| {
+        Request::builder()
+            .method("POST")
+            .uri(format!("/products/{}/reviews", product.id))
+            .header("Cookie", format!("session={}", session.session))
+            .header("Content-Type", "application/json")
+            .body(Body::from(
+                serde_json::json!({
+                    "rating": rating,
+                    "title": "Great",
+                    "body": "Works as expected"
+                })
+                .to_string(),
+            ))
+            .unwrap()
+    };
+
+    let response = config.app.clone().oneshot(review(6)).await.unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = config.app.clone().oneshot(review(4)).await.unwrap();
+    assert_eq!(response.status(), 201);
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+    assert_eq!(body["status"], REVIEW_STATUSES.pending);
+    assert_eq!(body["verified"], false);
+
+    let id = Uuid::parse_str(body["id"].as_str().unwrap()).unwrap();
+
+    let response = config.app.clone().oneshot(review(5)).await.unwrap();
+    assert_eq!(response.status(), 409);
+
+    let reviews = |app: axum::Router| {
+        app.oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/products/{}/reviews", product.id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+    };
+
+    // pending reviews stay hidden
+    let response = reviews(config.app.clone()).await.unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+    assert_eq!(body.as_array().unwrap().len(), 0);
+
+    services::review::update_status(&id, REVIEW_STATUSES.approved, &mut config.connection)
+        .await
+        .unwrap();
+
+    let response = reviews(config.app.clone()).await.unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+    assert_eq!(body.as_array().unwrap().len(), 1);
+    assert_eq!(body[0]["rating"], 4);
+
+    let ratings = services::review::ratings(&vec![product.id], &mut config.connection)
+        .await
+        .unwrap();
+    assert_eq!(ratings[0].count, 1);
+    assert_eq!(ratings[0].average, Some(4.0));
+
+    // once the author pays for one of the product's variants a new review is verified
+    let variant_id = sqlx::query_scalar!(
+        "INSERT INTO product_variants(price, product_id) VALUES (10, $1) RETURNING id",
+        product.id
+    )
+    .fetch_one(&mut *config.connection)
+    .await
+    .unwrap();
+
+    let warehouse = services::warehouse::insert(
+        &StoreWarehouseSchema {
+            name: "Warehouse 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    services::stock::set(
+        &StoreStockSchema {
+            variant_id,
+            warehouse_id: warehouse.id,
+            quantity: 5,
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/carts")
+                .header("Cookie", format!("session={}", session.session))
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    serde_json::json!({ "variant_id": variant_id, "quantity": 1 }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/carts/checkout")
+                .header("Cookie", format!("session={}", session.session))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let checkout: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+    let cart_id = Uuid::parse_str(checkout["cart_id"].as_str().unwrap()).unwrap();
+
+    services::review::destroy(&id, &mut config.connection)
+        .await
+        .unwrap();
+
+    // an order that was never paid doesn't count
+    let response = config.app.clone().oneshot(review(5)).await.unwrap();
+    assert_eq!(response.status(), 201);
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+    assert_eq!(body["verified"], false);
+
+    let id = Uuid::parse_str(body["id"].as_str().unwrap()).unwrap();
+
+    services::review::destroy(&id, &mut config.connection)
+        .await
+        .unwrap();
+
+    services::order::pay(&cart_id, &Utc::now().naive_utc(), &mut config.connection)
+        .await
+        .unwrap();
+
+    let response = config.app.clone().oneshot(review(5)).await.unwrap();
+    assert_eq!(response.status(), 201);
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+    assert_eq!(body["verified"], true);
+}
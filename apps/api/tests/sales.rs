@@ -0,0 +1,290 @@
+use axum::{body::Body, http::Request};
+use chrono::{Duration, Utc};
+use common::{auth, init};
+use http_body_util::BodyExt;
+use rumerce::{
+    services,
+    validations::{
+        category::StoreCategorySchema,
+        image::StoreImageSchema,
+        product::{StoreProductSchema, Variant, VariantOption},
+        unit::StoreUnitSchema,
+    },
+};
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use tower::ServiceExt;
+
+mod common;
+
+#[tokio::test]
+pub async fn sale_prices_switch_on_and_off() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let (_, session) = auth(&config.admin, &mut config.connection).await;
+
+    let unit = services::unit::insert(
+        &StoreUnitSchema {
+            name: "Unit 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let category = services::category::insert(
+        &StoreCategorySchema {
+            name: "Category 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let image = services::image::insert(
+        &StoreImageSchema {
+            name: "image.png".to_string(),
+            src: "image.png".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let input = StoreProductSchema {
+        name: "Product 1".to_string(),
+        slug: None,
+        description: None,
+        unit_id: unit.id,
+        category_id: category.id,
+        variants: vec![Variant {
+            options: vec![VariantOption {
+                key: "Size".to_string(),
+                value: "M".to_string(),
+            }],
+            price: 100.0,
+            sku: None,
+        }],
+        images: vec![image.id],
+    };
+
+    let product = services::product::insert(&input, &mut config.connection)
+        .await
+        .unwrap();
+
+    product
+        .attach_variants(&input.variants, &mut config.connection)
+        .await
+        .unwrap();
+
+    product
+        .attach_images(&input.images, &mut config.connection)
+        .await
+        .unwrap();
+
+    let variant_id = sqlx::query_scalar!("SELECT id FROM product_variants")
+        .fetch_one(&mut *config.connection)
+        .await
+        .unwrap();
+
+    let request = |method: &str, uri: &str, cookie: Option<&str>, body: serde_json::Value| {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Content-Type", "application/json");
+
+        let request = match cookie {
+            Some(cookie) => request.header("Cookie", cookie),
+            None => request,
+        };
+
+        request.body(Body::from(body.to_string())).unwrap()
+    };
+
+    let admin = format!("session={}", session.session);
+    let now = Utc::now().naive_utc();
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/sales",
+            Some(&admin),
+            serde_json::json!({
+                "name": "Black Friday",
+                "kind": "percentage",
+                "value": 25.0,
+                "category_id": category.id,
+                "starts_at": now - Duration::hours(1),
+                "ends_at": now + Duration::hours(1),
+            }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 201);
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let sale: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    // an old sale must not apply anymore
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/sales",
+            Some(&admin),
+            serde_json::json!({
+                "name": "Summer",
+                "kind": "fixed",
+                "value": 10.0,
+                "variant_id": variant_id,
+                "starts_at": now - Duration::days(30),
+                "ends_at": now - Duration::days(1),
+            }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 201);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "GET",
+            &format!("/products/{}", product.id),
+            None,
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body["variants"][0]["regular_price"], 100.0);
+    assert_eq!(body["variants"][0]["sale_price"], 75.0);
+    assert_eq!(body["variants"][0]["price"], 75.0);
+    assert_eq!(body["variants"][0]["sale_ends_at"], sale["ends_at"]);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/carts",
+            None,
+            serde_json::json!({ "variant_id": variant_id, "quantity": 2 }),
+        ))
+        .await
+        .unwrap();
+
+    let cookie = response.headers()["set-cookie"]
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "GET",
+            "/carts",
+            Some(&cookie),
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body[0]["unit_price"], 75.0);
+    assert_eq!(body[0]["total"], 150.0);
+
+    // end the sale early
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "PATCH",
+            &format!("/sales/{}", sale["id"].as_str().unwrap()),
+            Some(&admin),
+            serde_json::json!({
+                "name": "Black Friday",
+                "kind": "percentage",
+                "value": 25.0,
+                "category_id": category.id,
+                "starts_at": now - Duration::hours(2),
+                "ends_at": now - Duration::hours(1),
+            }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 204);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "GET",
+            "/carts",
+            Some(&cookie),
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body[0]["unit_price"], 100.0);
+    assert_eq!(body[0]["total"], 200.0);
+
+    // a fixed sale above the regular price isn't advertised
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/sales",
+            Some(&admin),
+            serde_json::json!({
+                "name": "Typo",
+                "kind": "fixed",
+                "value": 150.0,
+                "variant_id": variant_id,
+                "starts_at": now - Duration::hours(1),
+                "ends_at": now + Duration::hours(1),
+            }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 201);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "GET",
+            &format!("/products/{}", product.id),
+            None,
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body["variants"][0]["price"], 100.0);
+    assert!(body["variants"][0]["sale_price"].is_null());
+    assert!(body["variants"][0]["sale_ends_at"].is_null());
+}
@@ -0,0 +1,293 @@
+use std::net::SocketAddr;
+
+use axum::{
+    body::Body,
+    http::{HeaderMap, Request},
+    Router,
+};
+use chrono::{Duration, Utc};
+use common::{auth, init, init_with};
+use http_body_util::BodyExt;
+use rumerce::{
+    services,
+    utils::{
+        client,
+        constants::{CART_COOKIE_NAME, SESSION_COOKIE_NAME, SESSION_MAX_AGE},
+    },
+    validations::{auth::StoreSessionSchema, user::StoreUserSchema},
+};
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+mod common;
+
+#[tokio::test]
+pub async fn sessions_expire_renew_and_revoke() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let (user, session) = auth(&config.member, &mut config.connection).await;
+
+    let phone = services::sessions::insert(
+        StoreSessionSchema {
+            session: Uuid::new_v4(),
+            user_id: user.user.id,
+            expires_at: Utc::now().naive_utc() + Duration::seconds(SESSION_MAX_AGE),
+            user_agent: Some("Phone".to_string()),
+            ip: Some("10.0.0.1".to_string()),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let other = services::user::insert(
+        &StoreUserSchema {
+            name: None,
+            email: "other@example.com".to_string(),
+            role_id: config.member.id,
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let stranger = services::sessions::insert(
+        StoreSessionSchema {
+            session: Uuid::new_v4(),
+            user_id: other.user.id,
+            expires_at: Utc::now().naive_utc() + Duration::seconds(SESSION_MAX_AGE),
+            user_agent: None,
+            ip: None,
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let request = |method: &str, uri: &str, session: &Uuid| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Cookie", format!("session={}", session))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request("GET", "/sessions", &session.session))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    // just created, nothing to renew
+    assert!(response.headers().get("set-cookie").is_none());
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+    let devices = body.as_array().unwrap();
+
+    assert_eq!(devices.len(), 2);
+    assert!(devices.iter().all(|device| device.get("session").is_none()));
+
+    let current = devices
+        .iter()
+        .find(|device| device["current"] == true)
+        .unwrap();
+    assert_eq!(current["id"], session.id.to_string());
+
+    let device = devices
+        .iter()
+        .find(|device| device["current"] == false)
+        .unwrap();
+    assert_eq!(device["user_agent"], "Phone");
+    assert_eq!(device["ip"], "10.0.0.1");
+
+    // activity after a while pushes the expiry back
+    sqlx::query!(
+        "UPDATE sessions SET last_seen_at = $2, expires_at = $3 WHERE id = $1",
+        session.id,
+        Utc::now().naive_utc() - Duration::hours(1),
+        Utc::now().naive_utc() + Duration::hours(1)
+    )
+    .execute(&mut *config.connection)
+    .await
+    .unwrap();
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request("GET", "/profile", &session.session))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    assert!(response.headers()["set-cookie"]
+        .to_str()
+        .unwrap()
+        .starts_with(&format!("session={}", session.session)));
+
+    let expires_at =
+        sqlx::query_scalar!("SELECT expires_at FROM sessions WHERE id = $1", session.id)
+            .fetch_one(&mut *config.connection)
+            .await
+            .unwrap();
+
+    assert!(expires_at > Utc::now().naive_utc() + Duration::days(29));
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "DELETE",
+            &format!("/sessions/{}", stranger.id),
+            &session.session,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 404);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "DELETE",
+            &format!("/sessions/{}", phone.id),
+            &session.session,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request("GET", "/profile", &phone.session))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 401);
+
+    sqlx::query!(
+        "UPDATE sessions SET expires_at = $2 WHERE id = $1",
+        session.id,
+        Utc::now().naive_utc() - Duration::minutes(1)
+    )
+    .execute(&mut *config.connection)
+    .await
+    .unwrap();
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request("GET", "/profile", &session.session))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 401);
+}
+
+async fn profile(app: &Router, cookie: &str) -> u16 {
+    let request = Request::builder()
+        .uri("/profile")
+        .header("Cookie", format!("{}={}", SESSION_COOKIE_NAME, cookie))
+        .body(Body::empty())
+        .unwrap();
+
+    app.clone()
+        .oneshot(request)
+        .await
+        .unwrap()
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+pub async fn session_cookies_are_signed() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init_with(&container, |env| {
+        env.app_secret = "current-secret-for-signing-cookies".to_string();
+        env.app_previous_secrets = vec!["retired-secret-for-signing-cookies".to_string()];
+    })
+    .await;
+
+    let (_, session) = auth(&config.member, &mut config.connection).await;
+    let id = session.session.to_string();
+
+    let signed = client::signed_value(&config.env, SESSION_COOKIE_NAME, &id);
+    assert_eq!(profile(&config.bare_app, &signed).await, 200);
+
+    // the bare id, or one with a signature that doesn't match, is no session at all
+    assert_eq!(profile(&config.bare_app, &id).await, 401);
+
+    let (_, signature) = signed.rsplit_once('.').unwrap();
+    let forged = format!("{}.{}", Uuid::new_v4(), signature);
+    assert_eq!(profile(&config.bare_app, &forged).await, 401);
+
+    // a cart cookie can't be passed off as a session
+    let cart = client::signed_value(&config.env, CART_COOKIE_NAME, &id);
+    assert_eq!(profile(&config.bare_app, &cart).await, 401);
+
+    // cookies from before the secret changed keep working until it is dropped
+    let mut retired = config.env.clone();
+    retired.app_secret = "retired-secret-for-signing-cookies".to_string();
+    let old = client::signed_value(&retired, SESSION_COOKIE_NAME, &id);
+    assert_eq!(profile(&config.bare_app, &old).await, 200);
+
+    let mut unknown = config.env.clone();
+    unknown.app_secret = "unknown-secret-for-signing-cookies".to_string();
+    let stolen = client::signed_value(&unknown, SESSION_COOKIE_NAME, &id);
+    assert_eq!(profile(&config.bare_app, &stolen).await, 401);
+}
+
+#[tokio::test]
+pub async fn device_ip_only_trusts_known_proxies() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let config = init_with(&container, |env| {
+        env.trusted_proxies = vec!["10.0.0.1".parse().unwrap()];
+    })
+    .await;
+
+    let mut headers = HeaderMap::new();
+    headers.insert("x-forwarded-for", "1.1.1.1, 2.2.2.2".parse().unwrap());
+
+    let ip = |headers: &HeaderMap, peer: [u8; 4]| {
+        client::trusted_ip(&config.env, headers, Some(SocketAddr::from((peer, 443))))
+            .map(|ip| ip.to_string())
+    };
+
+    // straight from the client, the header is whatever it wanted to send
+    assert_eq!(ip(&headers, [3, 3, 3, 3]).as_deref(), Some("3.3.3.3"));
+
+    // through the proxy, only the hop it appended counts
+    assert_eq!(ip(&headers, [10, 0, 0, 1]).as_deref(), Some("2.2.2.2"));
+
+    headers.insert("x-forwarded-for", "1.1.1.1, 10.0.0.1".parse().unwrap());
+    assert_eq!(ip(&headers, [10, 0, 0, 1]).as_deref(), Some("1.1.1.1"));
+
+    headers.insert("x-forwarded-for", "nonsense".parse().unwrap());
+    assert_eq!(ip(&headers, [10, 0, 0, 1]).as_deref(), Some("10.0.0.1"));
+
+    assert_eq!(client::trusted_ip(&config.env, &headers, None), None);
+}
@@ -0,0 +1,296 @@
+use axum::{body::Body, http::Request};
+use common::{auth, init};
+use http_body_util::BodyExt;
+use rumerce::{
+    services,
+    validations::{
+        category::StoreCategorySchema,
+        image::StoreImageSchema,
+        product::{StoreProductSchema, Variant, VariantOption},
+        stock::StoreStockSchema,
+        unit::StoreUnitSchema,
+        warehouse::StoreWarehouseSchema,
+    },
+};
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use tower::ServiceExt;
+
+mod common;
+
+#[tokio::test]
+pub async fn one_alert_per_threshold_crossing() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let (_, session) = auth(&config.admin, &mut config.connection).await;
+
+    let unit = services::unit::insert(
+        &StoreUnitSchema {
+            name: "Unit 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let category = services::category::insert(
+        &StoreCategorySchema {
+            name: "Category 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let image = services::image::insert(
+        &StoreImageSchema {
+            name: "image.png".to_string(),
+            src: "image.png".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let input = StoreProductSchema {
+        name: "Product 1".to_string(),
+        slug: None,
+        description: None,
+        unit_id: unit.id,
+        category_id: category.id,
+        variants: vec![Variant {
+            options: vec![VariantOption {
+                key: "Size".to_string(),
+                value: "M".to_string(),
+            }],
+            price: 100.0,
+            sku: None,
+        }],
+        images: vec![image.id],
+    };
+
+    let product = services::product::insert(&input, &mut config.connection)
+        .await
+        .unwrap();
+
+    product
+        .attach_variants(&input.variants, &mut config.connection)
+        .await
+        .unwrap();
+
+    product
+        .attach_images(&input.images, &mut config.connection)
+        .await
+        .unwrap();
+
+    let variant_id = sqlx::query_scalar!("SELECT id FROM product_variants")
+        .fetch_one(&mut *config.connection)
+        .await
+        .unwrap();
+
+    let request = |method: &str, uri: &str, cookie: Option<&str>, body: serde_json::Value| {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Content-Type", "application/json");
+
+        let request = match cookie {
+            Some(cookie) => request.header("Cookie", cookie),
+            None => request,
+        };
+
+        request.body(Body::from(body.to_string())).unwrap()
+    };
+
+    let warehouse = services::warehouse::insert(
+        &StoreWarehouseSchema {
+            name: "Warehouse 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    services::stock::set(
+        &StoreStockSchema {
+            variant_id,
+            warehouse_id: warehouse.id,
+            quantity: 5,
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let admin = format!("session={}", session.session);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            "/stocks/alert-recipients",
+            Some(&admin),
+            serde_json::json!({ "emails": ["stock@example.com", "nope"] }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 400);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            "/stocks/alert-recipients",
+            Some(&admin),
+            serde_json::json!({ "emails": ["stock@example.com", "buyer@example.com"] }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            "/stocks/threshold",
+            Some(&admin),
+            serde_json::json!({
+                "variant_id": variant_id,
+                "warehouse_id": uuid::Uuid::new_v4(),
+                "reorder_threshold": 3,
+            }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 404);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            "/stocks/threshold",
+            Some(&admin),
+            serde_json::json!({
+                "variant_id": variant_id,
+                "warehouse_id": warehouse.id,
+                "reorder_threshold": 3,
+            }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(config.mails.lock().unwrap().len(), 0);
+
+    let set = |quantity: i32| {
+        request(
+            "PUT",
+            "/stocks",
+            Some(&admin),
+            serde_json::json!({
+                "variant_id": variant_id,
+                "warehouse_id": warehouse.id,
+                "quantity": quantity,
+            }),
+        )
+    };
+
+    let response = config.app.clone().oneshot(set(2)).await.unwrap();
+    assert_eq!(response.status(), 200);
+
+    {
+        let mails = config.mails.lock().unwrap();
+        assert_eq!(mails.len(), 1);
+        assert_eq!(
+            mails[0]["to"],
+            serde_json::json!(["stock@example.com", "buyer@example.com"])
+        );
+        assert!(mails[0]["html"].as_str().unwrap().contains("Product 1"));
+    }
+
+    // still below, same crossing
+    config.app.clone().oneshot(set(1)).await.unwrap();
+    assert_eq!(config.mails.lock().unwrap().len(), 1);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "GET",
+            "/stocks/low",
+            Some(&admin),
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body.as_array().unwrap().len(), 1);
+    assert_eq!(body[0]["quantity"], 1);
+    assert_eq!(body[0]["warehouse"], "Warehouse 1");
+
+    // back up then down again is a new crossing
+    config.app.clone().oneshot(set(3)).await.unwrap();
+    assert_eq!(config.mails.lock().unwrap().len(), 1);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "GET",
+            "/stocks/low",
+            Some(&admin),
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body, serde_json::json!([]));
+
+    config.app.clone().oneshot(set(0)).await.unwrap();
+    assert_eq!(config.mails.lock().unwrap().len(), 2);
+
+    // nobody to mail leaves the crossing for whoever is listed next
+    services::stock::set_alert_recipients(&[], &mut config.connection)
+        .await
+        .unwrap();
+
+    config.app.clone().oneshot(set(3)).await.unwrap();
+    config.app.clone().oneshot(set(1)).await.unwrap();
+    assert_eq!(config.mails.lock().unwrap().len(), 2);
+
+    let alerted = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM stocks WHERE alerted_at IS NOT NULL"#
+    )
+    .fetch_one(&mut *config.connection)
+    .await
+    .unwrap();
+
+    assert_eq!(alerted, 0);
+
+    services::stock::set_alert_recipients(
+        &["stock@example.com".to_string()],
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    config.app.clone().oneshot(set(0)).await.unwrap();
+    assert_eq!(config.mails.lock().unwrap().len(), 3);
+}
@@ -0,0 +1,111 @@
+use rumerce::utils::storage::{LocalStorage, S3Storage, Storage};
+use s3::{creds::Credentials, Bucket, BucketConfiguration, Region};
+use testcontainers::runners::AsyncRunner;
+use testcontainers_modules::minio::MinIO;
+use uuid::Uuid;
+
+#[tokio::test]
+pub async fn local_storage_round_trip() {
+    let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+    let storage = LocalStorage::new(&root);
+
+    storage
+        .put("image.png", b"content", "image/png")
+        .await
+        .unwrap();
+
+    assert_eq!(storage.get("image.png").await.unwrap(), b"content");
+    assert_eq!(storage.url("image.png").await.unwrap(), "/public/image.png");
+    assert_eq!(storage.local_root(), Some(root.as_path()));
+
+    storage.delete("image.png").await.unwrap();
+
+    assert!(storage.get("image.png").await.is_err());
+}
+
+#[tokio::test]
+pub async fn local_storage_invalid_key() {
+    let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+    let storage = LocalStorage::new(&root);
+
+    assert!(storage
+        .put("../image.png", b"content", "image/png")
+        .await
+        .is_err());
+    assert!(storage.get("/etc/passwd").await.is_err());
+}
+
+#[tokio::test]
+pub async fn s3_storage_round_trip() {
+    let container = MinIO::default().start().await.unwrap();
+    let endpoint = format!(
+        "http://localhost:{}",
+        container.get_host_port_ipv4(9000).await.unwrap()
+    );
+
+    let region = Region::Custom {
+        region: "us-east-1".to_string(),
+        endpoint: endpoint.clone(),
+    };
+
+    let credentials = Credentials {
+        access_key: Some("minioadmin".to_string()),
+        secret_key: Some("minioadmin".to_string()),
+        security_token: None,
+        session_token: None,
+        expiration: None,
+    };
+
+    Bucket::create_with_path_style(
+        "images",
+        region,
+        credentials,
+        BucketConfiguration::default(),
+    )
+    .await
+    .unwrap();
+
+    let storage = S3Storage::new(
+        "images",
+        "us-east-1",
+        &endpoint,
+        "minioadmin",
+        "minioadmin",
+        None,
+    )
+    .unwrap();
+
+    storage
+        .put("image.png", b"content", "image/png")
+        .await
+        .unwrap();
+
+    assert_eq!(storage.get("image.png").await.unwrap(), b"content");
+
+    let url = storage.url("image.png").await.unwrap();
+    assert!(url.starts_with(&format!("{}/images/image.png?", endpoint)));
+    assert!(url.contains("X-Amz-Signature="));
+    assert!(storage.local_root().is_none());
+
+    storage.delete("image.png").await.unwrap();
+
+    assert!(storage.get("image.png").await.is_err());
+}
+
+#[tokio::test]
+pub async fn s3_storage_public_url() {
+    let storage = S3Storage::new(
+        "images",
+        "us-east-1",
+        "http://localhost:9000",
+        "minioadmin",
+        "minioadmin",
+        Some("https://cdn.example.com/".to_string()),
+    )
+    .unwrap();
+
+    assert_eq!(
+        storage.url("image.png").await.unwrap(),
+        "https://cdn.example.com/image.png"
+    );
+}
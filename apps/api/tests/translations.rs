@@ -0,0 +1,271 @@
+use axum::{body::Body, http::Request};
+use common::{auth, init};
+use http_body_util::BodyExt;
+use rumerce::{
+    services,
+    validations::{
+        category::StoreCategorySchema,
+        image::StoreImageSchema,
+        product::{StoreProductSchema, Variant, VariantOption},
+        unit::StoreUnitSchema,
+    },
+};
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use tower::ServiceExt;
+
+mod common;
+
+#[tokio::test]
+pub async fn content_follows_the_requested_locale() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let (_, session) = auth(&config.admin, &mut config.connection).await;
+
+    let unit = services::unit::insert(
+        &StoreUnitSchema {
+            name: "Unit 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let category = services::category::insert(
+        &StoreCategorySchema {
+            name: "Category 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let image = services::image::insert(
+        &StoreImageSchema {
+            name: "image.png".to_string(),
+            src: "image.png".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let input = StoreProductSchema {
+        name: "Product 1".to_string(),
+        slug: None,
+        description: None,
+        unit_id: unit.id,
+        category_id: category.id,
+        variants: vec![Variant {
+            options: vec![VariantOption {
+                key: "Size".to_string(),
+                value: "M".to_string(),
+            }],
+            price: 100.0,
+            sku: None,
+        }],
+        images: vec![image.id],
+    };
+
+    let product = services::product::insert(&input, &mut config.connection)
+        .await
+        .unwrap();
+
+    product
+        .attach_variants(&input.variants, &mut config.connection)
+        .await
+        .unwrap();
+
+    product
+        .attach_images(&input.images, &mut config.connection)
+        .await
+        .unwrap();
+
+    let request = |method: &str, uri: &str, cookie: Option<&str>, body: serde_json::Value| {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Content-Type", "application/json");
+
+        let request = match cookie {
+            Some(cookie) => request.header("Cookie", cookie),
+            None => request,
+        };
+
+        request.body(Body::from(body.to_string())).unwrap()
+    };
+
+    let admin = format!("session={}", session.session);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            &format!("/products/{}/translations", product.id),
+            Some(&admin),
+            serde_json::json!({ "locale": "fr_FR", "name": "Produit 1" }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 400);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            &format!("/products/{}/translations", product.id),
+            Some(&admin),
+            serde_json::json!({
+                "locale": "fr",
+                "name": "Produit 1",
+                "options": [
+                    { "source": "Size", "name": "Taille" },
+                ],
+            }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            &format!("/categories/{}/translations", category.id),
+            Some(&admin),
+            serde_json::json!({ "locale": "fr", "name": "Catégorie 1" }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            &format!("/units/{}/translations", unit.id),
+            Some(&admin),
+            serde_json::json!({ "locale": "de", "name": "Einheit 1" }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/products/{}", product.id))
+                .header("Accept-Language", "fr-CH, fr;q=0.9, en;q=0.8")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body["product"]["name"], "Produit 1");
+    assert_eq!(body["category"]["name"], "Catégorie 1");
+    // no french unit, so the default locale is kept
+    assert_eq!(body["unit"]["name"], "Unit 1");
+    assert_eq!(
+        body["variants"][0]["collections"][0]["key"]["name"],
+        "Taille"
+    );
+    assert_eq!(body["variants"][0]["collections"][0]["value"]["name"], "M");
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/units?locale=de")
+                .header("Accept-Language", "fr")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body[0]["name"], "Einheit 1");
+
+    // the default locale never looks further
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/categories/{}", category.id))
+                .header("Accept-Language", "en, fr;q=0.5")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body["name"], "Category 1");
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request("GET", "/locales", None, serde_json::Value::Null))
+        .await
+        .unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body["default"], "en");
+    assert_eq!(body["available"], serde_json::json!(["de", "fr"]));
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "DELETE",
+            &format!("/products/{}/translations/fr", product.id),
+            Some(&admin),
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(request(
+            "GET",
+            &format!("/products/{}?locale=fr", product.id),
+            None,
+            serde_json::Value::Null,
+        ))
+        .await
+        .unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body["product"]["name"], "Product 1");
+    assert_eq!(body["category"]["name"], "Catégorie 1");
+    assert_eq!(body["variants"][0]["collections"][0]["key"]["name"], "Size");
+}
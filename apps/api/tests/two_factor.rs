@@ -0,0 +1,290 @@
+use chrono::{Duration, Utc};
+use common::{auth, init, send};
+use rumerce::{
+    services,
+    utils::{
+        constants::{RECOVERY_CODE_COUNT, SESSION_MAX_AGE, TWO_FACTOR_RATE_LIMITS},
+        totp,
+    },
+    validations::auth::StoreSessionSchema,
+};
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use uuid::Uuid;
+
+mod common;
+
+async fn new_session(user_id: &Uuid, connection: &mut sqlx::PgConnection) -> String {
+    let session = services::sessions::insert(
+        StoreSessionSchema {
+            session: Uuid::new_v4(),
+            user_id: *user_id,
+            expires_at: Utc::now().naive_utc() + Duration::seconds(SESSION_MAX_AGE),
+            user_agent: None,
+            ip: None,
+        },
+        connection,
+    )
+    .await
+    .unwrap()
+    .session;
+
+    format!("session={}", session)
+}
+
+#[tokio::test]
+pub async fn two_factor_guards_admin_routes() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let (user, session) = auth(&config.admin, &mut config.connection).await;
+    let session = format!("session={}", session.session);
+
+    let (status, _, _) = send(&config.app, "GET", "/users", &session, None).await;
+    assert_eq!(status, 200);
+
+    let (status, _, enrollment) = send(&config.app, "POST", "/two-factor", &session, None).await;
+    assert_eq!(status, 200);
+    assert!(enrollment["uri"]
+        .as_str()
+        .unwrap()
+        .starts_with("otpauth://totp/"));
+
+    let factor = services::two_factor::find(&user.user.id, &mut config.connection)
+        .await
+        .unwrap()
+        .unwrap();
+    let secret = totp::open(config.env.secrets(), &factor.secret).unwrap();
+    assert_eq!(enrollment["secret"], totp::base32(&secret));
+    // only the sealed secret is stored
+    assert!(factor.sealed);
+    assert_ne!(factor.secret, secret);
+
+    // enrolling without confirming changes nothing yet
+    let (status, _, _) = send(&config.app, "GET", "/users", &session, None).await;
+    assert_eq!(status, 200);
+
+    let step = totp::step(Utc::now().timestamp());
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/two-factor/confirm",
+        &session,
+        Some(serde_json::json!({ "code": "nope" })),
+    )
+    .await;
+    assert_eq!(status, 400);
+
+    let (status, _, codes) = send(
+        &config.app,
+        "POST",
+        "/two-factor/confirm",
+        &session,
+        Some(serde_json::json!({ "code": totp::code(&secret, step) })),
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    let codes: Vec<String> = serde_json::from_value(codes["codes"].clone()).unwrap();
+    assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+
+    // confirming counts for the session it happened on
+    let (status, _, _) = send(&config.app, "GET", "/users", &session, None).await;
+    assert_eq!(status, 200);
+
+    let other = new_session(&user.user.id, &mut config.connection).await;
+
+    let (status, _, body) = send(&config.app, "GET", "/users", &other, None).await;
+    assert_eq!(status, 403);
+    assert_eq!(body, "Second factor required");
+
+    // the code used to confirm can't be replayed
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/two-factor/verify",
+        &other,
+        Some(serde_json::json!({ "code": totp::code(&secret, step) })),
+    )
+    .await;
+    assert_eq!(status, 400);
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/two-factor/verify",
+        &other,
+        Some(serde_json::json!({ "code": codes[0].to_uppercase() })),
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    let (status, _, _) = send(&config.app, "GET", "/users", &other, None).await;
+    assert_eq!(status, 200);
+
+    let third = new_session(&user.user.id, &mut config.connection).await;
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/two-factor/verify",
+        &third,
+        Some(serde_json::json!({ "code": codes[0] })),
+    )
+    .await;
+    assert_eq!(status, 400);
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/two-factor/verify",
+        &third,
+        Some(serde_json::json!({ "code": totp::code(&secret, step + 1) })),
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    let (status, _, body) = send(&config.app, "GET", "/two-factor", &third, None).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["enabled"], true);
+    assert_eq!(body["verified"], true);
+    assert_eq!(body["recovery_codes"], RECOVERY_CODE_COUNT as i64 - 1);
+
+    let (status, _, _) = send(
+        &config.app,
+        "DELETE",
+        "/two-factor",
+        &third,
+        Some(serde_json::json!({ "code": codes[1] })),
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    let fourth = new_session(&user.user.id, &mut config.connection).await;
+
+    let (status, _, _) = send(&config.app, "GET", "/users", &fourth, None).await;
+    assert_eq!(status, 200);
+}
+
+#[tokio::test]
+pub async fn two_factor_required_setting() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let (user, session) = auth(&config.admin, &mut config.connection).await;
+    let session = format!("session={}", session.session);
+
+    let (status, _, _) = send(
+        &config.app,
+        "PUT",
+        "/two-factor/required",
+        &session,
+        Some(serde_json::json!({ "required": true })),
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    let (status, _, body) = send(&config.app, "GET", "/users", &session, None).await;
+    assert_eq!(status, 403);
+    assert_eq!(body, "Two-factor enrollment required");
+
+    let (status, _, body) = send(&config.app, "GET", "/two-factor", &session, None).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["required"], true);
+    assert_eq!(body["enabled"], false);
+
+    let (status, _, _) = send(&config.app, "POST", "/two-factor", &session, None).await;
+    assert_eq!(status, 200);
+
+    let factor = services::two_factor::find(&user.user.id, &mut config.connection)
+        .await
+        .unwrap()
+        .unwrap();
+    let secret = totp::open(config.env.secrets(), &factor.secret).unwrap();
+
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/two-factor/confirm",
+        &session,
+        Some(serde_json::json!({
+            "code": totp::code(&secret, totp::step(Utc::now().timestamp()))
+        })),
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    let (status, _, _) = send(&config.app, "GET", "/users", &session, None).await;
+    assert_eq!(status, 200);
+}
+
+#[tokio::test]
+pub async fn two_factor_guesses_capped() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let (user, session) = auth(&config.admin, &mut config.connection).await;
+    let session = format!("session={}", session.session);
+
+    let (status, _, _) = send(&config.app, "POST", "/two-factor", &session, None).await;
+    assert_eq!(status, 200);
+
+    let factor = services::two_factor::find(&user.user.id, &mut config.connection)
+        .await
+        .unwrap()
+        .unwrap();
+    let secret = totp::open(config.env.secrets(), &factor.secret).unwrap();
+
+    for _ in 0..TWO_FACTOR_RATE_LIMITS[0].capacity {
+        let (status, _, _) = send(
+            &config.app,
+            "POST",
+            "/two-factor/confirm",
+            &session,
+            Some(serde_json::json!({ "code": "nope" })),
+        )
+        .await;
+        assert_eq!(status, 400);
+    }
+
+    // even the right code waits now
+    let (status, _, _) = send(
+        &config.app,
+        "POST",
+        "/two-factor/confirm",
+        &session,
+        Some(serde_json::json!({
+            "code": totp::code(&secret, totp::step(Utc::now().timestamp()))
+        })),
+    )
+    .await;
+    assert_eq!(status, 429);
+}
+
+#[test]
+pub fn totp_matches_rfc_6238() {
+    // sha1 test vectors from the rfc, truncated to six digits
+    let secret = b"12345678901234567890";
+
+    assert_eq!(totp::code(secret, totp::step(59)), "287082");
+    assert_eq!(totp::code(secret, totp::step(1111111109)), "081804");
+    assert_eq!(totp::code(secret, totp::step(20000000000)), "353130");
+
+    assert_eq!(totp::base32(b"foobar"), "MZXW6YTBOI");
+}
@@ -0,0 +1,364 @@
+use axum::{body::Body, http::Request};
+use common::{auth, init};
+use http_body_util::BodyExt;
+use rumerce::{
+    services,
+    validations::{
+        category::StoreCategorySchema,
+        product::{StoreProductSchema, Variant, VariantOption},
+        unit::StoreUnitSchema,
+    },
+};
+use testcontainers::{runners::AsyncRunner, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use tower::ServiceExt;
+
+mod common;
+
+#[tokio::test]
+pub async fn guest_wishlist_to_cart() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let unit = services::unit::insert(
+        &StoreUnitSchema {
+            name: "Unit 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let category = services::category::insert(
+        &StoreCategorySchema {
+            name: "Category 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let input = StoreProductSchema {
+        name: "Product 1".to_string(),
+        slug: None,
+        description: None,
+        unit_id: unit.id,
+        category_id: category.id,
+        variants: vec![Variant {
+            options: vec![VariantOption {
+                key: "Size".to_string(),
+                value: "M".to_string(),
+            }],
+            price: 10.0,
+            sku: None,
+        }],
+        images: vec![],
+    };
+
+    let product = services::product::insert(&input, &mut config.connection)
+        .await
+        .unwrap();
+
+    product
+        .attach_variants(&input.variants, &mut config.connection)
+        .await
+        .unwrap();
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/wishlists")
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    serde_json::json!({ "product_id": product.id }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 201);
+
+    let cookie = response.headers()["set-cookie"]
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/wishlists")
+                .header("Cookie", &cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+
+    assert_eq!(body[0]["product"]["id"], product.id.to_string());
+    assert!(body[0]["variant"].is_null());
+
+    let item_id = body[0]["item"]["id"].as_str().unwrap().to_string();
+
+    let variants = sqlx::query_scalar!("SELECT id FROM product_variants")
+        .fetch_all(&mut *config.connection)
+        .await
+        .unwrap();
+
+    let move_to_cart = |body: serde_json::Value| {
+        Request::builder()
+            .method("POST")
+            .uri(format!("/wishlists/{}/cart", item_id))
+            .header("Cookie", &cookie)
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    // a product needs a variant picked
+    let response = config
+        .app
+        .clone()
+        .oneshot(move_to_cart(serde_json::json!({ "quantity": 1 })))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 400);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(move_to_cart(
+            serde_json::json!({ "quantity": 2, "variant_id": variants[0] }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let cart_cookie = response.headers()["set-cookie"]
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/wishlists")
+                .header("Cookie", &cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+    assert_eq!(body.as_array().unwrap().len(), 0);
+
+    let response = config
+        .app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/carts")
+                .header("Cookie", &cart_cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+    assert_eq!(body[0]["quantity"], 2);
+    assert_eq!(body[0]["variant_id"], variants[0].to_string());
+}
+
+#[tokio::test]
+pub async fn guest_wishlist_carries_over_on_sign_in() {
+    let container = Postgres::default()
+        .with_tag("latest")
+        .start()
+        .await
+        .unwrap();
+
+    let mut config = init(&container).await;
+
+    let unit = services::unit::insert(
+        &StoreUnitSchema {
+            name: "Unit 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let category = services::category::insert(
+        &StoreCategorySchema {
+            name: "Category 1".to_string(),
+        },
+        &mut config.connection,
+    )
+    .await
+    .unwrap();
+
+    let input = StoreProductSchema {
+        name: "Product 1".to_string(),
+        slug: None,
+        description: None,
+        unit_id: unit.id,
+        category_id: category.id,
+        variants: vec![Variant {
+            options: vec![VariantOption {
+                key: "Size".to_string(),
+                value: "M".to_string(),
+            }],
+            price: 10.0,
+            sku: None,
+        }],
+        images: vec![],
+    };
+
+    let product = services::product::insert(&input, &mut config.connection)
+        .await
+        .unwrap();
+
+    product
+        .attach_variants(&input.variants, &mut config.connection)
+        .await
+        .unwrap();
+
+    let variant_id = sqlx::query_scalar!("SELECT id FROM product_variants")
+        .fetch_one(&mut *config.connection)
+        .await
+        .unwrap();
+
+    let (user, session) = auth(&config.member, &mut config.connection).await;
+    let session = format!("session={}", session.session);
+
+    let add = |cookie: Option<&str>, body: serde_json::Value| {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/wishlists")
+            .header("Content-Type", "application/json");
+
+        let request = match cookie {
+            Some(cookie) => request.header("Cookie", cookie),
+            None => request,
+        };
+
+        request.body(Body::from(body.to_string())).unwrap()
+    };
+
+    let list = |cookie: String| {
+        Request::builder()
+            .method("GET")
+            .uri("/wishlists")
+            .header("Cookie", cookie)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let guest_cookie = |response: &axum::response::Response| {
+        response.headers()["set-cookie"]
+            .to_str()
+            .unwrap()
+            .split(';')
+            .next()
+            .unwrap()
+            .to_string()
+    };
+
+    // a user without a wishlist takes the guest one over
+    let response = config
+        .app
+        .clone()
+        .oneshot(add(None, serde_json::json!({ "product_id": product.id })))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 201);
+
+    let guest = guest_cookie(&response);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(list(format!("{session}; {guest}")))
+        .await
+        .unwrap();
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+    assert_eq!(body.as_array().unwrap().len(), 1);
+
+    let owners = sqlx::query_scalar!("SELECT user_id FROM wishlists")
+        .fetch_all(&mut *config.connection)
+        .await
+        .unwrap();
+    assert_eq!(owners, vec![Some(user.user.id)]);
+
+    // one who has a wishlist gets the guest items added to it, without duplicates
+    let response = config
+        .app
+        .clone()
+        .oneshot(add(None, serde_json::json!({ "product_id": product.id })))
+        .await
+        .unwrap();
+
+    let guest = guest_cookie(&response);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(add(
+            Some(&guest),
+            serde_json::json!({ "variant_id": variant_id }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 201);
+
+    let response = config
+        .app
+        .clone()
+        .oneshot(list(format!("{session}; {guest}")))
+        .await
+        .unwrap();
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+    assert_eq!(body.as_array().unwrap().len(), 2);
+
+    let owners = sqlx::query_scalar!("SELECT user_id FROM wishlists")
+        .fetch_all(&mut *config.connection)
+        .await
+        .unwrap();
+    assert_eq!(owners, vec![Some(user.user.id)]);
+
+    // the guest cookie is left with nothing to reach
+    let response = config.app.clone().oneshot(list(guest)).await.unwrap();
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes[..]).unwrap();
+    assert_eq!(body.as_array().unwrap().len(), 0);
+}
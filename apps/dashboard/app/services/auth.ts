@@ -3,16 +3,74 @@ import { redirect } from "@remix-run/react";
 import { env } from "@/env";
 import { z } from "zod";
 
-export const api = (
+let csrfToken: Promise<string> | undefined;
+
+const fetchCsrfToken = async (cookie?: string) => {
+  const url = new URL(env.VITE_API_URL);
+  url.pathname = "/csrf";
+
+  const response = await fetch(url, {
+    headers: cookie ? { cookie } : undefined,
+    credentials: "include",
+  });
+
+  if (!response.ok) {
+    throw new Error(response.statusText);
+  }
+
+  return z.string().parse(await response.json());
+};
+
+// the API wants the csrf cookie echoed back in a header on anything that isn't a read
+export const withCsrf = async (headers: Headers) => {
+  const cookie = headers.get("cookie");
+
+  // loaders and actions forward the browser's cookies, the token has to match those
+  if (cookie) {
+    const token = cookie
+      .split(";")
+      .map((part) => part.trim())
+      .find((part) => part.startsWith("csrf="))
+      ?.slice("csrf=".length);
+
+    if (token) {
+      headers.set("x-csrf-token", token);
+      return headers;
+    }
+
+    const fresh = await fetchCsrfToken();
+    headers.set("cookie", `${cookie}; csrf=${fresh}`);
+    headers.set("x-csrf-token", fresh);
+    return headers;
+  }
+
+  // the browser keeps the cookie, so one token is enough for the whole session
+  if (!csrfToken) {
+    csrfToken = fetchCsrfToken().catch((err) => {
+      csrfToken = undefined;
+      throw err;
+    });
+  }
+
+  headers.set("x-csrf-token", await csrfToken);
+  return headers;
+};
+
+export const api = async (
   input: string | URL | globalThis.Request,
   init?: RequestInit
 ): Promise<Response> => {
+  const headers = new Headers(init?.headers);
+  headers.set("Content-Type", "application/json");
+
+  const method = init?.method?.toUpperCase() ?? "GET";
+  if (method !== "GET" && method !== "HEAD") {
+    await withCsrf(headers);
+  }
+
   return fetch(input, {
     ...init,
-    headers: {
-      ...init?.headers,
-      "Content-Type": "application/json",
-    },
+    headers,
     credentials: "include",
   });
 };
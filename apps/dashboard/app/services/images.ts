@@ -1,5 +1,13 @@
+import { z } from "zod";
 import { env } from "@/env";
 import { imageSchema } from "@/validations/image";
+import { withCsrf } from "./auth";
+
+const uploadResultSchema = z.object({
+  name: z.string(),
+  image: imageSchema.nullable(),
+  error: z.string().nullable(),
+});
 
 class Image {
   public async upload(file: File) {
@@ -7,16 +15,23 @@ class Image {
     url.pathname = "/images";
 
     const formData = new FormData();
-    formData.append(file.name, file);
+    formData.append("file", file);
 
     const response = await fetch(url, {
+      headers: await withCsrf(new Headers()),
       credentials: "include",
       method: "POST",
       body: formData,
     });
 
     const data = await response.json();
-    return imageSchema.parse(data);
+    const [result] = z.array(uploadResultSchema).parse(data);
+
+    if (!result.image) {
+      throw new Error(result.error ?? "Couldn't upload image");
+    }
+
+    return result.image;
   }
 }
 